@@ -2,6 +2,45 @@
 // SPDX-License-Identifier: Apache-2.0 or MIT
 
 //! Merge strategies for boolean types.
+//!
+//! A plain `bool` cannot tell whether a layer explicitly disabled an option or did not mention it
+//! at all, so [`overwrite_false`][] cannot be overridden by an explicit `false` from a higher
+//! layer.  [`TriState`][] stores whether the value was set, and is resolved to a `bool` with a
+//! default value after merging.
+//!
+//! # Example
+//!
+//! ```
+//! use conflate::bool::TriState;
+//! use conflate::Merge;
+//!
+//! #[derive(Merge)]
+//! struct Config {
+//!     color: TriState,
+//!     verbose: TriState,
+//! }
+//!
+//! // the command line explicitly disables colors
+//! let mut config = Config {
+//!     color: TriState::Off,
+//!     verbose: TriState::Unset,
+//! };
+//! config.merge(Config {
+//!     color: TriState::On,
+//!     verbose: TriState::On,
+//! });
+//!
+//! assert!(!config.color.resolve(true));
+//! assert!(config.verbose.resolve(false));
+//! ```
+//!
+//! [`overwrite_false`]: fn.overwrite_false.html
+//! [`TriState`]: enum.TriState.html
+
+use core::fmt;
+use core::str::FromStr;
+
+use crate::{FieldPath, Merge, MergeChanged, MergeMeta, MergeRef};
 
 /// Overwrite left with right if the value of left is false.
 pub fn overwrite_false(left: &mut bool, right: bool) {
@@ -16,3 +55,539 @@ pub fn overwrite_true(left: &mut bool, right: bool) {
         *left = right;
     }
 }
+
+/// Overwrite left with right if the value of left is false and return whether left was changed.
+pub fn overwrite_false_changed(left: &mut bool, right: bool) -> bool {
+    let changed = !*left && right;
+    overwrite_false(left, right);
+    changed
+}
+
+/// Overwrite left with right if the value of left is true and return whether left was changed.
+pub fn overwrite_true_changed(left: &mut bool, right: bool) -> bool {
+    let changed = *left && !right;
+    overwrite_true(left, right);
+    changed
+}
+
+/// Overwrite left with right.
+///
+/// Note that right always wins, even if it was not set explicitly.  Use [`TriState`][] to
+/// distinguish values that were not set from values that were explicitly disabled.
+///
+/// [`TriState`]: enum.TriState.html
+pub fn overwrite(left: &mut bool, right: bool) {
+    *left = right;
+}
+
+/// Overwrite left with right and return whether left was changed.
+pub fn overwrite_changed(left: &mut bool, right: bool) -> bool {
+    let changed = *left != right;
+    overwrite(left, right);
+    changed
+}
+
+/// Overwrite left with right if left is not set.
+///
+/// This is the strategy used by the `Merge` implementation of [`TriState`][].
+///
+/// [`TriState`]: enum.TriState.html
+pub fn overwrite_unset(left: &mut TriState, right: TriState) {
+    if left.is_unset() {
+        *left = right;
+    }
+}
+
+/// Overwrite left with right if right is set.
+pub fn overwrite_with_set(left: &mut TriState, right: TriState) {
+    if right.is_set() {
+        *left = right;
+    }
+}
+
+/// Overwrite left with right if left is not set.
+pub fn overwrite_unset_ref(left: &mut TriState, right: &TriState) {
+    overwrite_unset(left, *right);
+}
+
+/// Overwrite left with right if right is set.
+pub fn overwrite_with_set_ref(left: &mut TriState, right: &TriState) {
+    overwrite_with_set(left, *right);
+}
+
+/// Overwrite left with right if left is not set and return whether left was changed.
+pub fn overwrite_unset_changed(left: &mut TriState, right: TriState) -> bool {
+    let changed = left.is_unset() && right.is_set();
+    overwrite_unset(left, right);
+    changed
+}
+
+/// Overwrite left with right if right is set and return whether left was changed.
+pub fn overwrite_with_set_changed(left: &mut TriState, right: TriState) -> bool {
+    let changed = right.is_set() && *left != right;
+    overwrite_with_set(left, right);
+    changed
+}
+
+/// A boolean value that can be explicitly enabled, explicitly disabled or not set.
+///
+/// The `Merge` implementation keeps the value of `self` if it is set, so an explicit value of a
+/// layer with a higher priority always wins, see [`overwrite_unset`][].  After merging, the value
+/// is converted to a `bool` using [`resolve`][].
+///
+/// `TriState` is converted from and to `Option<bool>`, where `None` is `Unset`.  If the `serde`
+/// feature is enabled, it is serialized and deserialized like `Option<bool>`.  It can be parsed
+/// from the strings `on`, `true`, `yes`, `off`, `false`, `no` and `unset`, e.g. as the value of a
+/// command-line option.
+///
+/// # Command-line flags
+///
+/// If the `clap` feature is enabled, a pair of `--flag` and `--no-flag` options can be added to a
+/// `clap` parser by flattening a [`TriStateArgs`][] field.  Otherwise, the values of the options
+/// can be converted using [`from_flags`][].
+///
+/// [`overwrite_unset`]: fn.overwrite_unset.html
+/// [`resolve`]: #method.resolve
+/// [`from_flags`]: #method.from_flags
+/// [`TriStateArgs`]: struct.TriStateArgs.html
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TriState {
+    /// The value is not set.
+    #[default]
+    Unset,
+    /// The value is explicitly enabled.
+    On,
+    /// The value is explicitly disabled.
+    Off,
+}
+
+impl TriState {
+    /// Creates a value from a pair of `--flag` and `--no-flag` command-line flags.
+    ///
+    /// If both flags are set, `flag` wins.  If none of them is set, the value is `Unset`.
+    pub const fn from_flags(flag: bool, no_flag: bool) -> Self {
+        if flag {
+            Self::On
+        } else if no_flag {
+            Self::Off
+        } else {
+            Self::Unset
+        }
+    }
+
+    /// Returns whether the value is set.
+    pub const fn is_set(self) -> bool {
+        !self.is_unset()
+    }
+
+    /// Returns whether the value is not set.
+    pub const fn is_unset(self) -> bool {
+        matches!(self, Self::Unset)
+    }
+
+    /// Returns the value as a `bool`, or `None` if it is not set.
+    pub const fn get(self) -> Option<bool> {
+        match self {
+            Self::Unset => None,
+            Self::On => Some(true),
+            Self::Off => Some(false),
+        }
+    }
+
+    /// Returns the value as a `bool`, or `default` if it is not set.
+    pub const fn resolve(self, default: bool) -> bool {
+        match self.get() {
+            Some(value) => value,
+            None => default,
+        }
+    }
+
+    /// Returns the name of the value as used by the `FromStr` and `Display` implementations.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Unset => "unset",
+            Self::On => "on",
+            Self::Off => "off",
+        }
+    }
+}
+
+impl From<bool> for TriState {
+    fn from(value: bool) -> Self {
+        if value {
+            Self::On
+        } else {
+            Self::Off
+        }
+    }
+}
+
+impl From<Option<bool>> for TriState {
+    fn from(value: Option<bool>) -> Self {
+        value.map_or(Self::Unset, Self::from)
+    }
+}
+
+impl From<TriState> for Option<bool> {
+    fn from(value: TriState) -> Self {
+        value.get()
+    }
+}
+
+impl fmt::Display for TriState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TriState {
+    type Err = ParseTriStateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "unset" => Ok(Self::Unset),
+            "on" | "true" | "yes" => Ok(Self::On),
+            "off" | "false" | "no" => Ok(Self::Off),
+            _ => Err(ParseTriStateError),
+        }
+    }
+}
+
+impl Merge for TriState {
+    fn merge(&mut self, other: Self) {
+        overwrite_unset(self, other);
+    }
+}
+
+impl MergeRef for TriState {
+    fn merge_ref(&mut self, other: &Self) {
+        overwrite_unset_ref(self, other);
+    }
+}
+
+impl MergeChanged for TriState {
+    fn merge_changed_with(
+        &mut self,
+        other: Self,
+        path: &FieldPath<'_>,
+        changed: &mut dyn FnMut(&FieldPath<'_>),
+    ) {
+        if overwrite_unset_changed(self, other) {
+            changed(path);
+        }
+    }
+}
+
+impl MergeMeta for TriState {}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for TriState {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serde::Serialize::serialize(&self.get(), serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for TriState {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <Option<bool> as serde::Deserialize<'de>>::deserialize(deserializer).map(Self::from)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl crate::DeserializeMerge for TriState {
+    /// Deserializes an `Option<bool>` and merges it into this value using
+    /// [`overwrite_with_set`][], so a layer only changes the value if it sets it.
+    ///
+    /// [`overwrite_with_set`]: fn.overwrite_with_set.html
+    fn deserialize_merge<'de, D: serde::Deserializer<'de>>(
+        &mut self,
+        deserializer: D,
+    ) -> Result<(), D::Error> {
+        let other = <Option<bool> as serde::Deserialize<'de>>::deserialize(deserializer)?;
+        overwrite_with_set(self, other.into());
+        Ok(())
+    }
+
+    fn merge_field<'de, A: serde::de::MapAccess<'de>>(
+        &mut self,
+        _key: &str,
+        _map: &mut A,
+    ) -> Result<bool, A::Error> {
+        Ok(false)
+    }
+}
+
+/// The error returned when parsing an unknown [`TriState`][] value.
+///
+/// [`TriState`]: enum.TriState.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseTriStateError;
+
+impl fmt::Display for ParseTriStateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("expected \"on\", \"off\" or \"unset\"")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseTriStateError {}
+
+/// The names of a pair of `--flag` and `--no-flag` command-line options for [`TriStateArgs`][].
+///
+/// This trait is only available if the `clap` feature is enabled.
+///
+/// [`TriStateArgs`]: struct.TriStateArgs.html
+#[cfg(feature = "clap")]
+pub trait Flag {
+    /// The long name of the option that enables the value, e.g. `color` for `--color`.
+    ///
+    /// It is also used as the ID of the argument.
+    const LONG: &'static str;
+    /// The long name of the option that disables the value, e.g. `no-color` for `--no-color`.
+    ///
+    /// It is also used as the ID of the argument.
+    const NO_LONG: &'static str;
+    /// The short name of the option that enables the value, if any.
+    const SHORT: Option<char> = None;
+    /// The help text of the option that enables the value, if any.
+    const HELP: Option<&'static str> = None;
+}
+
+/// A [`TriState`][] value that is set by a pair of `--flag` and `--no-flag` command-line options.
+///
+/// `TriStateArgs` implements `clap::Args`, so it can be flattened into a `clap` parser.  The names
+/// of the options are given by the [`Flag`][] implementation of `F`.  The options override each
+/// other, so the last one wins, and the value is `Unset` if none of them is given.
+///
+/// The value dereferences to a `TriState` and is merged, serialized and deserialized like a
+/// `TriState`, so the same struct can also be merged with other layers.
+///
+/// This type is only available if the `clap` feature is enabled.
+///
+/// # Example
+///
+/// ```
+/// use clap::Parser;
+/// use conflate::bool::{Flag, TriState, TriStateArgs};
+///
+/// struct Color;
+///
+/// impl Flag for Color {
+///     const LONG: &'static str = "color";
+///     const NO_LONG: &'static str = "no-color";
+/// }
+///
+/// #[derive(Parser)]
+/// struct Args {
+///     #[command(flatten)]
+///     color: TriStateArgs<Color>,
+/// }
+///
+/// let args = Args::parse_from(["app", "--color", "--no-color"]);
+/// assert_eq!(TriState::Off, *args.color);
+///
+/// let args = Args::parse_from(["app"]);
+/// assert!(args.color.resolve(true));
+/// ```
+///
+/// [`TriState`]: enum.TriState.html
+/// [`Flag`]: trait.Flag.html
+#[cfg(feature = "clap")]
+pub struct TriStateArgs<F> {
+    value: TriState,
+    flag: core::marker::PhantomData<fn() -> F>,
+}
+
+#[cfg(feature = "clap")]
+impl<F> TriStateArgs<F> {
+    /// Creates a value from a `TriState`.
+    pub const fn new(value: TriState) -> Self {
+        Self {
+            value,
+            flag: core::marker::PhantomData,
+        }
+    }
+
+    /// Returns the `TriState` value.
+    pub const fn get(&self) -> TriState {
+        self.value
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<F> Clone for TriStateArgs<F> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<F> Copy for TriStateArgs<F> {}
+
+#[cfg(feature = "clap")]
+impl<F> fmt::Debug for TriStateArgs<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.value, f)
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<F> Default for TriStateArgs<F> {
+    fn default() -> Self {
+        Self::new(TriState::Unset)
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<F> PartialEq for TriStateArgs<F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<F> Eq for TriStateArgs<F> {}
+
+#[cfg(feature = "clap")]
+impl<F> core::ops::Deref for TriStateArgs<F> {
+    type Target = TriState;
+
+    fn deref(&self) -> &TriState {
+        &self.value
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<F> From<TriState> for TriStateArgs<F> {
+    fn from(value: TriState) -> Self {
+        Self::new(value)
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<F> From<TriStateArgs<F>> for TriState {
+    fn from(value: TriStateArgs<F>) -> Self {
+        value.value
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<F: Flag> clap::FromArgMatches for TriStateArgs<F> {
+    fn from_arg_matches(matches: &clap::ArgMatches) -> Result<Self, clap::Error> {
+        let mut value = Self::default();
+        value.update_from_arg_matches(matches)?;
+        Ok(value)
+    }
+
+    fn update_from_arg_matches(&mut self, matches: &clap::ArgMatches) -> Result<(), clap::Error> {
+        let value = TriState::from_flags(matches.get_flag(F::LONG), matches.get_flag(F::NO_LONG));
+        if value.is_set() {
+            self.value = value;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<F: Flag> clap::Args for TriStateArgs<F> {
+    fn augment_args(cmd: clap::Command) -> clap::Command {
+        let mut flag = clap::Arg::new(F::LONG)
+            .long(F::LONG)
+            .action(clap::ArgAction::SetTrue)
+            .overrides_with(F::NO_LONG);
+        if let Some(short) = F::SHORT {
+            flag = flag.short(short);
+        }
+        if let Some(help) = F::HELP {
+            flag = flag.help(help);
+        }
+        let no_flag = clap::Arg::new(F::NO_LONG)
+            .long(F::NO_LONG)
+            .action(clap::ArgAction::SetTrue)
+            .overrides_with(F::LONG);
+        cmd.arg(flag).arg(no_flag)
+    }
+
+    fn augment_args_for_update(cmd: clap::Command) -> clap::Command {
+        Self::augment_args(cmd)
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<F> Merge for TriStateArgs<F> {
+    fn merge(&mut self, other: Self) {
+        self.value.merge(other.value);
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<F> MergeRef for TriStateArgs<F> {
+    fn merge_ref(&mut self, other: &Self) {
+        self.value.merge_ref(&other.value);
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<F> MergeChanged for TriStateArgs<F> {
+    fn merge_changed_with(
+        &mut self,
+        other: Self,
+        path: &FieldPath<'_>,
+        changed: &mut dyn FnMut(&FieldPath<'_>),
+    ) {
+        self.value.merge_changed_with(other.value, path, changed);
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<F> MergeMeta for TriStateArgs<F> {}
+
+#[cfg(feature = "clap")]
+impl<F> crate::MergeVisit for TriStateArgs<F> {
+    /// Visits the wrapped [`TriState`][] as a leaf.
+    ///
+    /// [`TriState`]: enum.TriState.html
+    fn visit_with(&self, path: &FieldPath<'_>, visitor: &mut dyn crate::visit::Visitor) {
+        visitor.visit(path, &self.value);
+    }
+
+    fn visit_mut_with(&mut self, path: &FieldPath<'_>, visitor: &mut dyn crate::visit::VisitorMut) {
+        visitor.visit_mut(path, &mut self.value);
+    }
+}
+
+#[cfg(all(feature = "clap", feature = "serde"))]
+impl<F> serde::Serialize for TriStateArgs<F> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize(serializer)
+    }
+}
+
+#[cfg(all(feature = "clap", feature = "serde"))]
+impl<'de, F> serde::Deserialize<'de> for TriStateArgs<F> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        TriState::deserialize(deserializer).map(Self::new)
+    }
+}
+
+#[cfg(all(feature = "clap", feature = "serde"))]
+impl<F> crate::DeserializeMerge for TriStateArgs<F> {
+    /// Deserializes a `TriState` value and merges it into this value.
+    fn deserialize_merge<'de, D: serde::Deserializer<'de>>(
+        &mut self,
+        deserializer: D,
+    ) -> Result<(), D::Error> {
+        let other = <TriState as serde::Deserialize<'de>>::deserialize(deserializer)?;
+        self.value.merge(other);
+        Ok(())
+    }
+
+    fn merge_field<'de, A: serde::de::MapAccess<'de>>(
+        &mut self,
+        _key: &str,
+        _map: &mut A,
+    ) -> Result<bool, A::Error> {
+        Ok(false)
+    }
+}
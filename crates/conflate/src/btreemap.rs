@@ -36,3 +36,158 @@ pub fn append_or_recurse<K: Eq + Ord, V: crate::Merge>(
         }
     }
 }
+
+/// Append values, on conflict, overwrite elements of `left` with `right`.
+///
+/// Returns whether `left` was changed, i.e. whether a key was added or a value was replaced with
+/// a different value.
+pub fn append_or_overwrite_changed<K: Eq + Ord, V: PartialEq>(
+    left: &mut BTreeMap<K, V>,
+    right: BTreeMap<K, V>,
+) -> bool {
+    use std::collections::btree_map::Entry;
+
+    let mut changed = false;
+    for (k, v) in right {
+        match left.entry(k) {
+            Entry::Occupied(mut existing) => {
+                if *existing.get() != v {
+                    let _ = existing.insert(v);
+                    changed = true;
+                }
+            }
+            Entry::Vacant(empty) => {
+                let _ = empty.insert(v);
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+/// Append values, on conflict, ignore elements from `right`.
+///
+/// Returns whether `left` was changed, i.e. whether a key was added.
+pub fn append_or_ignore_changed<K: Eq + Ord, V>(
+    left: &mut BTreeMap<K, V>,
+    right: BTreeMap<K, V>,
+) -> bool {
+    let len = left.len();
+    append_or_ignore(left, right);
+    left.len() != len
+}
+
+/// Append values, on conflict, recursively merge the elements.
+///
+/// Returns whether `left` was changed, i.e. whether a key was added or a value was changed.
+pub fn append_or_recurse_changed<K: Eq + Ord, V: crate::MergeChanged>(
+    left: &mut BTreeMap<K, V>,
+    right: BTreeMap<K, V>,
+) -> bool {
+    use std::collections::btree_map::Entry;
+
+    let mut changed = false;
+    for (k, v) in right {
+        match left.entry(k) {
+            Entry::Occupied(mut existing) => changed |= existing.get_mut().merge_changed(v),
+            Entry::Vacant(empty) => {
+                let _ = empty.insert(v);
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+/// Returns a strategy that appends the values of `right` and then only retains the keys that
+/// match the predicate.  On conflict, elements of `left` are overwritten with `right`.
+///
+/// The predicate is applied to both maps, so keys of `left` that do not match it are removed too.
+pub fn retain_keys<K: Eq + Ord, V, P: Fn(&K) -> bool>(
+    predicate: P,
+) -> impl Fn(&mut BTreeMap<K, V>, BTreeMap<K, V>) {
+    move |left, right| {
+        left.retain(|k, _| predicate(k));
+        left.extend(right.into_iter().filter(|(k, _)| predicate(k)));
+    }
+}
+
+/// Append values, on conflict, overwrite elements of `left` with `right`, but remove the keys
+/// that are marked with `None` in `right`.
+///
+/// This allows a layer to delete entries that were added by a lower layer.
+pub fn remove_keys_marked<K: Eq + Ord, V>(
+    left: &mut BTreeMap<K, Option<V>>,
+    right: BTreeMap<K, Option<V>>,
+) {
+    for (k, v) in right {
+        if v.is_some() {
+            let _ = left.insert(k, v);
+        } else {
+            let _ = left.remove(&k);
+        }
+    }
+}
+
+/// Returns a strategy that merges the values of keys that exist in both maps using the given
+/// strategy and ignores the keys of `right` that do not exist in `left`.
+///
+/// This strategy only updates the existing keys of `left`, it never inserts keys.  Use
+/// [`append_or_recurse_with`][] to insert the other keys of `right` as well.
+///
+/// [`append_or_recurse_with`]: fn.append_or_recurse_with.html
+pub fn values_with<K: Eq + Ord, V, F: Fn(&mut V, V)>(
+    strategy: F,
+) -> impl Fn(&mut BTreeMap<K, V>, BTreeMap<K, V>) {
+    move |left, right| {
+        for (k, v) in right {
+            if let Some(existing) = left.get_mut(&k) {
+                strategy(existing, v);
+            }
+        }
+    }
+}
+
+/// Returns a strategy that appends values and, on conflict, merges the elements using the given
+/// strategy.
+///
+/// This is the same as [`append_or_recurse`][] with a custom strategy for the values.
+///
+/// [`append_or_recurse`]: fn.append_or_recurse.html
+pub fn append_or_recurse_with<K: Eq + Ord, V, F: Fn(&mut V, V)>(
+    strategy: F,
+) -> impl Fn(&mut BTreeMap<K, V>, BTreeMap<K, V>) {
+    use std::collections::btree_map::Entry;
+
+    move |left, right| {
+        for (k, v) in right {
+            match left.entry(k) {
+                Entry::Occupied(mut existing) => strategy(existing.get_mut(), v),
+                Entry::Vacant(empty) => {
+                    let _ = empty.insert(v);
+                }
+            }
+        }
+    }
+}
+
+/// Append values, on conflict, overwrite elements of `left` with `right`, but remove the keys
+/// that are marked with `None` in `right`.
+///
+/// Returns whether `left` was changed, i.e. whether a key was added or removed or a value was
+/// replaced with a different value.
+pub fn remove_keys_marked_changed<K: Eq + Ord, V: PartialEq>(
+    left: &mut BTreeMap<K, Option<V>>,
+    right: BTreeMap<K, Option<V>>,
+) -> bool {
+    let mut changed = false;
+    for (k, v) in right {
+        if v.is_some() {
+            changed |= left.get(&k) != Some(&v);
+            let _ = left.insert(k, v);
+        } else {
+            changed |= left.remove(&k).is_some();
+        }
+    }
+    changed
+}
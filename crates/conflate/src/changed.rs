@@ -0,0 +1,113 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Report whether a merge changed a value.
+//!
+//! [`Merge::merge`][] does not tell whether merging another value had any effect, e.g. whether a
+//! newly loaded configuration layer changed the effective configuration.  The
+//! [`MergeChanged`][] trait merges like [`Merge`][], but reports the paths of the fields that
+//! were changed.
+//!
+//! The strategy modules provide change-reporting versions of their strategies with a `_changed`
+//! suffix, for example [`option::overwrite_none_changed`][].  They have the signature
+//! `fn merge<T>(left: &mut T, right: T) -> bool` and return whether `left` was changed.
+//!
+//! # Example
+//!
+//! ```
+//! use conflate::MergeChanged;
+//!
+//! #[derive(MergeChanged)]
+//! struct Repository {
+//!     #[merge(strategy_changed = conflate::option::overwrite_none_changed)]
+//!     path: Option<String>,
+//! }
+//!
+//! #[derive(MergeChanged)]
+//! struct Config {
+//!     #[merge(strategy = conflate::ord::max)]
+//!     verbosity: u8,
+//!
+//!     repository: Repository,
+//! }
+//!
+//! let mut config = Config {
+//!     verbosity: 1,
+//!     repository: Repository { path: None },
+//! };
+//!
+//! assert!(!config.merge_changed(Config {
+//!     verbosity: 0,
+//!     repository: Repository { path: None },
+//! }));
+//!
+//! let changed = config.merge_changed_paths(Config {
+//!     verbosity: 0,
+//!     repository: Repository { path: Some("/srv/backup".to_owned()) },
+//! });
+//! assert_eq!(vec!["repository.path"], changed);
+//! ```
+//!
+//! [`Merge`]: ../trait.Merge.html
+//! [`Merge::merge`]: ../trait.Merge.html#tymethod.merge
+//! [`MergeChanged`]: trait.MergeChanged.html
+//! [`option::overwrite_none_changed`]: ../option/fn.overwrite_none_changed.html
+
+use crate::FieldPath;
+
+/// A trait for objects that can be merged while reporting the changed fields.
+///
+/// # Deriving
+///
+/// `MergeChanged` can be derived for structs if the `derive` feature is enabled.  The derive
+/// macro uses the same `merge` attributes as the `Merge` derive macro, with one addition:
+/// - `strategy_changed = f`: Call `f(&mut self.field, other.field)` for this field, which
+///   returns whether the field was changed.
+///
+/// Fields with a `strategy` but without a `strategy_changed` attribute are cloned before merging
+/// them using the strategy and compared to the merged value, so their type has to implement
+/// `Clone` and `PartialEq`.  Fields without a strategy are merged by calling
+/// `MergeChanged::merge_changed_with`.  As with `strategy`, a default `strategy_changed` can be
+/// set for all fields on the struct.
+///
+/// The field paths use the keys determined from the `serde` attributes of the struct, see
+/// [`FieldPath`][].
+///
+/// [`FieldPath`]: ../path/enum.FieldPath.html
+pub trait MergeChanged {
+    /// Merge another object into this object, calling `changed` with the path of each changed
+    /// field.
+    ///
+    /// `path` is the path of this object, which is prepended to the paths of its fields.
+    fn merge_changed_with(
+        &mut self,
+        other: Self,
+        path: &FieldPath<'_>,
+        changed: &mut dyn FnMut(&FieldPath<'_>),
+    );
+
+    /// Merge another object into this object and return whether any field was changed.
+    fn merge_changed(&mut self, other: Self) -> bool
+    where
+        Self: Sized,
+    {
+        let mut any = false;
+        self.merge_changed_with(other, &FieldPath::Root, &mut |_| any = true);
+        any
+    }
+
+    /// Merge another object into this object and return the paths of the changed fields.
+    ///
+    /// This method is only available if the `std` feature is enabled.
+    #[cfg(feature = "std")]
+    fn merge_changed_paths(&mut self, other: Self) -> Vec<String>
+    where
+        Self: Sized,
+    {
+        let mut paths = Vec::new();
+        self.merge_changed_with(other, &FieldPath::Root, &mut |path| {
+            paths.push(path.to_string());
+        });
+        paths
+    }
+}
@@ -0,0 +1,390 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Collect warnings about values that are ignored while merging.
+//!
+//! If a value from one layer is silently dropped because another layer already set the field, it
+//! can be hard to understand where the effective configuration comes from.  A [`MergeContext`][]
+//! remembers which source set each field and collects a [`Warning`][] for every value that the
+//! merge strategy of its field ignored or that was set using a deprecated field.  It is passed
+//! through the merge by the [`MergeWithContext`][] trait and works with the strategies of all
+//! strategy modules, because it only compares the values before and after the strategy is
+//! applied.
+//!
+//! [`Layers::load_with_ctx`][] merges all layers of a `layers::Layers` list using a context whose
+//! source is set to the name of each layer and returns the collected warnings.
+//!
+//! These items are only available if the `std` feature is enabled.
+//!
+//! # Example
+//!
+//! ```
+//! use conflate::{MergeContext, MergeWithContext};
+//!
+//! #[derive(Debug, Default, MergeWithContext)]
+//! #[merge(strategy = conflate::option::overwrite_none)]
+//! struct Config {
+//!     output: Option<String>,
+//!     format: Option<String>,
+//! }
+//!
+//! let mut ctx = MergeContext::new();
+//! let mut config = Config::default();
+//!
+//! ctx.set_source("CLI");
+//! config.merge_with_ctx(
+//!     Config {
+//!         output: Some("out.json".to_owned()),
+//!         format: None,
+//!     },
+//!     &mut ctx,
+//! );
+//!
+//! ctx.set_source("config.toml");
+//! config.merge_with_ctx(
+//!     Config {
+//!         output: Some("out.toml".to_owned()),
+//!         format: Some("toml".to_owned()),
+//!     },
+//!     &mut ctx,
+//! );
+//!
+//! assert_eq!(Some("out.json"), config.output.as_deref());
+//! assert_eq!(Some("toml"), config.format.as_deref());
+//! assert_eq!(
+//!     "`output` from config.toml ignored: already set by CLI",
+//!     ctx.warnings()[0].to_string(),
+//! );
+//! ```
+//!
+//! [`MergeContext`]: struct.MergeContext.html
+//! [`Warning`]: struct.Warning.html
+//! [`MergeWithContext`]: trait.MergeWithContext.html
+//! [`Layers::load_with_ctx`]: ../layers/struct.Layers.html#method.load_with_ctx
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+
+#[cfg(feature = "indexmap")]
+use indexmap::IndexMap;
+
+use crate::{FieldPath, Locked};
+
+/// The state of a sequence of merges that collects warnings about ignored values.
+///
+/// Set the name of the source of the merged value with [`set_source`][] before every merge.  The
+/// context records the source of every field that a merge changed and creates a [`Warning`][] for
+/// every non-default value that a merge ignored.
+///
+/// [`set_source`]: #method.set_source
+/// [`Warning`]: struct.Warning.html
+#[derive(Clone, Debug, Default)]
+pub struct MergeContext {
+    source: String,
+    sources: BTreeMap<String, String>,
+    warnings: Vec<Warning>,
+}
+
+impl MergeContext {
+    /// Creates an empty context.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the name of the source of the values that are merged next, e.g. the path of a
+    /// configuration file.
+    pub fn set_source(&mut self, source: impl Into<String>) {
+        self.source = source.into();
+    }
+
+    /// Returns the name of the source of the values that are merged next.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the name of the source that last changed the field with the given path, if any.
+    pub fn source_of(&self, path: &str) -> Option<&str> {
+        self.sources.get(path).map(String::as_str)
+    }
+
+    /// Returns the warnings collected so far.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// Removes and returns the warnings collected so far.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Merge right into left using the given strategy and record the result for the field with
+    /// the given path.
+    ///
+    /// If the strategy changed left, the current source is recorded as the source of the field.
+    /// Otherwise, a warning is created if right is neither the default value nor equal to left,
+    /// i.e. if the strategy ignored a value that was set in the current source.
+    pub fn merge_field<T, F>(&mut self, path: &FieldPath<'_>, left: &mut T, right: T, strategy: F)
+    where
+        T: Clone + Default + PartialEq,
+        F: FnOnce(&mut T, T),
+    {
+        let previous = left.clone();
+        let ignored = right != T::default() && right != previous;
+        strategy(left, right);
+
+        let path = path.to_string();
+        if *left != previous {
+            self.sources.insert(path, self.source.clone());
+        } else if ignored {
+            let set_by = self.sources.get(&path).cloned();
+            self.warnings.push(Warning {
+                path,
+                source: self.source.clone(),
+                kind: WarningKind::Ignored { set_by },
+            });
+        }
+    }
+
+    /// Merge right into left using the given strategy and record the result for every entry of
+    /// the map with the given path.
+    ///
+    /// This works like [`merge_field`][], but ignored values are reported per key: a warning is
+    /// created for every entry of right whose value differs from the value of left if the strategy
+    /// did not change that entry of left, e.g. for the conflicting keys that
+    /// `hashmap::append_or_ignore` drops while appending the other keys.  The current source is
+    /// recorded for the field and for every changed entry.
+    ///
+    /// [`merge_field`]: #method.merge_field
+    pub fn merge_entries<T, F>(&mut self, path: &FieldPath<'_>, left: &mut T, right: T, strategy: F)
+    where
+        T: Clone + PartialEq + Entries,
+        F: FnOnce(&mut T, T),
+    {
+        let previous = left.clone();
+        let entries = right.clone();
+        strategy(left, right);
+
+        let field_path = path.to_string();
+        if *left != previous {
+            self.sources.insert(field_path.clone(), self.source.clone());
+        }
+        entries.for_each_entry(&mut |key, value| {
+            let current = left.entry(key);
+            let changed = current != previous.entry(key);
+            let key = key.to_string();
+            let path = path.join(&key).to_string();
+            if changed {
+                self.sources.insert(path, self.source.clone());
+            } else if current != Some(value) {
+                let set_by = self
+                    .sources
+                    .get(&path)
+                    .or_else(|| self.sources.get(&field_path))
+                    .cloned();
+                self.warnings.push(Warning {
+                    path,
+                    source: self.source.clone(),
+                    kind: WarningKind::Ignored { set_by },
+                });
+            }
+        });
+    }
+
+    /// Records a warning that the current source set the deprecated field with the given path
+    /// instead of its replacement.
+    pub fn deprecated(&mut self, path: &FieldPath<'_>, replacement: &FieldPath<'_>) {
+        self.warnings.push(Warning {
+            path: path.to_string(),
+            source: self.source.clone(),
+            kind: WarningKind::Deprecated {
+                replacement: replacement.to_string(),
+            },
+        });
+    }
+}
+
+/// A map whose entries are recorded separately by [`MergeContext::merge_entries`][].
+///
+/// This trait is implemented for `HashMap`, `BTreeMap` and, if the `indexmap` feature is enabled,
+/// `IndexMap`, if their keys implement `Display`.  The keys are used as the last element of the
+/// paths of the entries.
+///
+/// [`MergeContext::merge_entries`]: struct.MergeContext.html#method.merge_entries
+pub trait Entries {
+    /// The type of the keys of the map.
+    type Key: fmt::Display;
+    /// The type of the values of the map.
+    type Value: PartialEq;
+
+    /// Returns the value for the given key, if any.
+    fn entry(&self, key: &Self::Key) -> Option<&Self::Value>;
+
+    /// Calls `f` with every entry of the map.
+    fn for_each_entry(&self, f: &mut dyn FnMut(&Self::Key, &Self::Value));
+}
+
+impl<K: fmt::Display + Eq + Hash, V: PartialEq, S: BuildHasher> Entries for HashMap<K, V, S> {
+    type Key = K;
+    type Value = V;
+
+    fn entry(&self, key: &K) -> Option<&V> {
+        self.get(key)
+    }
+
+    fn for_each_entry(&self, f: &mut dyn FnMut(&K, &V)) {
+        for (key, value) in self {
+            f(key, value);
+        }
+    }
+}
+
+impl<K: fmt::Display + Ord, V: PartialEq> Entries for BTreeMap<K, V> {
+    type Key = K;
+    type Value = V;
+
+    fn entry(&self, key: &K) -> Option<&V> {
+        self.get(key)
+    }
+
+    fn for_each_entry(&self, f: &mut dyn FnMut(&K, &V)) {
+        for (key, value) in self {
+            f(key, value);
+        }
+    }
+}
+
+#[cfg(feature = "indexmap")]
+impl<K: fmt::Display + Eq + Hash, V: PartialEq, S: BuildHasher> Entries for IndexMap<K, V, S> {
+    type Key = K;
+    type Value = V;
+
+    fn entry(&self, key: &K) -> Option<&V> {
+        self.get(key)
+    }
+
+    fn for_each_entry(&self, f: &mut dyn FnMut(&K, &V)) {
+        for (key, value) in self {
+            f(key, value);
+        }
+    }
+}
+
+/// A value that was ignored or set using a deprecated field while merging, see
+/// [`MergeContext`][].
+///
+/// [`MergeContext`]: struct.MergeContext.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Warning {
+    path: String,
+    source: String,
+    kind: WarningKind,
+}
+
+impl Warning {
+    /// Returns the path of the field whose value caused the warning.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the name of the source of the value.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the kind of this warning.
+    pub const fn kind(&self) -> &WarningKind {
+        &self.kind
+    }
+
+    /// Returns the name of the source that set the current value of the field, or `None` if it
+    /// was not set by a merge using the same context or if the value was not ignored.
+    pub fn set_by(&self) -> Option<&str> {
+        match &self.kind {
+            WarningKind::Ignored { set_by } => set_by.as_deref(),
+            WarningKind::Deprecated { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            WarningKind::Ignored { set_by } => {
+                write!(f, "`{}` from {} ignored", self.path, self.source)?;
+                if let Some(set_by) = set_by {
+                    write!(f, ": already set by {set_by}")?;
+                }
+                Ok(())
+            }
+            WarningKind::Deprecated { replacement } => write!(
+                f,
+                "`{}` from {} is deprecated, use `{replacement}` instead",
+                self.path, self.source
+            ),
+        }
+    }
+}
+
+/// The kind of a [`Warning`][].
+///
+/// [`Warning`]: struct.Warning.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WarningKind {
+    /// The merge strategy ignored the value.
+    Ignored {
+        /// The name of the source that set the current value of the field, if known.
+        set_by: Option<String>,
+    },
+    /// The value was set using a deprecated field, see the `deprecated` module.
+    Deprecated {
+        /// The path of the field that replaces the deprecated field.
+        replacement: String,
+    },
+}
+
+/// A trait for objects that can be merged while collecting warnings in a [`MergeContext`][].
+///
+/// # Deriving
+///
+/// `MergeWithContext` can be derived for structs if the `derive` feature is enabled.  The derive
+/// macro uses the same `merge` attributes as the `Merge` derive macro:
+/// - Fields with a strategy are merged using [`MergeContext::merge_field`][] and the strategy, so
+///   their type has to implement `Clone`, `Default` and `PartialEq`.
+/// - Fields of type `HashMap`, `BTreeMap` or `IndexMap` with a strategy are merged using
+///   [`MergeContext::merge_entries`][], so that ignored values are reported per key.  Their keys
+///   have to implement `Display`.
+/// - Fields without a strategy are merged by calling `MergeWithContext::merge_with_ctx_at`.
+///
+/// The field paths use the keys determined from the `serde` attributes of the struct, see
+/// [`FieldPath`][].
+///
+/// [`MergeContext`]: struct.MergeContext.html
+/// [`MergeContext::merge_field`]: struct.MergeContext.html#method.merge_field
+/// [`MergeContext::merge_entries`]: struct.MergeContext.html#method.merge_entries
+/// [`FieldPath`]: ../path/enum.FieldPath.html
+pub trait MergeWithContext {
+    /// Merge another object into this object, recording the fields with the given path prefix in
+    /// the given context.
+    fn merge_with_ctx_at(&mut self, other: Self, path: &FieldPath<'_>, ctx: &mut MergeContext);
+
+    /// Merge another object into this object, recording the changed and ignored fields in the
+    /// given context.
+    fn merge_with_ctx(&mut self, other: Self, ctx: &mut MergeContext)
+    where
+        Self: Sized,
+    {
+        self.merge_with_ctx_at(other, &FieldPath::Root, ctx);
+    }
+}
+
+impl<T: MergeWithContext> MergeWithContext for Locked<T> {
+    /// Ignores the other value if this value is locked.
+    fn merge_with_ctx_at(&mut self, other: Self, path: &FieldPath<'_>, ctx: &mut MergeContext) {
+        crate::locked::merge_with(self, other, |left, right| {
+            left.merge_with_ctx_at(right, path, ctx);
+        });
+    }
+}
@@ -0,0 +1,60 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Renamed fields that are kept for compatibility.
+//!
+//! When a configuration key is renamed, the old field is often kept for a release so that existing
+//! configuration files keep working.  The `deprecated_alias = old_field` attribute of the derive
+//! macros marks a field as the replacement of the field `old_field` of the same type.  Before the
+//! fields are merged, the value of the old field is moved into the new field using [`fold`][] on
+//! both sides of the merge, so the old field is always cleared and the new field is merged using
+//! its own strategy.
+//!
+//! The attribute is supported by the derive macros for `Merge`, `MergeRef`, `MergeChanged`,
+//! `MergePreview`, `TryMerge`, `MergeTrusted`, `MergeWithContext` and `DeserializeMerge`.  The
+//! derives that take the other value by reference fold clones of its fields, and `MergePreview`
+//! requires a merge strategy for both fields.  `DeserializeMerge` treats the key of the old field
+//! as an alias of the new key that is only used if the new key is not present.
+//! `MergeWithContext` additionally records a deprecation warning with the source of the layer that
+//! set the old field.
+//!
+//! # Example
+//!
+//! ```
+//! use conflate::Merge;
+//!
+//! #[derive(Debug, Default, Merge)]
+//! #[merge(strategy = conflate::option::overwrite_with_some)]
+//! struct Config {
+//!     #[merge(deprecated_alias = repo)]
+//!     repository: Option<String>,
+//!     repo: Option<String>,
+//! }
+//!
+//! let mut config = Config::default();
+//! config.merge(Config {
+//!     repository: None,
+//!     repo: Some("/srv/backup".to_owned()),
+//! });
+//! assert_eq!(Some("/srv/backup"), config.repository.as_deref());
+//! assert_eq!(None, config.repo);
+//! ```
+//!
+//! [`fold`]: fn.fold.html
+
+/// Moves the value of a deprecated field into the field that replaces it, and returns whether the
+/// deprecated field was set.
+///
+/// The deprecated field is reset to its default value.  Its value is only used if the new field
+/// has its default value, i.e. the new field takes precedence if both are set.
+pub fn fold<T: Default + PartialEq>(new: &mut T, old: &mut T) -> bool {
+    let old = core::mem::take(old);
+    if old == T::default() {
+        false
+    } else {
+        if *new == T::default() {
+            *new = old;
+        }
+        true
+    }
+}
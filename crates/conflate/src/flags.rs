@@ -0,0 +1,194 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Merge strategies for sets of flags.
+//!
+//! The strategies of this module can be used for all types that implement `bitflags::Flags` and
+//! `Copy`, i.e. for the types generated by the `bitflags!` macro of the `bitflags` crate that
+//! derive `Clone` and `Copy`.  [`union`][] enables all flags that are enabled in one of the
+//! layers, [`intersection`][] only keeps the flags that are enabled in both layers.
+//!
+//! To override single flags instead of the complete set, a layer can store the flags that it
+//! explicitly enables and disables in a [`SetUnset`][] value.  The merged value is applied to the
+//! default flags using [`SetUnset::apply`][].
+//!
+//! This module also implements `Merge`, `MergeRef` and `MergeChanged` for `enumset::EnumSet`.
+//! Like [`union`][], these implementations enable all flags that are enabled in one of the sets.
+//!
+//! These strategies are only available if the `flags` feature is enabled.
+//!
+//! # Example
+//!
+//! ```
+//! use conflate::Merge;
+//!
+//! bitflags::bitflags! {
+//!     #[derive(Clone, Copy, Debug, PartialEq)]
+//!     struct Features: u8 {
+//!         const COMPRESSION = 1;
+//!         const ENCRYPTION = 1 << 1;
+//!         const DEDUPLICATION = 1 << 2;
+//!     }
+//! }
+//!
+//! #[derive(Merge)]
+//! struct Config {
+//!     #[merge(strategy = conflate::flags::set_unset)]
+//!     features: conflate::flags::SetUnset<Features>,
+//! }
+//!
+//! let mut config = Config {
+//!     features: conflate::flags::SetUnset::new(Features::COMPRESSION, Features::ENCRYPTION),
+//! };
+//! config.merge(Config {
+//!     features: conflate::flags::SetUnset::new(Features::ENCRYPTION, Features::DEDUPLICATION),
+//! });
+//!
+//! let defaults = Features::COMPRESSION | Features::DEDUPLICATION;
+//! assert_eq!(
+//!     Features::COMPRESSION | Features::ENCRYPTION,
+//!     config.features.apply(defaults),
+//! );
+//! ```
+//!
+//! [`union`]: fn.union.html
+//! [`intersection`]: fn.intersection.html
+//! [`SetUnset`]: struct.SetUnset.html
+//! [`SetUnset::apply`]: struct.SetUnset.html#method.apply
+
+use bitflags::Flags;
+use enumset::{EnumSet, EnumSetType};
+
+use crate::{FieldPath, Merge, MergeChanged, MergeMeta, MergeRef};
+
+/// Set left to the union of left and right, i.e. enable all flags that are enabled in right.
+pub fn union<T: Flags + Copy>(left: &mut T, right: T) {
+    left.insert(right);
+}
+
+/// Set left to the intersection of left and right, i.e. disable all flags that are disabled in
+/// right.
+pub fn intersection<T: Flags + Copy>(left: &mut T, right: T) {
+    *left = left.intersection(right);
+}
+
+/// Merge the explicitly enabled and disabled flags of right into left.
+///
+/// Flags that are explicitly enabled or disabled in right override the state of the same flags
+/// in left.  All other flags keep their state from left.  This is the strategy used by the
+/// `Merge` implementation of [`SetUnset`][].
+///
+/// [`SetUnset`]: struct.SetUnset.html
+pub fn set_unset<T: Flags + Copy>(left: &mut SetUnset<T>, right: SetUnset<T>) {
+    left.set.remove(right.unset);
+    left.unset.remove(right.set);
+    left.set.insert(right.set);
+    left.unset.insert(right.unset);
+}
+
+/// Set left to the union of left and right and return whether left was changed.
+pub fn union_changed<T: Flags + Copy>(left: &mut T, right: T) -> bool {
+    let changed = !left.contains(right);
+    union(left, right);
+    changed
+}
+
+/// Set left to the intersection of left and right and return whether left was changed.
+pub fn intersection_changed<T: Flags + Copy>(left: &mut T, right: T) -> bool {
+    let changed = !right.contains(*left);
+    intersection(left, right);
+    changed
+}
+
+/// Flags that are explicitly enabled or disabled by a layer.
+///
+/// A configuration layer usually only mentions the flags it wants to change.  `SetUnset` stores
+/// these flags, so that flags that are not mentioned by a higher layer keep the state of the lower
+/// layers.  Use [`apply`][] to apply the merged value to the default flags.
+///
+/// If a flag is both in `set` and in `unset`, it is enabled.
+///
+/// [`apply`]: #method.apply
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SetUnset<T> {
+    /// The flags that are explicitly enabled.
+    pub set: T,
+    /// The flags that are explicitly disabled.
+    pub unset: T,
+}
+
+impl<T: Flags + Copy> SetUnset<T> {
+    /// Creates a new value with the given enabled and disabled flags.
+    pub const fn new(set: T, unset: T) -> Self {
+        Self { set, unset }
+    }
+
+    /// Returns the given flags with the flags of `unset` disabled and the flags of `set` enabled.
+    #[must_use]
+    pub fn apply(&self, flags: T) -> T {
+        flags.difference(self.unset).union(self.set)
+    }
+}
+
+impl<T: Flags + Copy> Default for SetUnset<T> {
+    fn default() -> Self {
+        Self::new(T::empty(), T::empty())
+    }
+}
+
+impl<T: Flags + Copy> Merge for SetUnset<T> {
+    fn merge(&mut self, other: Self) {
+        set_unset(self, other);
+    }
+}
+
+impl<T: Flags + Copy> MergeRef for SetUnset<T> {
+    fn merge_ref(&mut self, other: &Self) {
+        set_unset(self, *other);
+    }
+}
+
+impl<T: Flags + Copy + PartialEq> MergeChanged for SetUnset<T> {
+    fn merge_changed_with(
+        &mut self,
+        other: Self,
+        path: &FieldPath<'_>,
+        changed: &mut dyn FnMut(&FieldPath<'_>),
+    ) {
+        let old = *self;
+        set_unset(self, other);
+        if *self != old {
+            changed(path);
+        }
+    }
+}
+
+impl<T: Flags + Copy> MergeMeta for SetUnset<T> {}
+
+impl<T: EnumSetType> Merge for EnumSet<T> {
+    fn merge(&mut self, other: Self) {
+        *self |= other;
+    }
+}
+
+impl<T: EnumSetType> MergeRef for EnumSet<T> {
+    fn merge_ref(&mut self, other: &Self) {
+        *self |= *other;
+    }
+}
+
+impl<T: EnumSetType> MergeChanged for EnumSet<T> {
+    fn merge_changed_with(
+        &mut self,
+        other: Self,
+        path: &FieldPath<'_>,
+        changed: &mut dyn FnMut(&FieldPath<'_>),
+    ) {
+        if !self.is_superset(other) {
+            *self |= other;
+            changed(path);
+        }
+    }
+}
+
+impl<T: EnumSetType> MergeMeta for EnumSet<T> {}
@@ -0,0 +1,202 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Merge strategies for `IndexMap`s of the `indexmap` crate.
+//!
+//! The strategies are the same as in the `hashmap` module, but they preserve the insertion order
+//! of the keys: keys that only exist in `right` are appended in the order of `right`, and the keys
+//! of `left` keep their position when their values are overwritten or merged.  Removed keys are
+//! removed using `shift_remove`, so the order of the remaining keys is preserved as well.
+//!
+//! These strategies are only available if the `indexmap` feature is enabled.
+
+use indexmap::IndexMap;
+use std::hash::Hash;
+
+/// Append values, on conflict, overwrite elements of `left` with `right`.
+///
+/// In other words, this gives precedence to `right`.
+pub fn append_or_overwrite<K: Eq + Hash, V>(left: &mut IndexMap<K, V>, right: IndexMap<K, V>) {
+    left.extend(right)
+}
+
+/// Append values, on conflict, ignore elements from `right`.
+///
+/// In other words, this gives precedence to `left`.
+pub fn append_or_ignore<K: Eq + Hash, V>(left: &mut IndexMap<K, V>, right: IndexMap<K, V>) {
+    for (k, v) in right {
+        left.entry(k).or_insert(v);
+    }
+}
+
+/// Append values, on conflict, recursively merge the elements.
+pub fn append_or_recurse<K: Eq + Hash, V: crate::Merge>(
+    left: &mut IndexMap<K, V>,
+    right: IndexMap<K, V>,
+) {
+    use indexmap::map::Entry;
+
+    for (k, v) in right {
+        match left.entry(k) {
+            Entry::Occupied(mut existing) => existing.get_mut().merge(v),
+            Entry::Vacant(empty) => {
+                empty.insert(v);
+            }
+        }
+    }
+}
+
+/// Append values, on conflict, overwrite elements of `left` with `right`.
+///
+/// Returns whether `left` was changed, i.e. whether a key was added or a value was replaced with
+/// a different value.
+pub fn append_or_overwrite_changed<K: Eq + Hash, V: PartialEq>(
+    left: &mut IndexMap<K, V>,
+    right: IndexMap<K, V>,
+) -> bool {
+    use indexmap::map::Entry;
+
+    let mut changed = false;
+    for (k, v) in right {
+        match left.entry(k) {
+            Entry::Occupied(mut existing) => {
+                if *existing.get() != v {
+                    let _ = existing.insert(v);
+                    changed = true;
+                }
+            }
+            Entry::Vacant(empty) => {
+                let _ = empty.insert(v);
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+/// Append values, on conflict, ignore elements from `right`.
+///
+/// Returns whether `left` was changed, i.e. whether a key was added.
+pub fn append_or_ignore_changed<K: Eq + Hash, V>(
+    left: &mut IndexMap<K, V>,
+    right: IndexMap<K, V>,
+) -> bool {
+    let len = left.len();
+    append_or_ignore(left, right);
+    left.len() != len
+}
+
+/// Append values, on conflict, recursively merge the elements.
+///
+/// Returns whether `left` was changed, i.e. whether a key was added or a value was changed.
+pub fn append_or_recurse_changed<K: Eq + Hash, V: crate::MergeChanged>(
+    left: &mut IndexMap<K, V>,
+    right: IndexMap<K, V>,
+) -> bool {
+    use indexmap::map::Entry;
+
+    let mut changed = false;
+    for (k, v) in right {
+        match left.entry(k) {
+            Entry::Occupied(mut existing) => changed |= existing.get_mut().merge_changed(v),
+            Entry::Vacant(empty) => {
+                let _ = empty.insert(v);
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+/// Returns a strategy that appends the values of `right` and then only retains the keys that
+/// match the predicate.  On conflict, elements of `left` are overwritten with `right`.
+///
+/// The predicate is applied to both maps, so keys of `left` that do not match it are removed too.
+pub fn retain_keys<K: Eq + Hash, V, P: Fn(&K) -> bool>(
+    predicate: P,
+) -> impl Fn(&mut IndexMap<K, V>, IndexMap<K, V>) {
+    move |left, right| {
+        left.retain(|k, _| predicate(k));
+        left.extend(right.into_iter().filter(|(k, _)| predicate(k)));
+    }
+}
+
+/// Append values, on conflict, overwrite elements of `left` with `right`, but remove the keys
+/// that are marked with `None` in `right`.
+///
+/// This allows a layer to delete entries that were added by a lower layer.
+pub fn remove_keys_marked<K: Eq + Hash, V>(
+    left: &mut IndexMap<K, Option<V>>,
+    right: IndexMap<K, Option<V>>,
+) {
+    for (k, v) in right {
+        if v.is_some() {
+            let _ = left.insert(k, v);
+        } else {
+            let _ = left.shift_remove(&k);
+        }
+    }
+}
+
+/// Returns a strategy that merges the values of keys that exist in both maps using the given
+/// strategy and ignores the keys of `right` that do not exist in `left`.
+///
+/// This strategy only updates the existing keys of `left`, it never inserts keys.  Use
+/// [`append_or_recurse_with`][] to insert the other keys of `right` as well.
+///
+/// [`append_or_recurse_with`]: fn.append_or_recurse_with.html
+pub fn values_with<K: Eq + Hash, V, F: Fn(&mut V, V)>(
+    strategy: F,
+) -> impl Fn(&mut IndexMap<K, V>, IndexMap<K, V>) {
+    move |left, right| {
+        for (k, v) in right {
+            if let Some(existing) = left.get_mut(&k) {
+                strategy(existing, v);
+            }
+        }
+    }
+}
+
+/// Returns a strategy that appends values and, on conflict, merges the elements using the given
+/// strategy.
+///
+/// This is the same as [`append_or_recurse`][] with a custom strategy for the values.
+///
+/// [`append_or_recurse`]: fn.append_or_recurse.html
+pub fn append_or_recurse_with<K: Eq + Hash, V, F: Fn(&mut V, V)>(
+    strategy: F,
+) -> impl Fn(&mut IndexMap<K, V>, IndexMap<K, V>) {
+    use indexmap::map::Entry;
+
+    move |left, right| {
+        for (k, v) in right {
+            match left.entry(k) {
+                Entry::Occupied(mut existing) => strategy(existing.get_mut(), v),
+                Entry::Vacant(empty) => {
+                    let _ = empty.insert(v);
+                }
+            }
+        }
+    }
+}
+
+/// Append values, on conflict, overwrite elements of `left` with `right`, but remove the keys
+/// that are marked with `None` in `right`.
+///
+/// Returns whether `left` was changed, i.e. whether a key was added or removed or a value was
+/// replaced with a different value.
+pub fn remove_keys_marked_changed<K: Eq + Hash, V: PartialEq>(
+    left: &mut IndexMap<K, Option<V>>,
+    right: IndexMap<K, Option<V>>,
+) -> bool {
+    let mut changed = false;
+    for (k, v) in right {
+        if v.is_some() {
+            changed |= left.get(&k) != Some(&v);
+            let _ = left.insert(k, v);
+        } else {
+            changed |= left.shift_remove(&k).is_some();
+        }
+    }
+    changed
+}
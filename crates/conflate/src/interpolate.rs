@@ -0,0 +1,331 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Resolve references to other fields and environment variables after merging.
+//!
+//! String fields often refer to other fields or to the environment, e.g. a cache directory
+//! inside the home directory of the user.  After all layers have been merged, the
+//! [`Interpolate`][] trait replaces such references in all string fields:
+//!
+//! - `${env:NAME}` is replaced with the value of the environment variable `NAME`.
+//! - `${path}` is replaced with the (interpolated) value of the string field at `path`, e.g.
+//!   `${repository.path}`.  Paths use the same keys as [`FieldPath`][].
+//! - `$${` is replaced with a literal `${`.
+//!
+//! References between fields may be nested, but not cyclic.  Cycles, undefined references and
+//! unterminated references are reported with the path of the field that contains them, see
+//! [`Error`][].  If any reference cannot be resolved, the value is not modified.
+//!
+//! These items are only available if the `interpolate` feature is enabled.
+//!
+//! # Example
+//!
+//! ```
+//! use conflate::interpolate::Interpolate;
+//!
+//! #[derive(Interpolate)]
+//! struct Repository {
+//!     path: String,
+//!     cache: Option<String>,
+//! }
+//!
+//! #[derive(Interpolate)]
+//! struct Config {
+//!     repository: Repository,
+//!     #[merge(skip_interpolate)]
+//!     template: String,
+//! }
+//!
+//! let mut config = Config {
+//!     repository: Repository {
+//!         path: "${env:HOME}/backup".to_owned(),
+//!         cache: Some("${repository.path}/cache".to_owned()),
+//!     },
+//!     template: "${not interpolated}".to_owned(),
+//! };
+//!
+//! config.interpolate_with(|name| (name == "HOME").then(|| "/home/ferris".to_owned())).unwrap();
+//! assert_eq!("/home/ferris/backup", config.repository.path);
+//! assert_eq!(Some("/home/ferris/backup/cache"), config.repository.cache.as_deref());
+//! assert_eq!("${not interpolated}", config.template);
+//! ```
+//!
+//! [`Interpolate`]: trait.Interpolate.html
+//! [`Error`]: struct.Error.html
+//! [`FieldPath`]: ../path/enum.FieldPath.html
+
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error as StdError;
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::{FieldPath, MergeVisit, Secret};
+
+#[cfg(feature = "derive")]
+pub use conflate_derive::Interpolate;
+
+/// A trait for objects whose string fields can be interpolated.
+///
+/// This trait is implemented for all types that implement [`MergeVisit`][].  The strings are the
+/// leaves of type `String`, `PathBuf` (if it is valid Unicode) and `Secret<String>` that are
+/// passed to the visitor, so the field paths use the same keys as `MergeVisit`.  Other leaves
+/// are ignored.  Secret values can be read from the environment using `${env:NAME}`, but other
+/// fields can refer to them too, so these fields should not be printed either.
+///
+/// # Deriving
+///
+/// The `Interpolate` derive macro implements `MergeVisit` for structs if the `derive` feature is
+/// enabled.  It generates the same implementation as the `visit` attribute of the `Merge` derive
+/// macro, so only one of them may be used for a struct.  The fields are visited unless they have
+/// the `skip` or the `skip_interpolate` attribute:
+///
+/// ```
+/// use conflate::interpolate::Interpolate;
+///
+/// #[derive(Interpolate)]
+/// struct S {
+///     #[merge(skip_interpolate)]
+///     template: String,
+/// }
+/// ```
+///
+/// [`MergeVisit`]: ../visit/trait.MergeVisit.html
+pub trait Interpolate: MergeVisit {
+    /// Resolves all references in this object using the environment of the current process.
+    ///
+    /// # Errors
+    ///
+    /// Returns all references that could not be resolved.  The object is not modified in this
+    /// case.
+    fn interpolate(&mut self) -> Result<(), Error>
+    where
+        Self: Sized,
+    {
+        self.interpolate_with(|name| std::env::var(name).ok())
+    }
+
+    /// Resolves all references in this object using the given function to look up environment
+    /// variables.
+    ///
+    /// # Errors
+    ///
+    /// Returns all references that could not be resolved.  The object is not modified in this
+    /// case.
+    fn interpolate_with<F>(&mut self, env: F) -> Result<(), Error>
+    where
+        Self: Sized,
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut fields = BTreeMap::new();
+        self.visit(&mut |path: &FieldPath<'_>, value: &dyn Any| {
+            if let Some(value) = as_str(value) {
+                let _ = fields.insert(path.to_string(), value.to_owned());
+            }
+        });
+
+        let mut resolver = Resolver {
+            fields: &fields,
+            env: &env,
+            resolved: HashMap::new(),
+            unresolved: Vec::new(),
+        };
+        for path in fields.keys() {
+            let _ = resolver.resolve(path);
+        }
+        if !resolver.unresolved.is_empty() {
+            return Err(Error {
+                unresolved: resolver.unresolved,
+            });
+        }
+
+        let resolved = resolver.resolved;
+        self.visit_mut(&mut |path: &FieldPath<'_>, value: &mut dyn Any| {
+            if let Some(State::Resolved(Some(new))) = resolved.get(&path.to_string()) {
+                set_str(value, new);
+            }
+        });
+        Ok(())
+    }
+}
+
+impl<T: MergeVisit + ?Sized> Interpolate for T {}
+
+/// Returns the string value of a leaf, or `None` if it is not a string.
+fn as_str(value: &dyn Any) -> Option<&str> {
+    if let Some(s) = value.downcast_ref::<String>() {
+        Some(s)
+    } else if let Some(path) = value.downcast_ref::<PathBuf>() {
+        path.to_str()
+    } else if let Some(secret) = value.downcast_ref::<Secret<String>>() {
+        Some(secret.expose())
+    } else {
+        None
+    }
+}
+
+/// Replaces the value of a string leaf that has been returned by `as_str`.
+fn set_str(value: &mut dyn Any, new: &str) {
+    if let Some(s) = value.downcast_mut::<String>() {
+        new.clone_into(s);
+    } else if let Some(path) = value.downcast_mut::<PathBuf>() {
+        *path = new.into();
+    } else if let Some(secret) = value.downcast_mut::<Secret<String>>() {
+        new.clone_into(secret.expose_mut());
+    }
+}
+
+enum State {
+    InProgress,
+    Resolved(Option<String>),
+}
+
+struct Resolver<'a, F> {
+    fields: &'a BTreeMap<String, String>,
+    env: &'a F,
+    resolved: HashMap<String, State>,
+    unresolved: Vec<Unresolved>,
+}
+
+impl<F: Fn(&str) -> Option<String>> Resolver<'_, F> {
+    /// Returns the interpolated value of the field at the given path, or `None` if it contains a
+    /// reference that cannot be resolved.
+    fn resolve(&mut self, path: &str) -> Option<String> {
+        match self.resolved.get(path) {
+            Some(State::Resolved(value)) => return value.clone(),
+            Some(State::InProgress) => unreachable!("cycles are detected before recursing"),
+            None => {}
+        }
+
+        let _ = self.resolved.insert(path.to_owned(), State::InProgress);
+        let fields = self.fields;
+        let value = self.interpolate(path, &fields[path]);
+        let _ = self
+            .resolved
+            .insert(path.to_owned(), State::Resolved(value.clone()));
+        value
+    }
+
+    fn interpolate(&mut self, path: &str, raw: &str) -> Option<String> {
+        let mut result = String::with_capacity(raw.len());
+        let mut ok = true;
+        let mut rest = raw;
+        while let Some(start) = rest.find('$') {
+            result.push_str(&rest[..start]);
+            rest = &rest[start..];
+            if let Some(tail) = rest.strip_prefix("$${") {
+                result.push_str("${");
+                rest = tail;
+            } else if let Some(tail) = rest.strip_prefix("${") {
+                let end = if let Some(end) = tail.find('}') {
+                    end
+                } else {
+                    self.report(path, tail, Reason::Unterminated);
+                    return None;
+                };
+                let reference = &tail[..end];
+                match self.lookup(path, reference) {
+                    Some(value) => result.push_str(&value),
+                    None => ok = false,
+                }
+                rest = &tail[end + 1..];
+            } else {
+                result.push('$');
+                rest = &rest[1..];
+            }
+        }
+        result.push_str(rest);
+        ok.then_some(result)
+    }
+
+    fn lookup(&mut self, path: &str, reference: &str) -> Option<String> {
+        if let Some(name) = reference.strip_prefix("env:") {
+            let value = (self.env)(name);
+            if value.is_none() {
+                self.report(path, reference, Reason::Undefined);
+            }
+            value
+        } else if !self.fields.contains_key(reference) {
+            self.report(path, reference, Reason::Undefined);
+            None
+        } else if matches!(self.resolved.get(reference), Some(State::InProgress)) {
+            self.report(path, reference, Reason::Cycle);
+            None
+        } else {
+            self.resolve(reference)
+        }
+    }
+
+    fn report(&mut self, path: &str, reference: &str, reason: Reason) {
+        self.unresolved.push(Unresolved {
+            path: path.to_owned(),
+            reference: reference.to_owned(),
+            reason,
+        });
+    }
+}
+
+/// The error returned by [`Interpolate::interpolate`][] if references could not be resolved.
+///
+/// [`Interpolate::interpolate`]: trait.Interpolate.html#method.interpolate
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    unresolved: Vec<Unresolved>,
+}
+
+impl Error {
+    /// Returns the references that could not be resolved, ordered by the path of the field that
+    /// contains them.
+    pub fn unresolved(&self) -> &[Unresolved] {
+        &self.unresolved
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("failed to resolve references: ")?;
+        for (i, unresolved) in self.unresolved.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{unresolved}")?;
+        }
+        Ok(())
+    }
+}
+
+impl StdError for Error {}
+
+/// A reference that could not be resolved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Unresolved {
+    /// The path of the field that contains the reference.
+    pub path: String,
+    /// The reference without the surrounding `${` and `}`.
+    pub reference: String,
+    /// The reason why the reference could not be resolved.
+    pub reason: Reason,
+}
+
+impl fmt::Display for Unresolved {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.reason {
+            Reason::Undefined => "is not defined",
+            Reason::Cycle => "is part of a cycle",
+            Reason::Unterminated => "is not terminated",
+        };
+        write!(f, "${{{}}} in {} {}", self.reference, self.path, reason)
+    }
+}
+
+/// The reason why a reference could not be resolved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Reason {
+    /// The environment variable or string field does not exist.
+    Undefined,
+    /// The field refers to itself, directly or indirectly.
+    Cycle,
+    /// The reference is missing the closing `}`.
+    Unterminated,
+}
@@ -0,0 +1,604 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Load a value from a list of layered sources.
+//!
+//! A configuration is often merged from several layers, for example a system-wide and a
+//! user-specific configuration file.  [`Layers`][] keeps the list of [`Source`][]s, so that the
+//! layers can be loaded and merged again later, e.g. when a configuration file changes, see the
+//! `watch` module.
+//!
+//! Each source has a [`Trust`][] level, the lowest level `Trust::Local` unless configured
+//! otherwise.  [`Layers::load_trusted`][] merges the layers using [`MergeTrusted`][] and ignores
+//! the values of fields that require a higher trust level than the source that sets them.  The
+//! other methods of [`Layers`][] ignore the trust levels and the `min_trust` attributes.
+//!
+//! If the `async` feature is enabled, layers can also be loaded from [`AsyncSource`][]s, for
+//! example a remote configuration store, using [`Layers::load_async`][] or
+//! [`Layers::load_trusted_async`][].
+//!
+//! These items are only available if the `std` feature is enabled.
+//!
+//! # Example
+//!
+//! ```
+//! use conflate::layers::{FileSource, Layers, ValueSource};
+//! use conflate::Merge;
+//!
+//! #[derive(Clone, Default, Merge, serde::Deserialize)]
+//! #[merge(strategy = conflate::option::overwrite_none)]
+//! struct Config {
+//!     repository: Option<String>,
+//!     password_file: Option<String>,
+//! }
+//!
+//! let cli = Config {
+//!     repository: Some("/srv/backup".to_owned()),
+//!     password_file: None,
+//! };
+//!
+//! let file = FileSource::new("/etc/backup.json", |s| serde_json::from_str(s));
+//! let layers = Layers::new()
+//!     .with_source(ValueSource::new("command line", cli))
+//!     .with_source(file.optional());
+//!
+//! // /etc/backup.json does not exist, so only the command line arguments are used
+//! let config = layers.load().unwrap();
+//! assert_eq!(Some("/srv/backup".to_owned()), config.repository);
+//! ```
+//!
+//! [`Layers`]: struct.Layers.html
+//! [`Source`]: trait.Source.html
+//! [`AsyncSource`]: trait.AsyncSource.html
+//! [`Layers::load_async`]: struct.Layers.html#method.load_async
+//! [`Layers::load_trusted_async`]: struct.Layers.html#method.load_trusted_async
+//! [`Trust`]: ../trust/enum.Trust.html
+//! [`Layers::load_trusted`]: struct.Layers.html#method.load_trusted
+//! [`MergeTrusted`]: ../trust/trait.MergeTrusted.html
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::context::{MergeContext, MergeWithContext, Warning};
+use crate::trust::{self, MergeTrusted, Trust};
+use crate::Merge;
+
+/// A source of a single configuration layer.
+pub trait Source<T>: Send + Sync {
+    /// Returns a human-readable name of this source that is used in error messages, e.g. the
+    /// path of a file.
+    fn name(&self) -> String;
+
+    /// Loads the layer from this source.
+    ///
+    /// Returns `None` if the source is optional and does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the source could not be read or parsed.
+    fn load(&self) -> Result<Option<T>, Error>;
+
+    /// Returns the time of the last modification of this source, if known.
+    ///
+    /// This is used to check whether the source has to be loaded again.  The default
+    /// implementation returns `None`, i.e. the source is assumed to never change.
+    fn modified(&self) -> Option<SystemTime> {
+        None
+    }
+
+    /// Returns the trust level of this source that is used by [`Layers::load_trusted`][].
+    ///
+    /// The default implementation returns `Trust::Local`, the lowest level, so the source cannot
+    /// set fields that require a higher level unless it overrides this method.
+    ///
+    /// [`Layers::load_trusted`]: struct.Layers.html#method.load_trusted
+    fn trust(&self) -> Trust {
+        Trust::Local
+    }
+}
+
+/// A source of a single configuration layer that is loaded asynchronously.
+///
+/// This trait is only available if the `async` feature is enabled.
+#[cfg(feature = "async")]
+pub trait AsyncSource<T>: Send + Sync {
+    /// Returns a human-readable name of this source that is used in error messages.
+    fn name(&self) -> String;
+
+    /// Loads the layer from this source.
+    ///
+    /// The returned future resolves to `None` if the source is optional and does not exist, or to
+    /// an error if the source could not be read or parsed.
+    fn load(&self) -> BoxFuture<'_, Result<Option<T>, Error>>;
+
+    /// Returns the time of the last modification of this source, if known.
+    ///
+    /// The default implementation returns `None`, i.e. the source is assumed to never change.
+    fn modified(&self) -> Option<SystemTime> {
+        None
+    }
+
+    /// Returns the trust level of this source that is used by [`Layers::load_trusted_async`][].
+    ///
+    /// The default implementation returns `Trust::Local`, the lowest level, so the source cannot
+    /// set fields that require a higher level unless it overrides this method.
+    ///
+    /// [`Layers::load_trusted_async`]: struct.Layers.html#method.load_trusted_async
+    fn trust(&self) -> Trust {
+        Trust::Local
+    }
+}
+
+/// The future returned by [`AsyncSource::load`][].
+///
+/// [`AsyncSource::load`]: trait.AsyncSource.html#tymethod.load
+#[cfg(feature = "async")]
+pub type BoxFuture<'a, T> = core::pin::Pin<Box<dyn core::future::Future<Output = T> + Send + 'a>>;
+
+enum Layer<T> {
+    Sync(Box<dyn Source<T>>),
+    #[cfg(feature = "async")]
+    Async(Box<dyn AsyncSource<T>>),
+}
+
+impl<T> Layer<T> {
+    fn name(&self) -> String {
+        match self {
+            Self::Sync(source) => source.name(),
+            #[cfg(feature = "async")]
+            Self::Async(source) => source.name(),
+        }
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        match self {
+            Self::Sync(source) => source.modified(),
+            #[cfg(feature = "async")]
+            Self::Async(source) => source.modified(),
+        }
+    }
+
+    fn trust(&self) -> Trust {
+        match self {
+            Self::Sync(source) => source.trust(),
+            #[cfg(feature = "async")]
+            Self::Async(source) => source.trust(),
+        }
+    }
+}
+
+/// An ordered list of sources that are loaded and merged into a single value.
+pub struct Layers<T> {
+    layers: Vec<Layer<T>>,
+}
+
+impl<T> Layers<T> {
+    /// Creates an empty list of layers.
+    #[must_use]
+    pub fn new() -> Self {
+        Self { layers: Vec::new() }
+    }
+
+    /// Appends a source to the list of layers and returns the list.
+    #[must_use]
+    pub fn with_source<S: Source<T> + 'static>(mut self, source: S) -> Self {
+        self.push(source);
+        self
+    }
+
+    /// Appends a source to the list of layers.
+    pub fn push<S: Source<T> + 'static>(&mut self, source: S) {
+        self.layers.push(Layer::Sync(Box::new(source)));
+    }
+
+    /// Appends an asynchronous source to the list of layers and returns the list.
+    ///
+    /// Layers with asynchronous sources can only be loaded using [`load_async`][] or
+    /// [`load_trusted_async`][].
+    ///
+    /// This method is only available if the `async` feature is enabled.
+    ///
+    /// [`load_async`]: #method.load_async
+    /// [`load_trusted_async`]: #method.load_trusted_async
+    #[cfg(feature = "async")]
+    #[must_use]
+    pub fn with_async_source<S: AsyncSource<T> + 'static>(mut self, source: S) -> Self {
+        self.push_async(source);
+        self
+    }
+
+    /// Appends an asynchronous source to the list of layers.
+    ///
+    /// Layers with asynchronous sources can only be loaded using [`load_async`][] or
+    /// [`load_trusted_async`][].
+    ///
+    /// This method is only available if the `async` feature is enabled.
+    ///
+    /// [`load_async`]: #method.load_async
+    /// [`load_trusted_async`]: #method.load_trusted_async
+    #[cfg(feature = "async")]
+    pub fn push_async<S: AsyncSource<T> + 'static>(&mut self, source: S) {
+        self.layers.push(Layer::Async(Box::new(source)));
+    }
+
+    /// Returns the names of the sources in the order in which they are merged.
+    pub fn names(&self) -> impl Iterator<Item = String> + '_ {
+        self.layers.iter().map(Layer::name)
+    }
+
+    /// Returns the modification times of the sources in the order in which they are merged.
+    pub fn modified(&self) -> Vec<Option<SystemTime>> {
+        self.layers.iter().map(Layer::modified).collect()
+    }
+}
+
+impl<T: Default + Merge> Layers<T> {
+    /// Loads all layers and merges them into a single value.
+    ///
+    /// Starting from `T::default()`, the layers are merged in the order in which they were added
+    /// using [`Merge::merge`][].  Whether earlier or later layers take precedence depends on the
+    /// merge strategies of `T`, e.g. `option::overwrite_none` gives precedence to earlier layers.
+    /// The trust levels of the sources are ignored, use [`load_trusted`][] to take them into
+    /// account.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first source that could not be loaded.  If the `async` feature
+    /// is enabled, an error with the kind `ErrorKind::Async` is returned for asynchronous sources.
+    ///
+    /// [`Merge::merge`]: ../trait.Merge.html#tymethod.merge
+    /// [`load_trusted`]: #method.load_trusted
+    pub fn load(&self) -> Result<T, Error> {
+        let mut value = T::default();
+        for layer in &self.layers {
+            let layer = match layer {
+                Layer::Sync(source) => source.load()?,
+                #[cfg(feature = "async")]
+                Layer::Async(source) => return Err(Error::new(source.name(), ErrorKind::Async)),
+            };
+            if let Some(layer) = layer {
+                value.merge(layer);
+            }
+        }
+        Ok(value)
+    }
+
+    /// Loads all layers concurrently and merges them into a single value.
+    ///
+    /// The asynchronous sources are loaded concurrently, but the layers are merged in the order
+    /// in which they were added, like in [`load`][], so the result does not depend on the order
+    /// in which the sources finish loading.  Synchronous sources are loaded while the
+    /// asynchronous sources are pending.
+    ///
+    /// This method is only available if the `async` feature is enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first source in merge order that could not be loaded.
+    ///
+    /// [`load`]: #method.load
+    #[cfg(feature = "async")]
+    pub async fn load_async(&self) -> Result<T, Error> {
+        use futures_util::future::{self, Either};
+
+        let layers = self.layers.iter().map(|layer| match layer {
+            Layer::Sync(source) => Either::Left(future::lazy(move |_| source.load())),
+            Layer::Async(source) => Either::Right(source.load()),
+        });
+
+        let mut value = T::default();
+        for layer in future::join_all(layers).await {
+            if let Some(layer) = layer? {
+                value.merge(layer);
+            }
+        }
+        Ok(value)
+    }
+}
+
+impl<T: Default + MergeTrusted> Layers<T> {
+    /// Loads all layers and merges them into a single value, taking into account the trust level
+    /// of the sources.
+    ///
+    /// Like [`load`][], but the layers are merged using [`MergeTrusted::merge_trusted`][] with the
+    /// trust level of their source.  Values of fields that require a higher trust level are
+    /// ignored, and the returned list contains the name of the source and the rejected fields for
+    /// every layer that tried to set them.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first source that could not be loaded.  If the `async` feature
+    /// is enabled, an error with the kind `ErrorKind::Async` is returned for asynchronous sources.
+    ///
+    /// [`load`]: #method.load
+    /// [`MergeTrusted::merge_trusted`]: ../trust/trait.MergeTrusted.html#method.merge_trusted
+    pub fn load_trusted(&self) -> Result<(T, Vec<(String, trust::Error)>), Error> {
+        let mut value = T::default();
+        let mut rejected = Vec::new();
+        for layer in &self.layers {
+            let loaded = match layer {
+                Layer::Sync(source) => source.load()?,
+                #[cfg(feature = "async")]
+                Layer::Async(source) => return Err(Error::new(source.name(), ErrorKind::Async)),
+            };
+            if let Some(loaded) = loaded {
+                if let Err(err) = value.merge_trusted(loaded, layer.trust()) {
+                    rejected.push((layer.name(), err));
+                }
+            }
+        }
+        Ok((value, rejected))
+    }
+
+    /// Loads all layers concurrently and merges them into a single value, taking into account the
+    /// trust level of the sources.
+    ///
+    /// Like [`load_trusted`][], but the sources are loaded like in [`load_async`][].
+    ///
+    /// This method is only available if the `async` feature is enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first source in merge order that could not be loaded.
+    ///
+    /// [`load_trusted`]: #method.load_trusted
+    /// [`load_async`]: #method.load_async
+    #[cfg(feature = "async")]
+    pub async fn load_trusted_async(&self) -> Result<(T, Vec<(String, trust::Error)>), Error> {
+        use futures_util::future::{self, Either};
+
+        let layers = self.layers.iter().map(|layer| match layer {
+            Layer::Sync(source) => Either::Left(future::lazy(move |_| source.load())),
+            Layer::Async(source) => Either::Right(source.load()),
+        });
+
+        let mut value = T::default();
+        let mut rejected = Vec::new();
+        for (layer, loaded) in self.layers.iter().zip(future::join_all(layers).await) {
+            if let Some(loaded) = loaded? {
+                if let Err(err) = value.merge_trusted(loaded, layer.trust()) {
+                    rejected.push((layer.name(), err));
+                }
+            }
+        }
+        Ok((value, rejected))
+    }
+}
+
+impl<T: Default + MergeWithContext> Layers<T> {
+    /// Loads all layers and merges them into a single value, collecting warnings about ignored
+    /// values.
+    ///
+    /// Like [`load`][], but the layers are merged using [`MergeWithContext::merge_with_ctx`][]
+    /// with a [`MergeContext`][] whose source is set to the name of the layer, so the returned
+    /// warnings name the layer of the ignored value and the layer that set the field.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first source that could not be loaded.  If the `async` feature
+    /// is enabled, an error with the kind `ErrorKind::Async` is returned for asynchronous sources.
+    ///
+    /// [`load`]: #method.load
+    /// [`MergeWithContext::merge_with_ctx`]: ../context/trait.MergeWithContext.html#method.merge_with_ctx
+    /// [`MergeContext`]: ../context/struct.MergeContext.html
+    pub fn load_with_ctx(&self) -> Result<(T, Vec<Warning>), Error> {
+        let mut value = T::default();
+        let mut ctx = MergeContext::new();
+        for layer in &self.layers {
+            let loaded = match layer {
+                Layer::Sync(source) => source.load()?,
+                #[cfg(feature = "async")]
+                Layer::Async(source) => return Err(Error::new(source.name(), ErrorKind::Async)),
+            };
+            if let Some(loaded) = loaded {
+                ctx.set_source(layer.name());
+                value.merge_with_ctx(loaded, &mut ctx);
+            }
+        }
+        Ok((value, ctx.take_warnings()))
+    }
+}
+
+impl<T> Default for Layers<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for Layers<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+type ParseFn<T> = dyn Fn(&str) -> Result<T, Box<dyn StdError + Send + Sync>> + Send + Sync;
+
+/// A layer that is read from a file and parsed using a function.
+pub struct FileSource<T> {
+    path: PathBuf,
+    parse: Box<ParseFn<T>>,
+    optional: bool,
+    trust: Trust,
+}
+
+impl<T> FileSource<T> {
+    /// Creates a source for the file with the given path that is parsed using the given function,
+    /// for example `toml::from_str`.
+    pub fn new<P, F, E>(path: P, parse: F) -> Self
+    where
+        P: Into<PathBuf>,
+        F: Fn(&str) -> Result<T, E> + Send + Sync + 'static,
+        E: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        Self {
+            path: path.into(),
+            parse: Box::new(move |s| parse(s).map_err(Into::into)),
+            optional: false,
+            trust: Trust::Local,
+        }
+    }
+
+    /// Marks the file as optional, i.e. the layer is skipped if the file does not exist.
+    #[must_use]
+    pub const fn optional(mut self) -> Self {
+        self.optional = true;
+        self
+    }
+
+    /// Sets the trust level of the file, `Trust::Local` by default.
+    #[must_use]
+    pub const fn with_trust(mut self, trust: Trust) -> Self {
+        self.trust = trust;
+        self
+    }
+
+    /// Returns the path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl<T> Source<T> for FileSource<T> {
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn load(&self) -> Result<Option<T>, Error> {
+        let s = match fs::read_to_string(&self.path) {
+            Ok(s) => s,
+            Err(err) if self.optional && err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(Error::new(self.name(), ErrorKind::Io(err))),
+        };
+        (self.parse)(&s)
+            .map(Some)
+            .map_err(|err| Error::new(self.name(), ErrorKind::Parse(err)))
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        fs::metadata(&self.path).and_then(|m| m.modified()).ok()
+    }
+
+    fn trust(&self) -> Trust {
+        self.trust
+    }
+}
+
+impl<T> fmt::Debug for FileSource<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileSource")
+            .field("path", &self.path)
+            .field("optional", &self.optional)
+            .field("trust", &self.trust)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A layer with a fixed value, for example the parsed command-line arguments.
+#[derive(Clone, Debug)]
+pub struct ValueSource<T> {
+    name: String,
+    value: T,
+    trust: Trust,
+}
+
+impl<T> ValueSource<T> {
+    /// Creates a source with the given name that always returns a clone of the given value.
+    pub fn new(name: impl Into<String>, value: T) -> Self {
+        Self {
+            name: name.into(),
+            value,
+            trust: Trust::Local,
+        }
+    }
+
+    /// Sets the trust level of the value, `Trust::Local` by default.
+    #[must_use]
+    pub fn with_trust(mut self, trust: Trust) -> Self {
+        self.trust = trust;
+        self
+    }
+}
+
+impl<T: Clone + Send + Sync> Source<T> for ValueSource<T> {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn load(&self) -> Result<Option<T>, Error> {
+        Ok(Some(self.value.clone()))
+    }
+
+    fn trust(&self) -> Trust {
+        self.trust
+    }
+}
+
+/// An error that occurred while loading a layer.
+#[derive(Debug)]
+pub struct Error {
+    source_name: String,
+    kind: ErrorKind,
+}
+
+/// The kind of an [`Error`][].
+///
+/// [`Error`]: struct.Error.html
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The source could not be read.
+    Io(io::Error),
+    /// The source could not be parsed.
+    Parse(Box<dyn StdError + Send + Sync>),
+    /// The source is asynchronous and cannot be loaded synchronously.
+    ///
+    /// This variant is only available if the `async` feature is enabled.
+    #[cfg(feature = "async")]
+    Async,
+}
+
+impl Error {
+    /// Creates an error for the source with the given name.
+    pub fn new(source_name: impl Into<String>, kind: ErrorKind) -> Self {
+        Self {
+            source_name: source_name.into(),
+            kind,
+        }
+    }
+
+    /// Returns the name of the source that could not be loaded.
+    pub fn source_name(&self) -> &str {
+        &self.source_name
+    }
+
+    /// Returns the kind of this error.
+    pub const fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ErrorKind::Io(_) => write!(f, "failed to read {}", self.source_name),
+            ErrorKind::Parse(_) => write!(f, "failed to parse {}", self.source_name),
+            #[cfg(feature = "async")]
+            ErrorKind::Async => write!(f, "{} can only be loaded asynchronously", self.source_name),
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match &self.kind {
+            ErrorKind::Io(err) => Some(err),
+            ErrorKind::Parse(err) => Some(err.as_ref()),
+            #[cfg(feature = "async")]
+            ErrorKind::Async => None,
+        }
+    }
+}
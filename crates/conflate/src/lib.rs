@@ -22,17 +22,51 @@
 //! crate provide strategies for the most common types, but you can also define your own
 //! strategies.
 //!
+//! To merge from a borrowed value without cloning it as a whole, the [`MergeRef`][] trait can be
+//! derived as well.  The [`MergePreview`][] trait reports the changes a merge would make without
+//! performing it, and the [`MergeChanged`][] trait reports which fields a merge changed.  The
+//! [`MergeVisit`][] trait walks the leaves of a merged value, and the [`TryMerge`][] trait reports
+//! attempts to change [`Locked`][] values.  The [`MergeTrusted`][] trait ignores and reports values
+//! of fields that require a higher trust level than the merged layer, and the
+//! [`MergeWithContext`][] trait collects warnings about values that a merge ignored.
+//!
+//! Each of these traits has its own derive macro, e.g. `#[derive(Merge, MergeRef)]`.  In
+//! particular, deriving `Merge` does not implement the [`MergeMeta`][] reflection table, which is
+//! derived separately using `#[derive(MergeMeta)]`.
+//!
 //! ## Features
 //!
 //! This crate has the following features:
 //!
+//! - `async`: Enables asynchronous sources for the `layers` module using the `futures-util` crate.
+//!   This feature requires the `std` feature.
+//! - `bytesize`: Implements the strategies of the `time` module for `ByteSize` values of the
+//!   `bytesize` crate.
+//! - `clap`: Enables the `bool::TriStateArgs` type for `--flag` and `--no-flag` command-line
+//!   options using the `clap` crate.  This feature requires the `std` feature.
 //! - `derive` (default):  Enables the derive macro for the `Merge` trait using the `conflate_derive`
-//!   crate.
+//!   crate.  The derive macros for the other traits are only available if the traits are enabled,
+//!   e.g. `DeserializeMerge` requires the `serde` feature.
+//! - `flags`: Enables the merge strategies in the `flags` module for the flag types of the
+//!   `bitflags` crate and implements the merge traits for `EnumSet` of the `enumset` crate.
+//! - `humantime`: Implements the strategies of the `time` module for `Duration` values of the
+//!   `humantime` crate.
+//! - `indexmap`: Enables the merge strategies in the `indexmap` module for the `IndexMap` type of
+//!   the `indexmap` crate that preserve the insertion order of the keys.  This feature requires
+//!   the `std` feature.
+//! - `interpolate`: Enables the `interpolate` module for resolving references to other fields and
+//!   environment variables after merging.  This feature requires the `std` feature.
 //! - `num` (default): Enables the merge strategies in the `num` module that require the
 //!   `num_traits` crate.
+//! - `serde`: Enables the `serde` module and the `DeserializeMerge` trait for deserializing values
+//!   on top of existing values using the `serde` crate.  This feature requires the `std` feature.
 //! - `std` (default): Enables the merge strategies in the `hashmap`, `btreemap` and `vec` modules
-//!    that require the standard library. If this feature is not set, `conflate` is a `no_std`
-//!    library.
+//!   and the `context`, `layers` and `preview` modules that require the standard library. If this
+//!   feature is not set, `conflate` is a `no_std` library.
+//! - `watch`: Enables the `watch` module for reloading a value when its layers change.  This
+//!   feature requires the `std` feature.
+//! - `zeroize`: Overwrites the memory of values wrapped in a `Secret` with zeros when they are
+//!   dropped using the `zeroize` crate.
 //!
 //! # Example
 //!
@@ -71,24 +105,78 @@
 //! ```
 //!
 //! [`Merge`]: trait.Merge.html
+//! [`MergeRef`]: trait.MergeRef.html
+//! [`MergePreview`]: preview/trait.MergePreview.html
+//! [`MergeChanged`]: changed/trait.MergeChanged.html
+//! [`MergeVisit`]: visit/trait.MergeVisit.html
+//! [`TryMerge`]: locked/trait.TryMerge.html
+//! [`Locked`]: locked/struct.Locked.html
+//! [`MergeTrusted`]: trust/trait.MergeTrusted.html
+//! [`MergeWithContext`]: context/trait.MergeWithContext.html
+//! [`MergeMeta`]: meta/trait.MergeMeta.html
 //! [`args.rs`]: https://git.sr.ht/~ireas/merge-rs/tree/master/examples/args.rs
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(all(feature = "derive", feature = "serde"))]
+pub use conflate_derive::DeserializeMerge;
+#[cfg(all(feature = "derive", feature = "interpolate"))]
+pub use conflate_derive::Interpolate;
 #[cfg(feature = "derive")]
-pub use conflate_derive::*;
+pub use conflate_derive::{Merge, MergeChanged, MergeMeta, MergeRef, MergeTrusted, TryMerge};
+#[cfg(all(feature = "derive", feature = "std"))]
+pub use conflate_derive::{MergePreview, MergeWithContext};
 
 pub mod bool;
 #[cfg(feature = "std")]
 pub mod btreemap;
+pub mod changed;
+#[cfg(feature = "std")]
+pub mod context;
+pub mod deprecated;
+#[cfg(feature = "flags")]
+pub mod flags;
 #[cfg(feature = "std")]
 pub mod hashmap;
+#[cfg(feature = "indexmap")]
+pub mod indexmap;
+#[cfg(feature = "interpolate")]
+pub mod interpolate;
+#[cfg(feature = "std")]
+pub mod layers;
+pub mod locked;
+pub mod meta;
 #[cfg(feature = "num")]
 pub mod num;
 pub mod option;
 pub mod ord;
+pub mod path;
+#[cfg(feature = "std")]
+pub mod preview;
+pub mod secret;
+#[cfg(feature = "serde")]
+pub mod serde;
+pub mod time;
+pub mod trust;
 #[cfg(feature = "std")]
 pub mod vec;
+pub mod visit;
+#[cfg(feature = "watch")]
+pub mod watch;
+
+pub use crate::changed::MergeChanged;
+#[cfg(feature = "std")]
+pub use crate::context::{MergeContext, MergeWithContext};
+pub use crate::locked::{Locked, TryMerge};
+pub use crate::meta::{FieldMeta, MergeMeta};
+pub use crate::path::FieldPath;
+#[cfg(feature = "std")]
+pub use crate::preview::MergePreview;
+pub use crate::secret::Secret;
+#[cfg(feature = "serde")]
+pub use crate::serde::DeserializeMerge;
+pub use crate::trust::MergeTrusted;
+pub use crate::visit::MergeVisit;
 
 /// A trait for objects that can be merged.
 ///
@@ -99,10 +187,60 @@ pub mod vec;
 /// You can use these field attributes to configure the generated implementation:
 /// - `skip`: Skip this field in the `merge` method.
 /// - `strategy = f`: Call `f(self.field, other.field)` instead of calling the `merge` function for
-///    this field.
+///   this field.  `f` can also be an expression that returns a strategy, for example
+///   `conflate::num::clamp(0, 100)`.
+/// - `lockable`: Merge this field of type [`Locked`][] using the strategy for the inner value,
+///   unless it is locked.
+/// - `skip_if = f`: Skip this field if `f(&self, &other.field)` returns true.
+/// - `only_if = f`: Only merge this field if `f(&self, &other.field)` returns true.
+/// - `deprecated_alias = old_field`: Move the value of the deprecated field `old_field` into this
+///   field before merging if this field is not set, and clear `old_field`, see the [`deprecated`][]
+///   module.
+/// - `min_trust = "..."`: Ignore this field when merging layers with a lower trust level using
+///   [`MergeTrusted`][].  `Merge::merge` does not check the trust level.
+///
+/// The `skip_if` and `only_if` predicates of all fields are evaluated before any field is merged,
+/// so they see the original value of `self`.
 ///
 /// You can also set a default strategy for all fields by setting the `strategy` attribute for the
-/// struct.
+/// struct.  To use different default strategies depending on the type of the fields, list them in
+/// a `strategy(...)` attribute, e.g. `strategy(Option = conflate::option::overwrite_none, Vec =
+/// conflate::vec::append)`.  A type-keyed default applies to all fields without a field strategy
+/// whose type has the given name, regardless of its generic arguments.  The type-keyed form is
+/// only accepted on the struct.
+///
+/// Instead of choosing the direction of every strategy, you can set the `precedence` attribute
+/// for the struct to `"left"` or `"right"`.  Fields of type `Option`, `HashMap`, `BTreeMap`,
+/// `IndexMap` and [`bool::TriState`][] without a field strategy then use a default strategy that
+/// keeps the value of the given side if both sides are set:
+///
+/// | Type       | `precedence = "left"`            | `precedence = "right"`              |
+/// |------------|----------------------------------|-------------------------------------|
+/// | `Option`   | [`option::overwrite_none`][]     | [`option::overwrite_with_some`][]   |
+/// | `HashMap`  | [`hashmap::append_or_ignore`][]  | [`hashmap::append_or_overwrite`][]  |
+/// | `BTreeMap` | [`btreemap::append_or_ignore`][] | [`btreemap::append_or_overwrite`][] |
+/// | `IndexMap` | `indexmap::append_or_ignore`     | `indexmap::append_or_overwrite`     |
+/// | `TriState` | [`bool::overwrite_unset`][]      | [`bool::overwrite_with_set`][]      |
+///
+/// A plain `bool` cannot tell whether a layer set it to `false` or did not set it at all, so
+/// `bool` fields use [`bool::overwrite_false`][] for both precedences: a value is `true` if one of
+/// the layers enables it.  Use `TriState` for values that a layer can explicitly disable.
+///
+/// If the `auto` attribute is set for the struct, `Vec` fields without a field strategy use
+/// [`vec::append`][] and `Option`, `HashMap`, `BTreeMap`, `IndexMap`, `TriState` and `bool` fields
+/// use the strategies listed above for the given precedence, or for `precedence = "left"` if it is
+/// not set.  Fields of other types, e.g. nested structs, are still merged by calling
+/// `Merge::merge`.
+///
+/// The type is determined from the last segment of its path, so type aliases are not recognized.
+/// Type-keyed defaults take precedence over these defaults, and both take precedence over the
+/// default strategy of the struct, so flipping the merge order of the layers only requires
+/// changing the `precedence` attribute.  All defaults are also used by the other derive macros of
+/// this crate, for example `MergeRef` and `MergeChanged`.
+///
+/// Fields without a strategy are merged by calling `Merge::merge`, so their type has to implement
+/// `Merge`.  If the `visit` attribute is set for the struct, the derive macro implements [`MergeVisit`][]
+/// as well.
 ///
 /// # Examples
 ///
@@ -173,11 +311,200 @@ pub mod vec;
 ///     option3: None,
 /// }, val);
 /// ```
+///
+/// Merging fields conditionally:
+///
+/// ```
+/// use conflate::Merge;
+///
+/// #[derive(Debug, PartialEq, Merge)]
+/// struct S {
+///     #[merge(strategy = conflate::bool::overwrite_false)]
+///     locked: bool,
+///
+///     #[merge(strategy = conflate::option::overwrite_with_some, skip_if = is_locked)]
+///     repository: Option<String>,
+/// }
+///
+/// fn is_locked(s: &S, _: &Option<String>) -> bool {
+///     s.locked
+/// }
+///
+/// let mut val = S {
+///     locked: true,
+///     repository: Some("/srv/backup".to_owned()),
+/// };
+///
+/// val.merge(S {
+///     locked: false,
+///     repository: Some("/tmp/backup".to_owned()),
+/// });
+///
+/// assert_eq!(Some("/srv/backup".to_owned()), val.repository);
+/// ```
+///
+/// Selecting the strategies automatically:
+///
+/// ```
+/// use conflate::Merge;
+///
+/// #[derive(Debug, PartialEq, Merge)]
+/// #[merge(auto)]
+/// struct S {
+///     option: Option<usize>,
+///     list: Vec<usize>,
+///     flag: bool,
+///     #[merge(strategy = conflate::option::overwrite_with_some)]
+///     latest: Option<usize>,
+/// }
+///
+/// let mut val = S {
+///     option: None,
+///     list: vec![1],
+///     flag: false,
+///     latest: Some(1),
+/// };
+///
+/// val.merge(S {
+///     option: Some(2),
+///     list: vec![2],
+///     flag: true,
+///     latest: Some(2),
+/// });
+///
+/// assert_eq!(S {
+///     option: Some(2),
+///     list: vec![1, 2],
+///     flag: true,
+///     latest: Some(2),
+/// }, val);
+/// ```
+///
+/// Setting default strategies by type:
+///
+/// ```
+/// use conflate::Merge;
+///
+/// #[derive(Debug, PartialEq, Merge)]
+/// #[merge(strategy(Option = conflate::option::overwrite_none, Vec = conflate::vec::append))]
+/// struct S {
+///     option: Option<usize>,
+///     list: Vec<usize>,
+/// }
+///
+/// let mut val = S {
+///     option: Some(1),
+///     list: vec![1],
+/// };
+///
+/// val.merge(S {
+///     option: Some(2),
+///     list: vec![2],
+/// });
+///
+/// assert_eq!(S {
+///     option: Some(1),
+///     list: vec![1, 2],
+/// }, val);
+/// ```
+///
+/// Setting the precedence:
+///
+/// ```
+/// use conflate::bool::TriState;
+/// use conflate::Merge;
+///
+/// #[derive(Debug, PartialEq, Merge)]
+/// #[merge(precedence = "right")]
+/// struct S {
+///     option: Option<usize>,
+///     flag: TriState,
+/// }
+///
+/// let mut defaults = S {
+///     option: Some(1),
+///     flag: TriState::On,
+/// };
+///
+/// defaults.merge(S {
+///     option: Some(2),
+///     flag: TriState::Off,
+/// });
+///
+/// assert_eq!(S {
+///     option: Some(2),
+///     flag: TriState::Off,
+/// }, defaults);
+/// ```
 pub trait Merge {
     /// Merge another object into this object.
     fn merge(&mut self, other: Self);
 }
 
+/// A trait for objects that can be merged from a borrowed object.
+///
+/// [`Merge::merge`][] takes the other object by value, so merging a shared value into many
+/// objects, e.g. a default configuration into the configurations of several repositories,
+/// requires cloning the whole shared value each time.  `MergeRef` instead only clones the parts
+/// of the other object that are actually taken.
+///
+/// # Deriving
+///
+/// `MergeRef` can be derived for structs if the `derive` feature is enabled.  The derive macro
+/// uses the same `merge` attributes as the `Merge` derive macro, with one addition:
+/// - `strategy_ref = f`: Call `f(&mut self.field, &other.field)` for this field.  The submodules
+///   of this crate provide borrowed versions of some strategies, for example
+///   [`option::overwrite_none_ref`][] and [`vec::append_cloned`][].
+///
+/// Fields with a `strategy` but without a `strategy_ref` attribute are cloned and merged using
+/// the strategy.  Fields without a strategy are merged by calling `MergeRef::merge_ref`.  As with
+/// `strategy`, a default `strategy_ref` can be set for all fields on the struct.
+///
+/// # Example
+///
+/// ```
+/// use conflate::MergeRef;
+///
+/// #[derive(Debug, PartialEq, MergeRef)]
+/// struct S {
+///     #[merge(strategy_ref = conflate::option::overwrite_none_ref)]
+///     option: Option<String>,
+///
+///     #[merge(strategy_ref = conflate::vec::append_cloned)]
+///     list: Vec<String>,
+///
+///     #[merge(strategy = conflate::bool::overwrite_false)]
+///     flag: bool,
+/// }
+///
+/// let defaults = S {
+///     option: Some("default".to_owned()),
+///     list: vec!["default".to_owned()],
+///     flag: true,
+/// };
+///
+/// let mut val = S {
+///     option: None,
+///     list: vec!["own".to_owned()],
+///     flag: false,
+/// };
+/// val.merge_ref(&defaults);
+///
+/// assert_eq!(S {
+///     option: Some("default".to_owned()),
+///     list: vec!["own".to_owned(), "default".to_owned()],
+///     flag: true,
+/// }, val);
+/// ```
+///
+/// [`Merge::merge`]: trait.Merge.html#tymethod.merge
+/// [`option::overwrite_none_ref`]: option/fn.overwrite_none_ref.html
+/// [`vec::append_cloned`]: vec/fn.append_cloned.html
+pub trait MergeRef {
+    /// Merge a borrowed object into this object.
+    fn merge_ref(&mut self, other: &Self);
+}
+
 /// A trait for objects that can be merged from another object
 /// of the same type creating a new object.
 ///
@@ -0,0 +1,435 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Values that cannot be overridden by later layers.
+//!
+//! Organization-wide configurations often contain values that users must not change.  A
+//! [`Locked`][] value can be marked as final by the layer that sets it.  Once a locked value has
+//! been merged, later merges leave it untouched.
+//!
+//! Locked fields can be merged using the [`Merge`][] implementation of the inner type or, if the
+//! field has the `lockable` attribute, using the merge strategy of the field.  The [`TryMerge`][]
+//! trait merges like [`Merge`][], but also reports the locked fields that the other value tried
+//! to change.
+//!
+//! Locking is only available through the API: configuration files cannot mark a value as locked,
+//! and deserialized `Locked` values are never locked.  To lock the values of a layer that is loaded
+//! from a file, e.g. an organization-wide configuration, call [`Locked::lock`][] on its fields after
+//! loading it.
+//!
+//! # Example
+//!
+//! ```
+//! use conflate::{Locked, Merge, TryMerge};
+//!
+//! #[derive(Debug, Merge, TryMerge)]
+//! struct Config {
+//!     #[merge(lockable, strategy = conflate::option::overwrite_with_some)]
+//!     repository: Locked<Option<String>>,
+//!
+//!     #[merge(strategy = conflate::ord::max)]
+//!     verbosity: u8,
+//! }
+//!
+//! let mut config = Config {
+//!     repository: Locked::new(None),
+//!     verbosity: 0,
+//! };
+//!
+//! // the organization-wide configuration locks the repository
+//! config.merge(Config {
+//!     repository: Locked::new_locked(Some("/srv/backup".to_owned())),
+//!     verbosity: 0,
+//! });
+//!
+//! // the user configuration cannot change it
+//! let err = config
+//!     .try_merge(Config {
+//!         repository: Locked::new(Some("/tmp/backup".to_owned())),
+//!         verbosity: 2,
+//!     })
+//!     .unwrap_err();
+//! assert_eq!(["repository"], err.paths());
+//! assert_eq!(Some("/srv/backup"), config.repository.as_deref());
+//! assert_eq!(2, config.verbosity);
+//! ```
+//!
+//! [`Locked`]: struct.Locked.html
+//! [`Locked::lock`]: struct.Locked.html#method.lock
+//! [`Merge`]: ../trait.Merge.html
+//! [`TryMerge`]: trait.TryMerge.html
+
+use core::ops::Deref;
+
+use crate::{FieldPath, Merge, MergeChanged, MergeMeta, MergeRef};
+
+/// A value that can be locked to prevent later merges from changing it.
+///
+/// The [`Merge`][] implementation ignores the other value if this value is locked.  Otherwise, it
+/// takes the other value as is if that value is locked, and merges the inner values if it is not.
+/// To merge the inner values using a strategy, use the `lockable` field attribute of the derive
+/// macros or the [`merge_with`][] function.
+///
+/// If the `serde` feature is enabled, `Locked` values are deserialized from the inner value and
+/// are not locked.  To lock the values of a layer that is loaded from a file, call [`lock`][] on
+/// them after loading it.
+///
+/// [`Merge`]: ../trait.Merge.html
+/// [`merge_with`]: fn.merge_with.html
+/// [`lock`]: #method.lock
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Locked<T> {
+    value: T,
+    locked: bool,
+}
+
+impl<T> Locked<T> {
+    /// Creates a value that is not locked.
+    pub const fn new(value: T) -> Self {
+        Self {
+            value,
+            locked: false,
+        }
+    }
+
+    /// Creates a locked value.
+    pub const fn new_locked(value: T) -> Self {
+        Self {
+            value,
+            locked: true,
+        }
+    }
+
+    /// Returns whether this value is locked.
+    pub const fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    /// Locks this value.
+    pub fn lock(&mut self) {
+        self.locked = true;
+    }
+
+    /// Returns a reference to the inner value.
+    pub const fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns the inner value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for Locked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> From<T> for Locked<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+/// Merge right into left using the given strategy for the inner values, unless left is locked.
+///
+/// If right is locked, left is replaced by right without applying the strategy, so a value is only
+/// locked if it was set by the layer that locked it.
+pub fn merge_with<T, F: FnOnce(&mut T, T)>(left: &mut Locked<T>, right: Locked<T>, strategy: F) {
+    if left.locked {
+        return;
+    }
+    if right.locked {
+        *left = right;
+    } else {
+        strategy(&mut left.value, right.value);
+    }
+}
+
+/// Merge right into left using the given strategy for the inner values, unless left is locked,
+/// and return whether right tried to change a locked value.
+///
+/// If left is locked, the inner values are merged into a copy of the left value to check whether
+/// the merge would have changed it.
+pub fn try_merge_with<T: Clone + PartialEq, F: FnOnce(&mut T, T)>(
+    left: &mut Locked<T>,
+    right: Locked<T>,
+    strategy: F,
+) -> bool {
+    if left.locked {
+        let mut value = left.value.clone();
+        strategy(&mut value, right.value);
+        value != left.value
+    } else {
+        merge_with(left, right, strategy);
+        false
+    }
+}
+
+impl<T: Merge> Merge for Locked<T> {
+    fn merge(&mut self, other: Self) {
+        merge_with(self, other, T::merge);
+    }
+}
+
+impl<T: MergeRef + Clone> MergeRef for Locked<T> {
+    fn merge_ref(&mut self, other: &Self) {
+        if self.locked {
+            return;
+        }
+        if other.locked {
+            self.clone_from(other);
+        } else {
+            self.value.merge_ref(&other.value);
+        }
+    }
+}
+
+impl<T: MergeChanged + PartialEq> MergeChanged for Locked<T> {
+    fn merge_changed_with(
+        &mut self,
+        other: Self,
+        path: &FieldPath<'_>,
+        changed: &mut dyn FnMut(&FieldPath<'_>),
+    ) {
+        if self.locked {
+            return;
+        }
+        if other.locked {
+            if self.value != other.value {
+                changed(path);
+            }
+            *self = other;
+        } else {
+            self.value.merge_changed_with(other.value, path, changed);
+        }
+    }
+}
+
+impl<T> MergeMeta for Locked<T> {}
+
+impl<T: crate::MergeTrusted + Clone> crate::MergeTrusted for Locked<T> {
+    /// Ignores the other value if this value is locked.
+    ///
+    /// A locked other value is only taken as is if the trust level allows setting all of its
+    /// fields.  Otherwise, the inner values are merged and this value is not locked.
+    fn merge_trusted_with(
+        &mut self,
+        other: Self,
+        trust: crate::trust::Trust,
+        path: &FieldPath<'_>,
+        rejected: &mut dyn FnMut(&FieldPath<'_>),
+    ) {
+        if self.locked {
+            return;
+        }
+        if other.locked {
+            let mut value = self.value.clone();
+            let mut accepted = true;
+            value.merge_trusted_with(other.value.clone(), trust, path, &mut |path| {
+                accepted = false;
+                rejected(path);
+            });
+            if accepted {
+                *self = other;
+            } else {
+                self.value = value;
+            }
+        } else {
+            self.value
+                .merge_trusted_with(other.value, trust, path, rejected);
+        }
+    }
+}
+
+impl<T: crate::MergeVisit> crate::MergeVisit for Locked<T> {
+    fn visit_with(&self, path: &FieldPath<'_>, visitor: &mut dyn crate::visit::Visitor) {
+        self.value.visit_with(path, visitor);
+    }
+
+    /// Visits the inner value, even if this value is locked.
+    fn visit_mut_with(&mut self, path: &FieldPath<'_>, visitor: &mut dyn crate::visit::VisitorMut) {
+        self.value.visit_mut_with(path, visitor);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: crate::MergePreview> crate::MergePreview for Locked<T> {
+    /// Returns no changes if this value is locked, and the differences to the other value if that
+    /// value is locked.
+    fn merge_preview(&self, other: &Self) -> Vec<crate::preview::Change> {
+        if self.locked {
+            Vec::new()
+        } else if other.locked {
+            self.value.diff(&other.value)
+        } else {
+            self.value.merge_preview(&other.value)
+        }
+    }
+
+    fn diff(&self, other: &Self) -> Vec<crate::preview::Change> {
+        self.value.diff(&other.value)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for Locked<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self::new)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: crate::DeserializeMerge> crate::DeserializeMerge for Locked<T> {
+    const FIELD_NAMES: &'static [crate::serde::FieldName] = T::FIELD_NAMES;
+
+    /// Ignores the input if this value is locked.
+    fn deserialize_merge<'de, D: serde::Deserializer<'de>>(
+        &mut self,
+        deserializer: D,
+    ) -> Result<(), D::Error> {
+        if self.locked {
+            <serde::de::IgnoredAny as serde::Deserialize>::deserialize(deserializer).map(|_| ())
+        } else {
+            self.value.deserialize_merge(deserializer)
+        }
+    }
+
+    /// Ignores the value of the field if this value is locked and the key is listed in the
+    /// [`FIELD_NAMES`][] table of the inner type.
+    ///
+    /// [`FIELD_NAMES`]: ../serde/trait.DeserializeMerge.html#associatedconstant.FIELD_NAMES
+    fn merge_field<'de, A: serde::de::MapAccess<'de>>(
+        &mut self,
+        key: &str,
+        map: &mut A,
+    ) -> Result<bool, A::Error> {
+        if !self.locked {
+            self.value.merge_field(key, map)
+        } else if has_key(T::FIELD_NAMES, key) {
+            let _: serde::de::IgnoredAny = map.next_value()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+fn has_key(fields: &[crate::serde::FieldName], key: &str) -> bool {
+    fields.iter().any(|field| {
+        if field.flatten {
+            field.nested.map_or(false, |nested| has_key(nested(), key))
+        } else {
+            field.key == Some(key) || field.aliases.contains(&key)
+        }
+    })
+}
+
+/// A trait for objects that can be merged while reporting attempts to change locked values.
+///
+/// # Deriving
+///
+/// `TryMerge` can be derived for structs if the `derive` feature is enabled.  The derive macro
+/// uses the same `merge` attributes as the `Merge` derive macro:
+/// - Fields with the `lockable` attribute must have the type [`Locked`][].  They are merged using
+///   [`try_merge_with`][] and the strategy of the field or, if the field has no strategy,
+///   `Merge::merge`, so the inner type has to implement `Clone` and `PartialEq`.
+/// - Fields with the `strategy_try = f` attribute are merged by calling
+///   `f(&mut self.field, other.field)`, which returns a `Result`, e.g. [`num::checked_add`][].  If
+///   it returns an error, the field is reported like a locked field.  As the `Merge` derive macro
+///   ignores this attribute, such fields usually also need an infallible `strategy`.
+/// - Other fields with a strategy are merged using the strategy.
+/// - Other fields without a strategy are merged by calling `TryMerge::try_merge_with`.
+///
+/// The field paths use the keys determined from the `serde` attributes of the struct, see
+/// [`FieldPath`][].
+///
+/// [`Locked`]: struct.Locked.html
+/// [`try_merge_with`]: fn.try_merge_with.html
+/// [`num::checked_add`]: ../num/fn.checked_add.html
+/// [`FieldPath`]: ../path/enum.FieldPath.html
+pub trait TryMerge {
+    /// Merge another object into this object, calling `overridden` with the path of each locked
+    /// field that the other object tried to change and of each field whose `strategy_try` failed.
+    ///
+    /// The paths of the fields are prefixed with `path`.
+    fn try_merge_with(
+        &mut self,
+        other: Self,
+        path: &FieldPath<'_>,
+        overridden: &mut dyn FnMut(&FieldPath<'_>),
+    );
+
+    /// Merge another object into this object and return an error if it tried to change locked
+    /// fields.
+    ///
+    /// The other fields are merged in any case, so the error can also be treated as a warning.
+    ///
+    /// This method is only available if the `std` feature is enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error with the paths of the locked fields that the other object tried to
+    /// change.
+    #[cfg(feature = "std")]
+    fn try_merge(&mut self, other: Self) -> Result<(), Error>
+    where
+        Self: Sized,
+    {
+        let mut paths = Vec::new();
+        self.try_merge_with(other, &FieldPath::Root, &mut |path| {
+            paths.push(path.to_string());
+        });
+        if paths.is_empty() {
+            Ok(())
+        } else {
+            Err(Error { paths })
+        }
+    }
+}
+
+impl<T: Merge + Clone + PartialEq> TryMerge for Locked<T> {
+    fn try_merge_with(
+        &mut self,
+        other: Self,
+        path: &FieldPath<'_>,
+        overridden: &mut dyn FnMut(&FieldPath<'_>),
+    ) {
+        if try_merge_with(self, other, T::merge) {
+            overridden(path);
+        }
+    }
+}
+
+/// The error returned by [`TryMerge::try_merge`][] if the other value tried to change locked
+/// fields or a fallible strategy failed.
+///
+/// [`TryMerge::try_merge`]: trait.TryMerge.html#method.try_merge
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    paths: Vec<String>,
+}
+
+#[cfg(feature = "std")]
+impl Error {
+    /// Returns the paths of the fields that could not be changed.
+    pub fn paths(&self) -> &[String] {
+        &self.paths
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cannot change fields: {}", self.paths.join(", "))
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
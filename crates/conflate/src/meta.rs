@@ -0,0 +1,124 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Reflection metadata for types that implement [`Merge`][].
+//!
+//! [`MergeMeta`][] can be derived for structs if the `derive` feature is enabled.  The derive macro
+//! reads the same `merge` attributes as the `Merge` derive macro and provides a table that
+//! describes how each field is merged.  It is separate from the `Merge` derive macro, so that
+//! deriving `Merge` does not require the field types to implement `MergeMeta`; add
+//! `#[derive(MergeMeta)]` next to `#[derive(Merge)]` to provide the table.  This can be used to generate documentation for a
+//! configuration, to explain the merge behavior to users or to write generic tooling without
+//! another procedural macro.
+//!
+//! # Example
+//!
+//! ```
+//! use conflate::{Merge, MergeMeta};
+//!
+//! #[derive(Merge, MergeMeta)]
+//! struct Repository {
+//!     /// The path of the repository.
+//!     #[merge(strategy = conflate::option::overwrite_none)]
+//!     path: Option<String>,
+//! }
+//!
+//! #[derive(Merge, MergeMeta)]
+//! struct Config {
+//!     /// The groups of the user.
+//!     #[merge(strategy = conflate::vec::append)]
+//!     groups: Vec<String>,
+//!
+//!     repository: Repository,
+//! }
+//!
+//! let groups = &Config::FIELDS[0];
+//! assert_eq!("groups", groups.name);
+//! assert_eq!("Vec<String>", groups.ty);
+//! assert_eq!(Some("conflate::vec::append"), groups.strategy);
+//! assert_eq!("The groups of the user.", groups.doc);
+//!
+//! let path = Config::field(&["repository", "path"]).unwrap();
+//! assert_eq!(Some("conflate::option::overwrite_none"), path.strategy);
+//! ```
+//!
+//! [`Merge`]: ../trait.Merge.html
+//! [`MergeMeta`]: trait.MergeMeta.html
+
+/// A trait for types that provide reflection metadata about their fields.
+///
+/// This trait can be derived for structs, see the [module documentation](index.html).  Fields
+/// that are merged by calling `Merge::merge`, i.e. fields without a strategy, link to the metadata
+/// of their type, so if a struct derives `MergeMeta`, these types have to implement `MergeMeta` as
+/// well.  For types with a handwritten `Merge` implementation, an empty implementation can be
+/// used:
+///
+/// ```
+/// use conflate::{Merge, MergeMeta};
+///
+/// struct Counter(usize);
+///
+/// impl Merge for Counter {
+///     fn merge(&mut self, other: Self) {
+///         self.0 += other.0;
+///     }
+/// }
+///
+/// impl MergeMeta for Counter {}
+/// ```
+pub trait MergeMeta {
+    /// The metadata of the fields of this type, in declaration order.
+    const FIELDS: &'static [FieldMeta] = &[];
+
+    /// Returns the metadata of the field at the given path of field names.
+    ///
+    /// Returns `None` if the path is empty or does not exist.
+    fn field(path: &[&str]) -> Option<&'static FieldMeta>
+    where
+        Self: Sized,
+    {
+        let (last, parents) = path.split_last()?;
+        let mut fields = Self::FIELDS;
+        for name in parents {
+            let field = fields.iter().find(|field| field.name == *name)?;
+            fields = field.nested?();
+        }
+        fields.iter().find(|field| field.name == *last)
+    }
+}
+
+/// The metadata of a single field of a type implementing [`MergeMeta`][].
+///
+/// [`MergeMeta`]: trait.MergeMeta.html
+#[derive(Clone, Copy, Debug)]
+pub struct FieldMeta {
+    /// The name of the field in Rust code, or its index for tuple structs.
+    pub name: &'static str,
+    /// The key of the field in serialized documents, or `None` if the field is flattened into its
+    /// parent or skipped by `serde`.
+    ///
+    /// The key respects the `rename` and `rename_all` attributes of `serde`.  It is determined
+    /// like the key of the `FIELD_NAMES` table of `DeserializeMerge`, so both tables always
+    /// agree.
+    pub key: Option<&'static str>,
+    /// The type of the field as written in the struct definition.
+    pub ty: &'static str,
+    /// The strategy used to merge the field as written in the `merge` attribute, or `None` if the
+    /// field is skipped or merged by calling `Merge::merge`.
+    pub strategy: Option<&'static str>,
+    /// Whether the field is skipped when merging.
+    pub skip: bool,
+    /// The documentation comment of the field.
+    pub doc: &'static str,
+    /// The metadata of the field type, if the field is merged by calling `Merge::merge`.
+    pub nested: Option<fn() -> &'static [FieldMeta]>,
+}
+
+impl FieldMeta {
+    /// Returns the metadata of the fields of the field type, or an empty slice if the field is not
+    /// merged recursively.
+    #[must_use]
+    pub fn fields(&self) -> &'static [Self] {
+        self.nested.map_or(&[], |nested| nested())
+    }
+}
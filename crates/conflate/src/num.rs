@@ -3,16 +3,252 @@
 
 //! Merge strategies for numeric types.
 //!
+//! Some strategies are parameterized: [`clamp`][], [`clamp_with`][], [`max_f_with`][] and
+//! [`min_f_with`][] return a strategy, so the call can be used as the `strategy` attribute of the
+//! derive macro, e.g. `#[merge(strategy = conflate::num::clamp(0, 100))]`.
+//!
+//! The floating-point strategies [`max_f`][] and [`min_f`][] can be used for `f32` and `f64`
+//! that don't implement `Ord` and therefore can't be merged using `ord::max` and `ord::min`.
+//!
 //! These strategies are only available if the `num` feature is enabled.
+//!
+//! [`clamp`]: fn.clamp.html
+//! [`clamp_with`]: fn.clamp_with.html
+//! [`max_f`]: fn.max_f.html
+//! [`max_f_with`]: fn.max_f_with.html
+//! [`min_f`]: fn.min_f.html
+//! [`min_f_with`]: fn.min_f_with.html
+
+use core::fmt;
 
 /// Set left to the saturated some of left and right.
 pub fn saturating_add<T: num_traits::SaturatingAdd>(left: &mut T, right: T) {
     *left = left.saturating_add(&right);
 }
 
+/// Set left to the saturated difference of left and right.
+pub fn saturating_sub<T: num_traits::SaturatingSub>(left: &mut T, right: T) {
+    *left = left.saturating_sub(&right);
+}
+
+/// Set left to the saturated product of left and right.
+pub fn saturating_mul<T: num_traits::SaturatingMul>(left: &mut T, right: T) {
+    *left = left.saturating_mul(&right);
+}
+
+/// Set left to the sum of left and right, or return an error and leave left unchanged if the sum
+/// overflows.
+///
+/// This is the fallible variant of [`saturating_add`][].  As it returns a `Result`, it cannot be
+/// used as the `strategy` attribute of the derive macros, but as the `strategy_try` attribute of
+/// the `TryMerge` derive macro, which reports the field if the sum overflows:
+///
+/// ```
+/// use conflate::{Merge, TryMerge};
+///
+/// #[derive(Debug, Merge, TryMerge)]
+/// struct Usage {
+///     #[merge(strategy = conflate::num::saturating_add, strategy_try = conflate::num::checked_add)]
+///     bytes: u8,
+/// }
+///
+/// let mut usage = Usage { bytes: 250 };
+/// let err = usage.try_merge(Usage { bytes: 10 }).unwrap_err();
+/// assert_eq!(["bytes"], err.paths());
+/// assert_eq!(250, usage.bytes);
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the sum of left and right overflows.
+///
+/// [`saturating_add`]: fn.saturating_add.html
+pub fn checked_add<T: num_traits::CheckedAdd>(left: &mut T, right: T) -> Result<(), Overflow> {
+    *left = left.checked_add(&right).ok_or(Overflow)?;
+    Ok(())
+}
+
 /// Overwrite left with right if the value of left is zero.
 pub fn overwrite_zero<T: num_traits::Zero>(left: &mut T, right: T) {
     if left.is_zero() {
         *left = right;
     }
 }
+
+/// Overwrite left with right if the value of left is the default value of its type.
+///
+/// Unlike [`overwrite_zero`][], this strategy can be used for types without a zero value.
+///
+/// [`overwrite_zero`]: fn.overwrite_zero.html
+pub fn overwrite_default<T: Default + PartialEq>(left: &mut T, right: T) {
+    if *left == T::default() {
+        *left = right;
+    }
+}
+
+/// Returns a strategy that overwrites left with right, clamped to the range `min..=max`.
+///
+/// # Panics
+///
+/// Panics if `min` is greater than `max`.
+///
+/// # Example
+///
+/// ```
+/// use conflate::Merge;
+///
+/// #[derive(Merge)]
+/// struct Config {
+///     #[merge(strategy = conflate::num::clamp(1, 10))]
+///     jobs: u8,
+/// }
+///
+/// let mut config = Config { jobs: 4 };
+/// config.merge(Config { jobs: 64 });
+/// assert_eq!(10, config.jobs);
+/// ```
+pub fn clamp<T: PartialOrd + Clone>(min: T, max: T) -> impl Fn(&mut T, T) {
+    clamp_with(min, max, |left: &mut T, right| *left = right)
+}
+
+/// Returns a strategy that merges right into left using the given strategy and clamps the result
+/// to the range `min..=max`.
+///
+/// # Panics
+///
+/// Panics if `min` is greater than `max`.
+pub fn clamp_with<T, F>(min: T, max: T, strategy: F) -> impl Fn(&mut T, T)
+where
+    T: PartialOrd + Clone,
+    F: Fn(&mut T, T),
+{
+    assert!(min <= max, "min must be less than or equal to max");
+    move |left, right| {
+        strategy(left, right);
+        if *left < min {
+            *left = min.clone();
+        } else if *left > max {
+            *left = max.clone();
+        }
+    }
+}
+
+/// How the floating-point strategies handle NaN values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NanPolicy {
+    /// Ignore NaN values, i.e. use the other value if one value is NaN.  The result is only NaN if
+    /// both values are NaN.
+    Ignore,
+    /// Propagate NaN values, i.e. the result is NaN if one of the values is NaN.
+    Propagate,
+}
+
+/// Set left to the maximum of left and right, ignoring NaN values.
+///
+/// This is the same as `max_f_with(NanPolicy::Ignore)` and behaves like `f64::max`.
+pub fn max_f<T: num_traits::Float>(left: &mut T, right: T) {
+    *left = left.max(right);
+}
+
+/// Set left to the minimum of left and right, ignoring NaN values.
+///
+/// This is the same as `min_f_with(NanPolicy::Ignore)` and behaves like `f64::min`.
+pub fn min_f<T: num_traits::Float>(left: &mut T, right: T) {
+    *left = left.min(right);
+}
+
+/// Returns a strategy that sets left to the maximum of left and right, handling NaN values
+/// according to the given policy.
+pub fn max_f_with<T: num_traits::Float>(nan: NanPolicy) -> impl Fn(&mut T, T) {
+    move |left, right| match nan {
+        NanPolicy::Propagate if left.is_nan() || right.is_nan() => *left = T::nan(),
+        _ => max_f(left, right),
+    }
+}
+
+/// Returns a strategy that sets left to the minimum of left and right, handling NaN values
+/// according to the given policy.
+pub fn min_f_with<T: num_traits::Float>(nan: NanPolicy) -> impl Fn(&mut T, T) {
+    move |left, right| match nan {
+        NanPolicy::Propagate if left.is_nan() || right.is_nan() => *left = T::nan(),
+        _ => min_f(left, right),
+    }
+}
+
+/// Set left to the saturated sum of left and right and return whether left was changed.
+pub fn saturating_add_changed<T: num_traits::SaturatingAdd + PartialEq>(
+    left: &mut T,
+    right: T,
+) -> bool {
+    let sum = left.saturating_add(&right);
+    if sum == *left {
+        false
+    } else {
+        *left = sum;
+        true
+    }
+}
+
+/// Set left to the saturated difference of left and right and return whether left was changed.
+pub fn saturating_sub_changed<T: num_traits::SaturatingSub + PartialEq>(
+    left: &mut T,
+    right: T,
+) -> bool {
+    let difference = left.saturating_sub(&right);
+    if difference == *left {
+        false
+    } else {
+        *left = difference;
+        true
+    }
+}
+
+/// Set left to the saturated product of left and right and return whether left was changed.
+pub fn saturating_mul_changed<T: num_traits::SaturatingMul + PartialEq>(
+    left: &mut T,
+    right: T,
+) -> bool {
+    let product = left.saturating_mul(&right);
+    if product == *left {
+        false
+    } else {
+        *left = product;
+        true
+    }
+}
+
+/// Overwrite left with right if the value of left is zero and return whether left was changed.
+pub fn overwrite_zero_changed<T: num_traits::Zero>(left: &mut T, right: T) -> bool {
+    if left.is_zero() && !right.is_zero() {
+        *left = right;
+        true
+    } else {
+        false
+    }
+}
+
+/// Overwrite left with right if the value of left is the default value of its type and return
+/// whether left was changed.
+pub fn overwrite_default_changed<T: Default + PartialEq>(left: &mut T, right: T) -> bool {
+    if *left == T::default() && right != *left {
+        *left = right;
+        true
+    } else {
+        false
+    }
+}
+
+/// The error returned by [`checked_add`][] if the sum overflows.
+///
+/// [`checked_add`]: fn.checked_add.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Overflow;
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("arithmetic overflow")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Overflow {}
@@ -28,3 +28,73 @@ pub fn recurse<T: crate::Merge>(left: &mut Option<T>, right: Option<T>) {
         }
     }
 }
+
+/// Overwrite the left value with a clone of the right value if the right value is `Some`.
+///
+/// This is the borrowed version of [`overwrite_with_some`][] for use with `MergeRef`.
+pub fn overwrite_with_some_ref<T: Clone>(left: &mut Option<T>, right: &Option<T>) {
+    if right.is_some() {
+        left.clone_from(right);
+    }
+}
+
+/// Overwrite `left` with a clone of `right` only if `left` is `None`.
+///
+/// This is the borrowed version of [`overwrite_none`][] for use with `MergeRef`.
+pub fn overwrite_none_ref<T: Clone>(left: &mut Option<T>, right: &Option<T>) {
+    if left.is_none() {
+        left.clone_from(right);
+    }
+}
+
+/// If both `left` and `right` are `Some`, recursively merge the two using `MergeRef`.
+/// Otherwise, fall back to `overwrite_none_ref`.
+///
+/// This is the borrowed version of [`recurse`][] for use with `MergeRef`.
+pub fn recurse_ref<T: crate::MergeRef + Clone>(left: &mut Option<T>, right: &Option<T>) {
+    if let Some(new) = right {
+        if let Some(original) = left {
+            original.merge_ref(new);
+        } else {
+            *left = Some(new.clone());
+        }
+    }
+}
+
+/// Overwrite the left value with the right value if the right value is `Some` and return whether
+/// the left value was changed.
+pub fn overwrite_with_some_changed<T: PartialEq>(left: &mut Option<T>, right: Option<T>) -> bool {
+    if right.is_some() && *left != right {
+        *left = right;
+        true
+    } else {
+        false
+    }
+}
+
+/// Overwrite `left` with `right` only if `left` is `None` and return whether `left` was changed.
+pub fn overwrite_none_changed<T>(left: &mut Option<T>, right: Option<T>) -> bool {
+    if left.is_none() && right.is_some() {
+        *left = right;
+        true
+    } else {
+        false
+    }
+}
+
+/// If both `left` and `right` are `Some`, recursively merge the two using `MergeChanged`.
+/// Otherwise, fall back to `overwrite_none_changed`.
+///
+/// Returns whether `left` was changed.
+pub fn recurse_changed<T: crate::MergeChanged>(left: &mut Option<T>, right: Option<T>) -> bool {
+    if let Some(new) = right {
+        if let Some(original) = left {
+            original.merge_changed(new)
+        } else {
+            *left = Some(new);
+            true
+        }
+    } else {
+        false
+    }
+}
@@ -2,6 +2,21 @@
 // SPDX-License-Identifier: Apache-2.0 or MIT
 
 //! Merge strategies for types that form a total order.
+//!
+//! Types that only implement `PartialOrd` can be merged using [`partial_max`][] and
+//! [`partial_min`][], and other types by comparing them with a key or a comparison function using
+//! [`max_by_key`][], [`min_by_key`][], [`max_by`][] and [`min_by`][].  These functions return a
+//! strategy, so the call can be used as the `strategy` attribute of the derive macro, e.g.
+//! `#[merge(strategy = conflate::ord::max_by_key(Retention::days))]`.
+//!
+//! If both values are equal, all strategies keep the left value.
+//!
+//! [`partial_max`]: fn.partial_max.html
+//! [`partial_min`]: fn.partial_min.html
+//! [`max_by_key`]: fn.max_by_key.html
+//! [`min_by_key`]: fn.min_by_key.html
+//! [`max_by`]: fn.max_by.html
+//! [`min_by`]: fn.min_by.html
 
 use core::cmp;
 
@@ -18,3 +33,125 @@ pub fn min<T: cmp::Ord>(left: &mut T, right: T) {
         *left = right;
     }
 }
+
+/// Set left to the maximum of left and right and return whether left was changed.
+pub fn max_changed<T: cmp::Ord>(left: &mut T, right: T) -> bool {
+    if cmp::Ord::cmp(left, &right) == cmp::Ordering::Less {
+        *left = right;
+        true
+    } else {
+        false
+    }
+}
+
+/// Set left to the minimum of left and right and return whether left was changed.
+pub fn min_changed<T: cmp::Ord>(left: &mut T, right: T) -> bool {
+    if cmp::Ord::cmp(left, &right) == cmp::Ordering::Greater {
+        *left = right;
+        true
+    } else {
+        false
+    }
+}
+
+/// Returns a strategy that sets left to the value with the maximum key.
+///
+/// # Example
+///
+/// ```
+/// use conflate::Merge;
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Retention {
+///     days: u32,
+///     name: &'static str,
+/// }
+///
+/// impl Retention {
+///     fn days(&self) -> u32 {
+///         self.days
+///     }
+/// }
+///
+/// #[derive(Merge)]
+/// struct Config {
+///     #[merge(strategy = conflate::ord::max_by_key(Retention::days))]
+///     retention: Retention,
+/// }
+///
+/// let mut config = Config {
+///     retention: Retention { days: 7, name: "week" },
+/// };
+/// config.merge(Config {
+///     retention: Retention { days: 30, name: "month" },
+/// });
+/// assert_eq!("month", config.retention.name);
+/// ```
+pub fn max_by_key<T, K: cmp::Ord, F: Fn(&T) -> K>(f: F) -> impl Fn(&mut T, T) {
+    max_by(move |left: &T, right: &T| f(left).cmp(&f(right)))
+}
+
+/// Returns a strategy that sets left to the value with the minimum key.
+pub fn min_by_key<T, K: cmp::Ord, F: Fn(&T) -> K>(f: F) -> impl Fn(&mut T, T) {
+    min_by(move |left: &T, right: &T| f(left).cmp(&f(right)))
+}
+
+/// Returns a strategy that sets left to the maximum of left and right with respect to the given
+/// comparison function.
+pub fn max_by<T, F: Fn(&T, &T) -> cmp::Ordering>(compare: F) -> impl Fn(&mut T, T) {
+    move |left, right| {
+        if compare(left, &right) == cmp::Ordering::Less {
+            *left = right;
+        }
+    }
+}
+
+/// Returns a strategy that sets left to the minimum of left and right with respect to the given
+/// comparison function.
+pub fn min_by<T, F: Fn(&T, &T) -> cmp::Ordering>(compare: F) -> impl Fn(&mut T, T) {
+    move |left, right| {
+        if compare(left, &right) == cmp::Ordering::Greater {
+            *left = right;
+        }
+    }
+}
+
+/// How [`partial_max`][] and [`partial_min`][] handle values that cannot be compared, e.g. NaN
+/// floating-point values.
+///
+/// [`partial_max`]: fn.partial_max.html
+/// [`partial_min`]: fn.partial_min.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Incomparable {
+    /// Keep the left value.
+    Keep,
+    /// Overwrite the left value with the right value.
+    Overwrite,
+}
+
+/// Returns a strategy that sets left to the maximum of left and right for types that only
+/// implement `PartialOrd`, handling incomparable values as specified.
+pub fn partial_max<T: PartialOrd>(incomparable: Incomparable) -> impl Fn(&mut T, T) {
+    move |left: &mut T, right| match (*left).partial_cmp(&right) {
+        Some(cmp::Ordering::Less) => *left = right,
+        Some(_) => {}
+        None => merge_incomparable(left, right, incomparable),
+    }
+}
+
+/// Returns a strategy that sets left to the minimum of left and right for types that only
+/// implement `PartialOrd`, handling incomparable values as specified.
+pub fn partial_min<T: PartialOrd>(incomparable: Incomparable) -> impl Fn(&mut T, T) {
+    move |left: &mut T, right| match (*left).partial_cmp(&right) {
+        Some(cmp::Ordering::Greater) => *left = right,
+        Some(_) => {}
+        None => merge_incomparable(left, right, incomparable),
+    }
+}
+
+fn merge_incomparable<T>(left: &mut T, right: T, incomparable: Incomparable) {
+    match incomparable {
+        Incomparable::Keep => {}
+        Incomparable::Overwrite => *left = right,
+    }
+}
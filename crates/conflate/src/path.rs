@@ -0,0 +1,62 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Paths of fields in nested structs.
+//!
+//! A [`FieldPath`][] is built on the stack while recursing into nested structs, so it does not
+//! require an allocation and is available without the `std` feature.  Use its `Display`
+//! implementation to get the dot-separated path, e.g. `repository.path`.
+//!
+//! [`FieldPath`]: enum.FieldPath.html
+
+use core::fmt;
+
+/// The path of a field, consisting of the keys of the field and all of its parents.
+///
+/// Flattened fields do not add a key to the path, so the keys are the same as in the serialized
+/// document.
+#[derive(Clone, Copy, Debug)]
+pub enum FieldPath<'a> {
+    /// The empty path of the outermost value.
+    Root,
+    /// The field with the given key in the value at the parent path.
+    Field {
+        /// The path of the value containing the field.
+        parent: &'a FieldPath<'a>,
+        /// The key of the field.
+        key: &'a str,
+    },
+}
+
+impl<'a> FieldPath<'a> {
+    /// Returns the path of the field with the given key in the value at this path.
+    #[must_use]
+    pub const fn join(&'a self, key: &'a str) -> Self {
+        Self::Field { parent: self, key }
+    }
+
+    /// Returns whether this is the empty path.
+    #[must_use]
+    pub const fn is_root(&self) -> bool {
+        matches!(self, Self::Root)
+    }
+
+    /// Returns the key of the last field in this path, or `None` for the empty path.
+    #[must_use]
+    pub const fn key(&self) -> Option<&'a str> {
+        match self {
+            Self::Root => None,
+            Self::Field { key, .. } => Some(key),
+        }
+    }
+}
+
+impl fmt::Display for FieldPath<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Root => Ok(()),
+            Self::Field { parent, key } if parent.is_root() => f.write_str(key),
+            Self::Field { parent, key } => write!(f, "{parent}.{key}"),
+        }
+    }
+}
@@ -0,0 +1,129 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Preview the effects of a merge without performing it.
+//!
+//! The [`MergePreview`][] trait reports which fields a merge would change, together with their
+//! old and new values, without mutating either value.  This can be used to show a dry run to the
+//! user before writing a merged configuration back to disk.
+//!
+//! These items are only available if the `std` feature is enabled.
+//!
+//! # Example
+//!
+//! ```
+//! use conflate::{Merge, MergePreview};
+//!
+//! #[derive(Merge, MergePreview)]
+//! struct Repository {
+//!     #[merge(strategy = conflate::option::overwrite_none)]
+//!     path: Option<String>,
+//! }
+//!
+//! #[derive(Merge, MergePreview)]
+//! struct Config {
+//!     #[merge(strategy = conflate::vec::append)]
+//!     groups: Vec<String>,
+//!
+//!     repository: Repository,
+//! }
+//!
+//! let config = Config {
+//!     groups: vec!["rust".to_owned()],
+//!     repository: Repository { path: None },
+//! };
+//! let layer = Config {
+//!     groups: vec![],
+//!     repository: Repository { path: Some("/srv/backup".to_owned()) },
+//! };
+//!
+//! let changes = config.merge_preview(&layer);
+//! assert_eq!(1, changes.len());
+//! assert_eq!("repository.path", changes[0].path);
+//! assert_eq!("None", changes[0].old);
+//! assert_eq!(r#"Some("/srv/backup")"#, changes[0].new);
+//! ```
+//!
+//! [`MergePreview`]: trait.MergePreview.html
+
+use std::fmt;
+
+/// A trait for objects that can report the effects of a merge without performing it.
+///
+/// # Deriving
+///
+/// `MergePreview` can be derived for structs if the `derive` feature is enabled.  The derive
+/// macro uses the same `merge` attributes as the `Merge` and [`MergeRef`][] derive macros, so the
+/// preview is consistent with the strategy of each field:
+/// - Fields with a strategy are cloned, merged using the strategy (or `strategy_ref`, if set) and
+///   compared to the current value.  Their type has to implement `Clone`, `Debug` and
+///   `PartialEq`.
+/// - Fields without a strategy are previewed recursively, so their type has to implement
+///   `MergePreview` as well.
+/// - Skipped fields are never reported.
+///
+/// The derived [`diff`][] implementation compares the fields with a strategy using `PartialEq`
+/// and the other fields recursively.
+///
+/// The field paths use the keys determined from the `serde` attributes of the struct, see
+/// [`DeserializeMerge`][].
+///
+/// [`MergeRef`]: ../trait.MergeRef.html
+/// [`diff`]: #tymethod.diff
+/// [`DeserializeMerge`]: ../serde/index.html
+pub trait MergePreview {
+    /// Returns the changes that merging `other` into this object would make, in field order.
+    ///
+    /// Neither this object nor `other` is modified.
+    fn merge_preview(&self, other: &Self) -> Vec<Change>;
+
+    /// Returns the differences between this object and `other`, in field order.
+    ///
+    /// Unlike [`merge_preview`][], this compares the fields directly, regardless of their merge
+    /// strategy.  The old values are taken from this object, the new values from `other`.
+    /// Skipped fields are not compared.
+    ///
+    /// [`merge_preview`]: #tymethod.merge_preview
+    fn diff(&self, other: &Self) -> Vec<Change>;
+}
+
+/// A change of a single field reported by [`MergePreview::merge_preview`][].
+///
+/// [`MergePreview::merge_preview`]: trait.MergePreview.html#tymethod.merge_preview
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    /// The dot-separated path of the changed field.
+    pub path: String,
+    /// The `Debug` representation of the current value.
+    pub old: String,
+    /// The `Debug` representation of the value after the merge.
+    pub new: String,
+}
+
+impl Change {
+    /// Creates a change for the field with the given path from its old and new value.
+    pub fn new<T: fmt::Debug + ?Sized>(path: impl Into<String>, old: &T, new: &T) -> Self {
+        Self {
+            path: path.into(),
+            old: format!("{old:?}"),
+            new: format!("{new:?}"),
+        }
+    }
+
+    /// Prepends the key of a parent field to the path of this change.
+    ///
+    /// An empty key, as used for flattened fields, leaves the path unchanged.
+    #[must_use]
+    pub fn with_parent(mut self, key: &str) -> Self {
+        if !key.is_empty() {
+            self.path = format!("{key}.{}", self.path);
+        }
+        self
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} -> {}", self.path, self.old, self.new)
+    }
+}
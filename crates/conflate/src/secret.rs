@@ -0,0 +1,248 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! A wrapper for secret values like passwords.
+//!
+//! Configuration values like passwords are merged like all other values, but they should never
+//! end up in log output.  [`Secret`][] wraps such a value and redacts it in its `Debug` and
+//! `Display` output, so printing a merged configuration or a [`MergePreview`][] diff never shows
+//! the secret value.
+//!
+//! If the `zeroize` feature is enabled, the memory of the wrapped value is overwritten with zeros
+//! when the `Secret` is dropped, see [`SecretValue`][].
+//!
+//! # Example
+//!
+//! ```
+//! use conflate::{Merge, Secret};
+//!
+//! #[derive(Debug, Merge)]
+//! #[merge(strategy = conflate::option::overwrite_none)]
+//! struct Config {
+//!     user: Option<String>,
+//!     password: Option<Secret<String>>,
+//! }
+//!
+//! let mut config = Config {
+//!     user: Some("ferris".to_owned()),
+//!     password: None,
+//! };
+//! config.merge(Config {
+//!     user: None,
+//!     password: Some(Secret::new("hunter2".to_owned())),
+//! });
+//!
+//! assert_eq!("hunter2", config.password.as_ref().unwrap().expose());
+//! assert_eq!(
+//!     r#"Config { user: Some("ferris"), password: Some(<redacted>) }"#,
+//!     format!("{:?}", config),
+//! );
+//! ```
+//!
+//! [`Secret`]: struct.Secret.html
+//! [`SecretValue`]: trait.SecretValue.html
+//! [`MergePreview`]: ../preview/trait.MergePreview.html
+
+use core::fmt;
+
+use crate::{FieldPath, Merge, MergeChanged, MergeMeta, MergeRef};
+
+/// The text that is printed instead of a secret value.
+pub const REDACTED: &str = "<redacted>";
+
+/// A value that is never printed.
+///
+/// The `Debug` and `Display` implementations print `<redacted>` instead of the value.  Use
+/// [`expose`][] to access the value.
+///
+/// `Secret` implements the merge traits of this crate if the wrapped type implements them, so a
+/// struct with secret fields can be wrapped as a whole.  Single secret values are usually stored
+/// as `Option<Secret<T>>` and merged using the strategies of the `option` module.
+///
+/// [`expose`]: #method.expose
+pub struct Secret<T: SecretValue>(T);
+
+impl<T: SecretValue> Secret<T> {
+    /// Wraps a secret value.
+    pub const fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// Returns a reference to the secret value.
+    pub const fn expose(&self) -> &T {
+        &self.0
+    }
+
+    /// Returns a mutable reference to the secret value.
+    pub fn expose_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: SecretValue> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.0.wipe();
+    }
+}
+
+impl<T: SecretValue> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: SecretValue + Clone> Clone for Secret<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.0.clone())
+    }
+}
+
+impl<T: SecretValue + Default> Default for Secret<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: SecretValue + PartialEq> PartialEq for Secret<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T: SecretValue + Eq> Eq for Secret<T> {}
+
+impl<T: SecretValue> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T: SecretValue> fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T: SecretValue + Merge + Default> Merge for Secret<T> {
+    fn merge(&mut self, mut other: Self) {
+        self.0.merge(core::mem::take(&mut other.0));
+    }
+}
+
+impl<T: SecretValue + MergeRef> MergeRef for Secret<T> {
+    fn merge_ref(&mut self, other: &Self) {
+        self.0.merge_ref(&other.0);
+    }
+}
+
+impl<T: SecretValue + MergeChanged + Default> MergeChanged for Secret<T> {
+    fn merge_changed_with(
+        &mut self,
+        mut other: Self,
+        path: &FieldPath<'_>,
+        changed: &mut dyn FnMut(&FieldPath<'_>),
+    ) {
+        self.0
+            .merge_changed_with(core::mem::take(&mut other.0), path, changed);
+    }
+}
+
+impl<T: SecretValue> MergeMeta for Secret<T> {}
+
+#[cfg(feature = "std")]
+impl<T: SecretValue + crate::MergePreview> crate::MergePreview for Secret<T> {
+    fn merge_preview(&self, other: &Self) -> Vec<crate::preview::Change> {
+        redact(self.0.merge_preview(&other.0))
+    }
+
+    fn diff(&self, other: &Self) -> Vec<crate::preview::Change> {
+        redact(self.0.diff(&other.0))
+    }
+}
+
+#[cfg(feature = "std")]
+fn redact(mut changes: Vec<crate::preview::Change>) -> Vec<crate::preview::Change> {
+    for change in &mut changes {
+        change.old = REDACTED.to_owned();
+        change.new = REDACTED.to_owned();
+    }
+    changes
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: SecretValue + serde::Deserialize<'de>> serde::Deserialize<'de> for Secret<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self::new)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: SecretValue + crate::DeserializeMerge> crate::DeserializeMerge for Secret<T> {
+    const FIELD_NAMES: &'static [crate::serde::FieldName] = T::FIELD_NAMES;
+
+    fn merge_field<'de, A: serde::de::MapAccess<'de>>(
+        &mut self,
+        key: &str,
+        map: &mut A,
+    ) -> Result<bool, A::Error> {
+        self.0.merge_field(key, map)
+    }
+}
+
+/// A type that can be wrapped in a [`Secret`][].
+///
+/// [`wipe`][] is called when the `Secret` is dropped.  If the `zeroize` feature is enabled, the
+/// implementations for the types in the standard library overwrite the memory of the value with
+/// zeros using the `zeroize` crate.  Otherwise, they do nothing.
+///
+/// To wrap other types in a `Secret`, implement this trait for them.  The default implementation
+/// of `wipe` does nothing:
+///
+/// ```
+/// use conflate::secret::{Secret, SecretValue};
+///
+/// struct Token([u8; 16]);
+///
+/// impl SecretValue for Token {
+///     fn wipe(&mut self) {
+///         self.0 = [0; 16];
+///     }
+/// }
+///
+/// let token = Secret::new(Token([1; 16]));
+/// ```
+///
+/// [`Secret`]: struct.Secret.html
+/// [`wipe`]: #method.wipe
+pub trait SecretValue {
+    /// Erases the value before it is dropped.
+    fn wipe(&mut self) {}
+}
+
+macro_rules! impl_secret_value {
+    ($($ty:ty),*) => {
+        $(
+            impl SecretValue for $ty {
+                fn wipe(&mut self) {
+                    #[cfg(feature = "zeroize")]
+                    zeroize::Zeroize::zeroize(self);
+                }
+            }
+        )*
+    };
+}
+
+impl_secret_value!(
+    bool, char, f32, f64, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize
+);
+
+#[cfg(feature = "std")]
+impl_secret_value!(String, Vec<u8>);
+
+impl<T: SecretValue> SecretValue for Option<T> {
+    fn wipe(&mut self) {
+        if let Some(value) = self {
+            value.wipe();
+        }
+    }
+}
@@ -0,0 +1,241 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Deserialize values directly on top of existing values.
+//!
+//! Deserializing a whole layer into a struct and merging it afterwards loses the distinction
+//! between a key that is absent and a key that is set to its default value.  The
+//! [`DeserializeMerge`][] trait instead deserializes a document on top of an existing value and
+//! only touches the fields whose keys are present in the input.  Each present field is merged
+//! using the strategy configured for the [`Merge`][] derive, so the result is the same as merging
+//! a layer that only contains the present keys.
+//!
+//! The derived implementation respects the `serde` attributes that change the keys of a struct,
+//! namely `rename`, `rename_all`, `alias`, `skip`, `skip_deserializing`, `flatten` and
+//! `deny_unknown_fields`.  The resulting key names are exposed in the
+//! [`DeserializeMerge::FIELD_NAMES`][] table, so that field paths can be reported using the names
+//! that users write in their configuration files, see [`DeserializeMerge::field_path`][].
+//!
+//! These items are only available if the `serde` feature is enabled.
+//!
+//! # Example
+//!
+//! ```
+//! use conflate::{DeserializeMerge, Merge};
+//!
+//! #[derive(Debug, PartialEq, DeserializeMerge, Merge)]
+//! struct Config {
+//!     #[merge(strategy = conflate::option::overwrite_with_some)]
+//!     repository: Option<String>,
+//!
+//!     #[merge(strategy = conflate::bool::overwrite_false)]
+//!     dry_run: bool,
+//!
+//!     #[merge(strategy = conflate::vec::append)]
+//!     tags: Vec<String>,
+//! }
+//!
+//! let mut config = Config {
+//!     repository: Some("/srv/backup".to_owned()),
+//!     dry_run: false,
+//!     tags: vec!["base".to_owned()],
+//! };
+//!
+//! let mut deserializer = serde_json::Deserializer::from_str(r#"{ "tags": ["extra"] }"#);
+//! config.deserialize_merge(&mut deserializer).unwrap();
+//!
+//! assert_eq!(Config {
+//!     repository: Some("/srv/backup".to_owned()),
+//!     dry_run: false,
+//!     tags: vec!["base".to_owned(), "extra".to_owned()],
+//! }, config);
+//! ```
+//!
+//! [`Merge`]: ../trait.Merge.html
+//! [`DeserializeMerge`]: trait.DeserializeMerge.html
+//! [`DeserializeMerge::FIELD_NAMES`]: trait.DeserializeMerge.html#associatedconstant.FIELD_NAMES
+//! [`DeserializeMerge::field_path`]: trait.DeserializeMerge.html#method.field_path
+
+use core::fmt;
+
+use ::serde::de::{DeserializeSeed, Deserializer, IgnoredAny, MapAccess, Visitor};
+
+/// A trait for objects that can be deserialized on top of an existing value.
+///
+/// # Deriving
+///
+/// `DeserializeMerge` can be derived for structs with named fields if the `derive` feature is
+/// enabled.  The generated implementation respects the same field attributes as the `Merge`
+/// derive:
+/// - Fields with a strategy (or a default strategy set on the struct) are deserialized from the
+///   input and merged into the existing value using that strategy.
+/// - Fields without a strategy are deserialized on top of the existing value recursively, so
+///   their type has to implement `DeserializeMerge` as well.
+/// - Fields with a `skip_if` or `only_if` predicate are deserialized, and the predicate is called
+///   with the existing value and the deserialized field value.  These fields must have a
+///   strategy.  As for `Merge`, all predicates are evaluated after the whole map has been read
+///   and before any field with a strategy is merged, so they do not depend on the order of the
+///   keys.  Nested fields are merged while the map is read, and if the struct is flattened into
+///   another struct, the predicates are evaluated when the key of the field is read.
+/// - The key of a field named by a `deprecated_alias` attribute is merged into the field that
+///   replaces it, unless the key of the new field is present as well.
+/// - Skipped fields and unknown keys are ignored.
+///
+/// The keys of the fields are determined from the `serde` attributes of the struct and its fields,
+/// see the [module documentation](index.html).  Flattened fields are always deserialized
+/// recursively and cannot have a merge strategy.
+///
+/// # Example
+///
+/// ```
+/// use conflate::{DeserializeMerge, Merge};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize, DeserializeMerge, Merge)]
+/// #[serde(rename_all = "kebab-case")]
+/// struct Repository {
+///     #[merge(strategy = conflate::option::overwrite_with_some)]
+///     password_file: Option<String>,
+/// }
+///
+/// #[derive(Deserialize, DeserializeMerge, Merge)]
+/// struct Config {
+///     #[serde(rename = "repo")]
+///     repository: Repository,
+/// }
+///
+/// assert_eq!(
+///     Some("repo.password-file".to_owned()),
+///     Config::field_path(&["repository", "password_file"]),
+/// );
+/// ```
+pub trait DeserializeMerge {
+    /// The names of the fields of this object.
+    ///
+    /// The table is generated by the derive macro and empty for manual implementations.
+    const FIELD_NAMES: &'static [FieldName] = &[];
+
+    /// Deserialize a map from `deserializer` and merge it into this object.
+    fn deserialize_merge<'de, D>(&mut self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+        Self: Sized,
+    {
+        deserializer.deserialize_map(MergeVisitor(self))
+    }
+
+    /// Merge all entries of `map` into this object.
+    ///
+    /// The default implementation calls [`merge_field`][] for every key and ignores the values of
+    /// unknown keys.  The derive macro overrides it for structs that need to read all entries
+    /// before merging the fields, see the [trait documentation](#deriving).
+    ///
+    /// [`merge_field`]: #tymethod.merge_field
+    fn merge_map<'de, A>(&mut self, mut map: A) -> Result<(), A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        while let Some(key) = map.next_key::<String>()? {
+            if !self.merge_field(&key, &mut map)? {
+                let _: IgnoredAny = map.next_value()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Deserialize the next value of `map` into the field with the given `key`.
+    ///
+    /// Returns `false` if this object does not have a field with that key.  In that case, the
+    /// value has not been consumed from `map`.
+    fn merge_field<'de, A>(&mut self, key: &str, map: &mut A) -> Result<bool, A::Error>
+    where
+        A: MapAccess<'de>;
+
+    /// Translate a path of Rust field identifiers into the dot-separated path of keys used in
+    /// serialized documents.
+    ///
+    /// Returns `None` if the path does not exist or if it contains a field that is skipped during
+    /// deserialization.  Flattened fields do not contribute a key to the path.
+    fn field_path(path: &[&str]) -> Option<String>
+    where
+        Self: Sized,
+    {
+        let mut fields = Self::FIELD_NAMES;
+        let mut keys = Vec::with_capacity(path.len());
+        for (i, ident) in path.iter().enumerate() {
+            let field = fields.iter().find(|field| field.ident == *ident)?;
+            if let Some(key) = field.key {
+                keys.push(key);
+            } else if !field.flatten {
+                return None;
+            }
+            fields = match field.nested {
+                Some(nested) => nested(),
+                None if i + 1 < path.len() => return None,
+                None => &[],
+            };
+        }
+        Some(keys.join("."))
+    }
+}
+
+/// The name of a field in the [`DeserializeMerge::FIELD_NAMES`][] table.
+///
+/// [`DeserializeMerge::FIELD_NAMES`]: trait.DeserializeMerge.html#associatedconstant.FIELD_NAMES
+#[derive(Clone, Copy, Debug)]
+pub struct FieldName {
+    /// The identifier of the field in Rust code.
+    pub ident: &'static str,
+    /// The key of the field in serialized documents, or `None` if the field is skipped during
+    /// deserialization or flattened into its parent.
+    pub key: Option<&'static str>,
+    /// The aliases that are accepted for the key of the field.
+    pub aliases: &'static [&'static str],
+    /// Whether the keys of the field are flattened into its parent.
+    pub flatten: bool,
+    /// The field names of the field type, if the field is deserialized recursively.
+    pub nested: Option<fn() -> &'static [FieldName]>,
+}
+
+/// A [`DeserializeSeed`][] that deserializes a value on top of an existing value.
+///
+/// This can be used to merge a nested value from a [`MapAccess`][] or [`SeqAccess`][].
+///
+/// [`DeserializeSeed`]: https://docs.rs/serde/latest/serde/de/trait.DeserializeSeed.html
+/// [`MapAccess`]: https://docs.rs/serde/latest/serde/de/trait.MapAccess.html
+/// [`SeqAccess`]: https://docs.rs/serde/latest/serde/de/trait.SeqAccess.html
+#[derive(Debug)]
+pub struct MergeSeed<'a, T>(pub &'a mut T);
+
+impl<'de, T: DeserializeMerge> DeserializeSeed<'de> for MergeSeed<'_, T> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.0.deserialize_merge(deserializer)
+    }
+}
+
+struct MergeVisitor<'a, T>(&'a mut T);
+
+impl<'de, T: DeserializeMerge> Visitor<'de> for MergeVisitor<'_, T> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a map")
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        self.0.merge_map(map)
+    }
+}
+
+#[doc(hidden)]
+pub mod __private {
+    pub use ::serde::de::{Error, IgnoredAny, MapAccess};
+}
@@ -0,0 +1,178 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Merge strategies for durations and other quantities like byte sizes.
+//!
+//! The strategies of this module are implemented for all types that implement [`Quantity`][]:
+//! - `core::time::Duration`
+//! - `humantime::Duration` if the `humantime` feature is enabled
+//! - `bytesize::ByteSize` if the `bytesize` feature is enabled
+//!
+//! The `humantime` and `bytesize` types can be parsed from human-readable strings like `"1h 30m"`
+//! or `"512 MiB"`, so layers can be loaded from configuration files and merged on the parsed
+//! values.  [`max`][] and [`min`][] have the same semantics as `ord::max` and `ord::min`, even for
+//! `humantime::Duration` that does not implement `Ord`.  If the `serde` feature is enabled as
+//! well, `ByteSize` values can be deserialized from such strings.
+//!
+//! # Example
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use conflate::Merge;
+//!
+//! #[derive(Merge)]
+//! struct Config {
+//!     #[merge(strategy = conflate::time::max)]
+//!     retention: Duration,
+//!     #[merge(strategy = conflate::time::overwrite_zero)]
+//!     timeout: Duration,
+//! }
+//!
+//! let mut config = Config {
+//!     retention: Duration::from_secs(3600),
+//!     timeout: Duration::ZERO,
+//! };
+//! config.merge(Config {
+//!     retention: Duration::from_secs(60),
+//!     timeout: Duration::from_secs(10),
+//! });
+//! assert_eq!(Duration::from_secs(3600), config.retention);
+//! assert_eq!(Duration::from_secs(10), config.timeout);
+//! ```
+//!
+//! [`Quantity`]: trait.Quantity.html
+//! [`max`]: fn.max.html
+//! [`min`]: fn.min.html
+
+use core::cmp::Ordering;
+use core::time::Duration;
+
+/// A quantity like a duration or a size that can be merged using the strategies of this module.
+pub trait Quantity: Copy {
+    /// Returns the zero value of this type.
+    fn zero() -> Self;
+
+    /// Compares this value with another value.
+    fn compare(&self, other: &Self) -> Ordering;
+
+    /// Returns the sum of this value and another value, saturating at the maximum value.
+    #[must_use]
+    fn saturating_add(self, other: Self) -> Self;
+
+    /// Returns whether this value is zero.
+    fn is_zero(&self) -> bool {
+        self.compare(&Self::zero()) == Ordering::Equal
+    }
+}
+
+impl Quantity for Duration {
+    fn zero() -> Self {
+        Self::from_secs(0)
+    }
+
+    fn compare(&self, other: &Self) -> Ordering {
+        self.cmp(other)
+    }
+
+    fn saturating_add(self, other: Self) -> Self {
+        Self::saturating_add(self, other)
+    }
+}
+
+#[cfg(feature = "humantime")]
+impl Quantity for humantime::Duration {
+    fn zero() -> Self {
+        Duration::from_secs(0).into()
+    }
+
+    fn compare(&self, other: &Self) -> Ordering {
+        Duration::compare(self, other)
+    }
+
+    fn saturating_add(self, other: Self) -> Self {
+        Duration::saturating_add(*self, *other).into()
+    }
+}
+
+#[cfg(feature = "bytesize")]
+impl Quantity for bytesize::ByteSize {
+    fn zero() -> Self {
+        Self(0)
+    }
+
+    fn compare(&self, other: &Self) -> Ordering {
+        self.cmp(other)
+    }
+
+    fn saturating_add(self, other: Self) -> Self {
+        Self(self.0.saturating_add(other.0))
+    }
+}
+
+/// Set left to the maximum of left and right.
+pub fn max<T: Quantity>(left: &mut T, right: T) {
+    if left.compare(&right) == Ordering::Less {
+        *left = right;
+    }
+}
+
+/// Set left to the minimum of left and right.
+pub fn min<T: Quantity>(left: &mut T, right: T) {
+    if left.compare(&right) == Ordering::Greater {
+        *left = right;
+    }
+}
+
+/// Set left to the saturated sum of left and right.
+pub fn saturating_add<T: Quantity>(left: &mut T, right: T) {
+    *left = Quantity::saturating_add(*left, right);
+}
+
+/// Overwrite left with right if left is zero.
+pub fn overwrite_zero<T: Quantity>(left: &mut T, right: T) {
+    if left.is_zero() {
+        *left = right;
+    }
+}
+
+/// Set left to the maximum of left and right and return whether left was changed.
+pub fn max_changed<T: Quantity>(left: &mut T, right: T) -> bool {
+    if left.compare(&right) == Ordering::Less {
+        *left = right;
+        true
+    } else {
+        false
+    }
+}
+
+/// Set left to the minimum of left and right and return whether left was changed.
+pub fn min_changed<T: Quantity>(left: &mut T, right: T) -> bool {
+    if left.compare(&right) == Ordering::Greater {
+        *left = right;
+        true
+    } else {
+        false
+    }
+}
+
+/// Set left to the saturated sum of left and right and return whether left was changed.
+pub fn saturating_add_changed<T: Quantity>(left: &mut T, right: T) -> bool {
+    let sum = Quantity::saturating_add(*left, right);
+    if sum.compare(left) == Ordering::Equal {
+        false
+    } else {
+        *left = sum;
+        true
+    }
+}
+
+/// Overwrite left with right if left is zero and return whether left was changed.
+pub fn overwrite_zero_changed<T: Quantity>(left: &mut T, right: T) -> bool {
+    if left.is_zero() && !right.is_zero() {
+        *left = right;
+        true
+    } else {
+        false
+    }
+}
@@ -0,0 +1,253 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Trust levels of layers and fields that may only be set by trusted layers.
+//!
+//! Some fields, for example commands that are executed automatically, must only be set by trusted
+//! sources like the system configuration, but not by a configuration file in the current
+//! repository.  The [`MergeTrusted`][] trait merges a layer with a given [`Trust`][] level and
+//! ignores the values of fields that require a higher level.  The ignored fields are reported, so
+//! that they can be logged or the whole layer can be rejected.
+//!
+//! If the `std` feature is enabled, the sources of the `layers` module can be assigned a trust
+//! level that is used by `Layers::load_trusted`.  Sources have the lowest level, `Trust::Local`,
+//! unless a higher level is configured explicitly.  Only `MergeTrusted` and
+//! `Layers::load_trusted` check the trust levels, `Merge` and `Layers::load` ignore the
+//! `min_trust` attribute.
+//!
+//! # Example
+//!
+//! ```
+//! use conflate::trust::Trust;
+//! use conflate::{Merge, MergeTrusted};
+//!
+//! #[derive(Debug, Default, Merge, MergeTrusted)]
+//! #[merge(strategy = conflate::option::overwrite_with_some)]
+//! struct Config {
+//!     #[merge(min_trust = "system")]
+//!     run_before: Option<String>,
+//!     repository: Option<String>,
+//! }
+//!
+//! let mut config = Config::default();
+//! config.merge_trusted(
+//!     Config {
+//!         run_before: Some("mount /srv".to_owned()),
+//!         repository: Some("/srv/backup".to_owned()),
+//!     },
+//!     Trust::System,
+//! )
+//! .unwrap();
+//!
+//! // a repository-local configuration file cannot change the hook
+//! let err = config
+//!     .merge_trusted(
+//!         Config {
+//!             run_before: Some("rm -rf ~".to_owned()),
+//!             repository: Some("/tmp/backup".to_owned()),
+//!         },
+//!         Trust::Local,
+//!     )
+//!     .unwrap_err();
+//! assert_eq!(["run_before"], err.paths());
+//! assert_eq!(Some("mount /srv"), config.run_before.as_deref());
+//! assert_eq!(Some("/tmp/backup"), config.repository.as_deref());
+//! ```
+//!
+//! [`MergeTrusted`]: trait.MergeTrusted.html
+//! [`Trust`]: enum.Trust.html
+
+use core::fmt;
+use core::str::FromStr;
+
+use crate::FieldPath;
+
+/// The trust level of a layer.
+///
+/// The levels are ordered from the least trusted level, [`Local`][], to the most trusted level,
+/// [`System`][].  They can be parsed from and formatted as the strings `"local"`, `"user"` and
+/// `"system"` that are also used by the `min_trust` attribute of the derive macro.
+///
+/// [`Local`]: #variant.Local
+/// [`System`]: #variant.System
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Trust {
+    /// A layer that is local to the current working directory, e.g. a configuration file in a
+    /// repository.
+    Local,
+    /// A layer that is controlled by the current user, e.g. a configuration file in the home
+    /// directory or the command-line arguments.
+    User,
+    /// A layer that is controlled by the administrator, e.g. a configuration file in `/etc`.
+    System,
+}
+
+impl Trust {
+    /// Returns the name of this level, i.e. `"local"`, `"user"` or `"system"`.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Local => "local",
+            Self::User => "user",
+            Self::System => "system",
+        }
+    }
+}
+
+impl fmt::Display for Trust {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Trust {
+    type Err = ParseTrustError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "local" => Ok(Self::Local),
+            "user" => Ok(Self::User),
+            "system" => Ok(Self::System),
+            _ => Err(ParseTrustError),
+        }
+    }
+}
+
+/// The error returned when parsing an unknown [`Trust`][] level.
+///
+/// [`Trust`]: enum.Trust.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseTrustError;
+
+impl fmt::Display for ParseTrustError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("expected \"local\", \"user\" or \"system\"")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseTrustError {}
+
+/// Merge right into left using the given strategy if `trust` is at least `min_trust`, and return
+/// whether right tried to change left although it is not trusted enough.
+///
+/// If `trust` is lower than `min_trust`, the values are merged into a copy of the left value to
+/// check whether the merge would have changed it.
+pub fn merge_with<T: Clone + PartialEq, F: FnOnce(&mut T, T)>(
+    left: &mut T,
+    right: T,
+    trust: Trust,
+    min_trust: Trust,
+    strategy: F,
+) -> bool {
+    if trust >= min_trust {
+        strategy(left, right);
+        false
+    } else {
+        let mut value = left.clone();
+        strategy(&mut value, right);
+        value != *left
+    }
+}
+
+/// A trait for objects that can be merged from layers with a trust level.
+///
+/// # Deriving
+///
+/// `MergeTrusted` can be derived for structs if the `derive` feature is enabled.  The derive
+/// macro uses the same `merge` attributes as the `Merge` derive macro and the `min_trust` field
+/// attribute:
+/// - Fields with the `min_trust = "..."` attribute are merged using [`merge_with`][] and the
+///   strategy of the field or, if the field has no strategy, `Merge::merge`, so their type has to
+///   implement `Clone` and `PartialEq`.  The level is one of `"local"`, `"user"` and `"system"`.
+/// - Other fields with a strategy are merged using the strategy.
+/// - Other fields without a strategy are merged by calling `MergeTrusted::merge_trusted_with`.
+///
+/// The `Merge` derive macro ignores the `min_trust` attribute, i.e. `Merge::merge` treats the
+/// other value as fully trusted.
+///
+/// The field paths use the keys determined from the `serde` attributes of the struct, see
+/// [`FieldPath`][].
+///
+/// [`merge_with`]: fn.merge_with.html
+/// [`FieldPath`]: ../path/enum.FieldPath.html
+pub trait MergeTrusted {
+    /// Merge another object with the given trust level into this object, calling `rejected` with
+    /// the path of each field that requires a higher trust level and that the other object tried
+    /// to change.
+    ///
+    /// The paths of the fields are prefixed with `path`.
+    fn merge_trusted_with(
+        &mut self,
+        other: Self,
+        trust: Trust,
+        path: &FieldPath<'_>,
+        rejected: &mut dyn FnMut(&FieldPath<'_>),
+    );
+
+    /// Merge another object with the given trust level into this object and return an error if it
+    /// tried to change fields that require a higher trust level.
+    ///
+    /// The other fields are merged in any case, so the error can also be treated as a warning.
+    /// To reject the whole layer instead, merge it into a clone of this object and discard the
+    /// clone if an error is returned.
+    ///
+    /// This method is only available if the `std` feature is enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error with the paths of the rejected fields.
+    #[cfg(feature = "std")]
+    fn merge_trusted(&mut self, other: Self, trust: Trust) -> Result<(), Error>
+    where
+        Self: Sized,
+    {
+        let mut paths = Vec::new();
+        self.merge_trusted_with(other, trust, &FieldPath::Root, &mut |path| {
+            paths.push(path.to_string());
+        });
+        if paths.is_empty() {
+            Ok(())
+        } else {
+            Err(Error { trust, paths })
+        }
+    }
+}
+
+/// The error returned by [`MergeTrusted::merge_trusted`][] if the other value tried to change
+/// fields that require a higher trust level.
+///
+/// [`MergeTrusted::merge_trusted`]: trait.MergeTrusted.html#method.merge_trusted
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    trust: Trust,
+    paths: Vec<String>,
+}
+
+#[cfg(feature = "std")]
+impl Error {
+    /// Returns the trust level of the rejected value.
+    pub const fn trust(&self) -> Trust {
+        self.trust
+    }
+
+    /// Returns the paths of the fields that the other value tried to change.
+    pub fn paths(&self) -> &[String] {
+        &self.paths
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "fields cannot be set with trust level {}: {}",
+            self.trust,
+            self.paths.join(", ")
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
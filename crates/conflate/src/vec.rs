@@ -22,3 +22,47 @@ pub fn prepend<T>(left: &mut Vec<T>, mut right: Vec<T>) {
     right.append(left);
     *left = right;
 }
+
+/// Overwrite left with clones of the elements of right if left is empty.
+///
+/// This is the borrowed version of [`overwrite_empty`][] for use with `MergeRef`.
+pub fn overwrite_empty_cloned<T: Clone>(left: &mut Vec<T>, right: &[T]) {
+    if left.is_empty() {
+        left.extend_from_slice(right);
+    }
+}
+
+/// Append clones of the contents of right to left.
+///
+/// This is the borrowed version of [`append`][] for use with `MergeRef`.
+pub fn append_cloned<T: Clone>(left: &mut Vec<T>, right: &[T]) {
+    left.extend_from_slice(right);
+}
+
+/// Prepend clones of the contents of right to left.
+///
+/// This is the borrowed version of [`prepend`][] for use with `MergeRef`.
+pub fn prepend_cloned<T: Clone>(left: &mut Vec<T>, right: &[T]) {
+    let _ = left.splice(0..0, right.iter().cloned());
+}
+
+/// Overwrite left with right if left is empty and return whether left was changed.
+pub fn overwrite_empty_changed<T>(left: &mut Vec<T>, right: Vec<T>) -> bool {
+    let changed = left.is_empty() && !right.is_empty();
+    overwrite_empty(left, right);
+    changed
+}
+
+/// Append the contents of right to left and return whether left was changed.
+pub fn append_changed<T>(left: &mut Vec<T>, right: Vec<T>) -> bool {
+    let changed = !right.is_empty();
+    append(left, right);
+    changed
+}
+
+/// Prepend the contents of right to left and return whether left was changed.
+pub fn prepend_changed<T>(left: &mut Vec<T>, right: Vec<T>) -> bool {
+    let changed = !right.is_empty();
+    prepend(left, right);
+    changed
+}
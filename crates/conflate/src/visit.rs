@@ -0,0 +1,262 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Walk all values of a merged object.
+//!
+//! The [`MergeVisit`][] trait calls a [`Visitor`][] with the path and value of every leaf of an
+//! object, e.g. every string or number of a merged configuration.  Nested structs, `Option`s,
+//! `Vec`s and maps are not reported themselves but traversed.  The leaves are passed as
+//! [`Any`][] so that the visitor can handle the types it is interested in.  This can be used to
+//! implement validation, redaction or other passes over a merged value without another
+//! procedural macro.
+//!
+//! # Example
+//!
+//! ```
+//! use std::any::Any;
+//!
+//! use conflate::{FieldPath, Merge, MergeVisit};
+//!
+//! #[derive(Merge)]
+//! #[merge(visit)]
+//! struct Repository {
+//!     #[merge(strategy = conflate::option::overwrite_none)]
+//!     path: Option<String>,
+//! }
+//!
+//! #[derive(Merge)]
+//! #[merge(visit)]
+//! struct Config {
+//!     #[merge(strategy = conflate::vec::append)]
+//!     groups: Vec<String>,
+//!     repository: Repository,
+//! }
+//!
+//! let mut config = Config {
+//!     groups: vec!["rust".to_owned()],
+//!     repository: Repository { path: Some(" /srv/backup ".to_owned()) },
+//! };
+//!
+//! config.visit_mut(&mut |_: &FieldPath<'_>, value: &mut dyn Any| {
+//!     if let Some(s) = value.downcast_mut::<String>() {
+//!         *s = s.trim().to_owned();
+//!     }
+//! });
+//!
+//! let mut paths = Vec::new();
+//! config.visit(&mut |path: &FieldPath<'_>, _: &dyn Any| paths.push(path.to_string()));
+//! assert_eq!(vec!["groups.0", "repository.path"], paths);
+//! assert_eq!(Some("/srv/backup"), config.repository.path.as_deref());
+//! ```
+//!
+//! [`Any`]: https://doc.rust-lang.org/core/any/trait.Any.html
+//! [`MergeVisit`]: trait.MergeVisit.html
+//! [`Visitor`]: trait.Visitor.html
+
+use core::any::Any;
+#[cfg(feature = "std")]
+use std::collections::{BTreeMap, HashMap};
+
+#[cfg(feature = "indexmap")]
+use indexmap::IndexMap;
+
+use crate::secret::{Secret, SecretValue};
+use crate::FieldPath;
+
+/// A visitor for the leaves of a [`MergeVisit`][] object.
+///
+/// This trait is implemented for all closures with a matching signature.
+///
+/// [`MergeVisit`]: trait.MergeVisit.html
+pub trait Visitor {
+    /// Visits the leaf value at the given path.
+    fn visit(&mut self, path: &FieldPath<'_>, value: &dyn Any);
+}
+
+impl<F: FnMut(&FieldPath<'_>, &dyn Any)> Visitor for F {
+    fn visit(&mut self, path: &FieldPath<'_>, value: &dyn Any) {
+        self(path, value);
+    }
+}
+
+/// A visitor for the mutable leaves of a [`MergeVisit`][] object.
+///
+/// This trait is implemented for all closures with a matching signature.
+///
+/// [`MergeVisit`]: trait.MergeVisit.html
+pub trait VisitorMut {
+    /// Visits the leaf value at the given path.
+    fn visit_mut(&mut self, path: &FieldPath<'_>, value: &mut dyn Any);
+}
+
+impl<F: FnMut(&FieldPath<'_>, &mut dyn Any)> VisitorMut for F {
+    fn visit_mut(&mut self, path: &FieldPath<'_>, value: &mut dyn Any) {
+        self(path, value);
+    }
+}
+
+/// A trait for objects whose leaf values can be visited.
+///
+/// # Deriving
+///
+/// The `Merge` derive macro implements `MergeVisit` if the `visit` attribute is set for the
+/// struct.  If the `interpolate` feature is enabled, the `Interpolate` derive macro generates the
+/// same implementation, see the `interpolate` module.  The generated implementation visits all
+/// fields without the `skip` and `skip_interpolate` attributes, using the keys determined from
+/// the `serde` attributes of the struct as field paths, so their types have to implement
+/// `MergeVisit` as well.
+///
+/// This crate implements `MergeVisit` for `Option`s, `Vec`s and maps with string keys, which are
+/// traversed, and for numbers, booleans, strings, paths and [`Secret`][]s, which are leaves.  The
+/// elements of a `Vec` use their index as key.  Other leaf types can implement this trait by
+/// passing themselves to the visitor:
+///
+/// ```
+/// use conflate::visit::{MergeVisit, Visitor, VisitorMut};
+/// use conflate::FieldPath;
+///
+/// struct Port(u16);
+///
+/// impl MergeVisit for Port {
+///     fn visit_with(&self, path: &FieldPath<'_>, visitor: &mut dyn Visitor) {
+///         visitor.visit(path, self);
+///     }
+///
+///     fn visit_mut_with(&mut self, path: &FieldPath<'_>, visitor: &mut dyn VisitorMut) {
+///         visitor.visit_mut(path, self);
+///     }
+/// }
+/// ```
+///
+/// [`Secret`]: ../secret/struct.Secret.html
+pub trait MergeVisit {
+    /// Calls the visitor for all leaves of this object, prefixing their paths with `path`.
+    fn visit_with(&self, path: &FieldPath<'_>, visitor: &mut dyn Visitor);
+
+    /// Calls the visitor for all leaves of this object, prefixing their paths with `path`.
+    fn visit_mut_with(&mut self, path: &FieldPath<'_>, visitor: &mut dyn VisitorMut);
+
+    /// Calls the visitor for all leaves of this object.
+    fn visit(&self, visitor: &mut impl Visitor)
+    where
+        Self: Sized,
+    {
+        self.visit_with(&FieldPath::Root, visitor);
+    }
+
+    /// Calls the visitor for all leaves of this object.
+    fn visit_mut(&mut self, visitor: &mut impl VisitorMut)
+    where
+        Self: Sized,
+    {
+        self.visit_mut_with(&FieldPath::Root, visitor);
+    }
+}
+
+impl<T: MergeVisit> MergeVisit for Option<T> {
+    /// Visits the contained value, if any, with the same path.
+    fn visit_with(&self, path: &FieldPath<'_>, visitor: &mut dyn Visitor) {
+        if let Some(value) = self {
+            value.visit_with(path, visitor);
+        }
+    }
+
+    fn visit_mut_with(&mut self, path: &FieldPath<'_>, visitor: &mut dyn VisitorMut) {
+        if let Some(value) = self {
+            value.visit_mut_with(path, visitor);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: MergeVisit> MergeVisit for Vec<T> {
+    /// Visits the elements with their index as key.
+    fn visit_with(&self, path: &FieldPath<'_>, visitor: &mut dyn Visitor) {
+        for (i, value) in self.iter().enumerate() {
+            value.visit_with(&path.join(&i.to_string()), visitor);
+        }
+    }
+
+    fn visit_mut_with(&mut self, path: &FieldPath<'_>, visitor: &mut dyn VisitorMut) {
+        for (i, value) in self.iter_mut().enumerate() {
+            value.visit_mut_with(&path.join(&i.to_string()), visitor);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+macro_rules! impl_visit_map {
+    ($($map:ident),*) => {
+        $(
+            impl<V: MergeVisit> MergeVisit for $map<String, V> {
+                fn visit_with(&self, path: &FieldPath<'_>, visitor: &mut dyn Visitor) {
+                    for (key, value) in self {
+                        value.visit_with(&path.join(key), visitor);
+                    }
+                }
+
+                fn visit_mut_with(&mut self, path: &FieldPath<'_>, visitor: &mut dyn VisitorMut) {
+                    for (key, value) in self {
+                        value.visit_mut_with(&path.join(key), visitor);
+                    }
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "std")]
+impl_visit_map!(BTreeMap, HashMap);
+#[cfg(feature = "indexmap")]
+impl_visit_map!(IndexMap);
+
+macro_rules! impl_visit_leaf {
+    ($($ty:ty),*) => {
+        $(
+            impl MergeVisit for $ty {
+                fn visit_with(&self, path: &FieldPath<'_>, visitor: &mut dyn Visitor) {
+                    visitor.visit(path, self);
+                }
+
+                fn visit_mut_with(&mut self, path: &FieldPath<'_>, visitor: &mut dyn VisitorMut) {
+                    visitor.visit_mut(path, self);
+                }
+            }
+        )*
+    };
+}
+
+impl_visit_leaf!(
+    bool,
+    char,
+    f32,
+    f64,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    &'static str,
+    crate::bool::TriState
+);
+
+#[cfg(feature = "std")]
+impl_visit_leaf!(String, std::path::PathBuf);
+
+impl<T: SecretValue + 'static> MergeVisit for Secret<T> {
+    /// Visits the `Secret` itself so that the visitor cannot access the value by accident.
+    fn visit_with(&self, path: &FieldPath<'_>, visitor: &mut dyn Visitor) {
+        visitor.visit(path, self);
+    }
+
+    fn visit_mut_with(&mut self, path: &FieldPath<'_>, visitor: &mut dyn VisitorMut) {
+        visitor.visit_mut(path, self);
+    }
+}
@@ -0,0 +1,327 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Reload a layered value when its sources change.
+//!
+//! A [`Watcher`][] keeps the [`Layers`][] of a value, for example the configuration of a
+//! long-running service.  When triggered, it loads and merges the layers again and publishes the
+//! new value through a [`Handle`][] that can be shared between threads, and as an [`Update`][]
+//! with the changed fields to all subscribers.
+//!
+//! The watcher does not start any threads and does not depend on a specific file notification
+//! mechanism.  Call [`Watcher::poll`][] periodically to reload the value if the modification time
+//! of a source changed, or call [`Watcher::reload`][] when notified of a change by other means,
+//! e.g. a file system notification or a signal.
+//!
+//! If the `async` feature is enabled, watchers for layers with asynchronous sources are created
+//! using [`Watcher::new_async`][] and reloaded using the `poll_async` and `reload_async` methods.
+//!
+//! These items are only available if the `watch` feature is enabled.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use conflate::layers::{FileSource, Layers};
+//! use conflate::watch::Watcher;
+//! use conflate::{Merge, MergePreview};
+//!
+//! #[derive(Clone, Debug, Default, PartialEq, Merge, MergePreview, serde::Deserialize)]
+//! #[merge(strategy = conflate::option::overwrite_none)]
+//! struct Config {
+//!     repository: Option<String>,
+//! }
+//!
+//! let source = FileSource::new("config.json", |s| serde_json::from_str::<Config>(s));
+//! let layers = Layers::new().with_source(source);
+//! let mut watcher = Watcher::new(layers)?;
+//! let handle = watcher.handle();
+//! let updates = watcher.subscribe();
+//!
+//! std::thread::spawn(move || loop {
+//!     if let Err(err) = watcher.poll() {
+//!         eprintln!("{}", err);
+//!     }
+//!     std::thread::sleep(Duration::from_secs(1));
+//! });
+//!
+//! for update in updates {
+//!     for change in &update.changes {
+//!         println!("{}", change);
+//!     }
+//!     assert_eq!(*update.value, *handle.load());
+//! }
+//! # Ok::<(), conflate::layers::Error>(())
+//! ```
+//!
+//! [`Layers`]: ../layers/struct.Layers.html
+//! [`Watcher`]: struct.Watcher.html
+//! [`Watcher::poll`]: struct.Watcher.html#method.poll
+//! [`Watcher::reload`]: struct.Watcher.html#method.reload
+//! [`Watcher::new_async`]: struct.Watcher.html#method.new_async
+//! [`Handle`]: struct.Handle.html
+//! [`Update`]: struct.Update.html
+
+use std::fmt;
+use std::sync::mpsc;
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::SystemTime;
+
+use crate::layers::{Error, Layers};
+use crate::preview::{Change, MergePreview};
+use crate::Merge;
+
+/// A shared handle to the current value of a [`Watcher`][].
+///
+/// The handle can be cloned and sent to other threads.  [`load`][] always returns the most
+/// recently published value.
+///
+/// [`Watcher`]: struct.Watcher.html
+/// [`load`]: #method.load
+pub struct Handle<T> {
+    current: Arc<RwLock<Arc<T>>>,
+}
+
+impl<T> Handle<T> {
+    fn new(value: T) -> Self {
+        Self {
+            current: Arc::new(RwLock::new(Arc::new(value))),
+        }
+    }
+
+    /// Returns the current value.
+    pub fn load(&self) -> Arc<T> {
+        Arc::clone(&self.current.read().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    fn store(&self, value: Arc<T>) {
+        *self.current.write().unwrap_or_else(PoisonError::into_inner) = value;
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            current: Arc::clone(&self.current),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Handle").field(&self.load()).finish()
+    }
+}
+
+/// A new value published by a [`Watcher`][].
+///
+/// [`Watcher`]: struct.Watcher.html
+#[derive(Debug)]
+pub struct Update<T> {
+    /// The new value.
+    pub value: Arc<T>,
+    /// The differences between the previous and the new value, see [`MergePreview::diff`][].
+    ///
+    /// The differences are computed using the `MergePreview` implementation of the value, so
+    /// fields that are skipped by the derive macros are not reported.
+    ///
+    /// [`MergePreview::diff`]: ../preview/trait.MergePreview.html#tymethod.diff
+    pub changes: Vec<Change>,
+}
+
+impl<T> Update<T> {
+    /// Returns the paths of the changed fields.
+    pub fn changed_paths(&self) -> impl Iterator<Item = &str> {
+        self.changes.iter().map(|change| change.path.as_str())
+    }
+}
+
+impl<T> Clone for Update<T> {
+    fn clone(&self) -> Self {
+        Self {
+            value: Arc::clone(&self.value),
+            changes: self.changes.clone(),
+        }
+    }
+}
+
+/// Reloads a value from its layers and publishes the changes.
+pub struct Watcher<T> {
+    layers: Layers<T>,
+    handle: Handle<T>,
+    modified: Vec<Option<SystemTime>>,
+    subscribers: Vec<mpsc::Sender<Update<T>>>,
+}
+
+impl<T: Default + Merge + MergePreview> Watcher<T> {
+    /// Creates a watcher for the given layers and loads the initial value.
+    ///
+    /// If the `async` feature is enabled and the layers contain asynchronous sources, use
+    /// [`new_async`][] instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layers could not be loaded.  If the layers contain asynchronous
+    /// sources, an error with the kind `ErrorKind::Async` is returned.
+    ///
+    /// [`new_async`]: #method.new_async
+    pub fn new(layers: Layers<T>) -> Result<Self, Error> {
+        let modified = layers.modified();
+        let value = layers.load()?;
+        Ok(Self {
+            layers,
+            handle: Handle::new(value),
+            modified,
+            subscribers: Vec::new(),
+        })
+    }
+
+    /// Returns a handle to the current value.
+    pub fn handle(&self) -> Handle<T> {
+        self.handle.clone()
+    }
+
+    /// Returns the layers of the value.
+    pub const fn layers(&self) -> &Layers<T> {
+        &self.layers
+    }
+
+    /// Returns a receiver for all updates published after this call.
+    ///
+    /// Subscribers whose receiver has been dropped are removed on the next update.
+    pub fn subscribe(&mut self) -> mpsc::Receiver<Update<T>> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    /// Reloads the value if the modification time of any source changed since the last reload.
+    ///
+    /// Returns the published update, or `None` if no source changed or the reloaded value is
+    /// equal to the current value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layers could not be loaded.  The current value is kept in this
+    /// case and the layers are loaded again on the next call, so a file that was read while it
+    /// was being written is not missed.
+    pub fn poll(&mut self) -> Result<Option<Update<T>>, Error> {
+        let modified = self.layers.modified();
+        if modified == self.modified {
+            return Ok(None);
+        }
+        let value = self.layers.load()?;
+        self.modified = modified;
+        Ok(self.publish(value))
+    }
+
+    /// Reloads the value unconditionally.
+    ///
+    /// Returns the published update, or `None` if the reloaded value is equal to the current
+    /// value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layers could not be loaded.  The current value is kept in this
+    /// case.
+    pub fn reload(&mut self) -> Result<Option<Update<T>>, Error> {
+        let modified = self.layers.modified();
+        let value = self.layers.load()?;
+        self.modified = modified;
+        Ok(self.publish(value))
+    }
+
+    /// Creates a watcher for the given layers and loads the initial value using
+    /// [`Layers::load_async`][].
+    ///
+    /// Watchers for layers with asynchronous sources must be created with this method and
+    /// reloaded with [`poll_async`][] or [`reload_async`][].
+    ///
+    /// This method is only available if the `async` feature is enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layers could not be loaded.
+    ///
+    /// [`Layers::load_async`]: ../layers/struct.Layers.html#method.load_async
+    /// [`poll_async`]: #method.poll_async
+    /// [`reload_async`]: #method.reload_async
+    #[cfg(feature = "async")]
+    pub async fn new_async(layers: Layers<T>) -> Result<Self, Error> {
+        let modified = layers.modified();
+        let value = layers.load_async().await?;
+        Ok(Self {
+            layers,
+            handle: Handle::new(value),
+            modified,
+            subscribers: Vec::new(),
+        })
+    }
+
+    /// Like [`poll`][], but loads the layers using [`Layers::load_async`][].
+    ///
+    /// This method is only available if the `async` feature is enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layers could not be loaded.  The current value is kept in this
+    /// case and the layers are loaded again on the next call.
+    ///
+    /// [`poll`]: #method.poll
+    /// [`Layers::load_async`]: ../layers/struct.Layers.html#method.load_async
+    #[cfg(feature = "async")]
+    pub async fn poll_async(&mut self) -> Result<Option<Update<T>>, Error> {
+        let modified = self.layers.modified();
+        if modified == self.modified {
+            return Ok(None);
+        }
+        let value = self.layers.load_async().await?;
+        self.modified = modified;
+        Ok(self.publish(value))
+    }
+
+    /// Like [`reload`][], but loads the layers using [`Layers::load_async`][].
+    ///
+    /// This method is only available if the `async` feature is enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layers could not be loaded.  The current value is kept in this
+    /// case.
+    ///
+    /// [`reload`]: #method.reload
+    /// [`Layers::load_async`]: ../layers/struct.Layers.html#method.load_async
+    #[cfg(feature = "async")]
+    pub async fn reload_async(&mut self) -> Result<Option<Update<T>>, Error> {
+        let modified = self.layers.modified();
+        let value = self.layers.load_async().await?;
+        self.modified = modified;
+        Ok(self.publish(value))
+    }
+
+    fn publish(&mut self, value: T) -> Option<Update<T>> {
+        let changes = self.handle.load().diff(&value);
+        if changes.is_empty() {
+            return None;
+        }
+
+        let update = Update {
+            value: Arc::new(value),
+            changes,
+        };
+        self.handle.store(Arc::clone(&update.value));
+        self.subscribers
+            .retain(|subscriber| subscriber.send(update.clone()).is_ok());
+        Some(update)
+    }
+}
+
+impl<T> fmt::Debug for Watcher<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Watcher")
+            .field("layers", &self.layers)
+            .field("subscribers", &self.subscribers.len())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,149 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+#![cfg(all(feature = "derive", feature = "std"))]
+
+use std::collections::{BTreeMap, HashMap};
+
+use conflate::{Merge, MergeChanged, MergeRef};
+
+fn test<T: std::fmt::Debug + Merge + PartialEq>(expected: T, mut left: T, right: T) {
+    left.merge(right);
+    assert_eq!(expected, left);
+}
+
+fn map<M: FromIterator<(String, u8)>>(entries: &[(&str, u8)]) -> M {
+    entries.iter().map(|(k, v)| ((*k).to_owned(), *v)).collect()
+}
+
+#[derive(Clone, Debug, PartialEq, Merge, MergeRef, MergeChanged)]
+#[merge(auto)]
+struct Nested {
+    option: Option<u8>,
+    list: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Merge, MergeRef, MergeChanged)]
+#[merge(auto)]
+struct S {
+    option: Option<u8>,
+    list: Vec<u8>,
+    hashmap: HashMap<String, u8>,
+    btreemap: BTreeMap<String, u8>,
+    flag: bool,
+    nested: Nested,
+}
+
+fn s(option: Option<u8>, list: &[u8], entries: &[(&str, u8)], flag: bool) -> S {
+    S {
+        option,
+        list: list.to_vec(),
+        hashmap: map(entries),
+        btreemap: map(entries),
+        flag,
+        nested: Nested {
+            option,
+            list: list.to_vec(),
+        },
+    }
+}
+
+#[test]
+fn test_auto() {
+    test(
+        s(Some(1), &[1, 2], &[("a", 1), ("b", 2)], true),
+        s(Some(1), &[1], &[("a", 1)], false),
+        s(Some(2), &[2], &[("a", 2), ("b", 2)], true),
+    );
+    test(
+        s(Some(2), &[2], &[("b", 2)], true),
+        s(None, &[], &[], true),
+        s(Some(2), &[2], &[("b", 2)], false),
+    );
+}
+
+#[test]
+fn test_other_derives() {
+    let left = s(None, &[1], &[], false);
+    let right = s(Some(2), &[2], &[("a", 2)], true);
+    let expected = s(Some(2), &[1, 2], &[("a", 2)], true);
+
+    let mut merged = left.clone();
+    merged.merge_ref(&right);
+    assert_eq!(expected, merged);
+
+    let mut merged = left;
+    assert_eq!(
+        vec![
+            "option",
+            "list",
+            "hashmap",
+            "btreemap",
+            "flag",
+            "nested.option",
+            "nested.list",
+        ],
+        merged.merge_changed_paths(right)
+    );
+    assert_eq!(expected, merged);
+}
+
+#[test]
+fn test_precedence() {
+    #[derive(Debug, PartialEq, Merge)]
+    #[merge(auto, precedence = "right")]
+    struct S {
+        option: Option<u8>,
+        list: Vec<u8>,
+        flag: bool,
+    }
+
+    test(
+        S {
+            option: Some(2),
+            list: vec![1, 2],
+            flag: true,
+        },
+        S {
+            option: Some(1),
+            list: vec![1],
+            flag: true,
+        },
+        S {
+            option: Some(2),
+            list: vec![2],
+            flag: false,
+        },
+    );
+}
+
+#[test]
+fn test_overrides() {
+    #[derive(Debug, PartialEq, Merge)]
+    #[merge(auto, strategy = conflate::ord::max)]
+    struct S {
+        #[merge(strategy = conflate::vec::overwrite_empty)]
+        list: Vec<u8>,
+        #[merge(skip)]
+        skipped: Option<u8>,
+        number: u8,
+    }
+
+    test(
+        S {
+            list: vec![1],
+            skipped: None,
+            number: 2,
+        },
+        S {
+            list: vec![1],
+            skipped: None,
+            number: 1,
+        },
+        S {
+            list: vec![2],
+            skipped: Some(2),
+            number: 2,
+        },
+    );
+}
@@ -0,0 +1,181 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+#![cfg(feature = "derive")]
+
+use conflate::bool::TriState;
+use conflate::{Merge, MergeChanged, MergeRef};
+
+fn test<T: std::fmt::Debug + Merge + PartialEq>(expected: T, mut left: T, right: T) {
+    left.merge(right);
+    assert_eq!(expected, left);
+}
+
+#[test]
+fn test_merge() {
+    #[derive(Debug, Merge, PartialEq)]
+    struct S(TriState);
+
+    test(S(TriState::Unset), S(TriState::Unset), S(TriState::Unset));
+    test(S(TriState::Off), S(TriState::Unset), S(TriState::Off));
+    test(S(TriState::Off), S(TriState::Off), S(TriState::On));
+    test(S(TriState::On), S(TriState::On), S(TriState::Unset));
+}
+
+#[test]
+fn test_overwrite_with_set() {
+    #[derive(Debug, Merge, PartialEq)]
+    struct S(#[merge(strategy = conflate::bool::overwrite_with_set)] TriState);
+
+    test(S(TriState::Off), S(TriState::Unset), S(TriState::Off));
+    test(S(TriState::On), S(TriState::Off), S(TriState::On));
+    test(S(TriState::Off), S(TriState::Off), S(TriState::Unset));
+}
+
+#[test]
+fn test_precedence() {
+    #[derive(Clone, Debug, PartialEq, Merge, MergeRef, MergeChanged)]
+    #[merge(precedence = "right")]
+    struct S {
+        color: TriState,
+    }
+
+    let s = |color| S { color };
+    test(s(TriState::On), s(TriState::Off), s(TriState::On));
+    test(s(TriState::Off), s(TriState::Off), s(TriState::Unset));
+
+    let mut left = s(TriState::Off);
+    left.merge_ref(&s(TriState::On));
+    assert_eq!(s(TriState::On), left);
+
+    assert!(!left.merge_changed(s(TriState::On)));
+    assert!(left.merge_changed(s(TriState::Off)));
+    assert_eq!(s(TriState::Off), left);
+}
+
+#[test]
+fn test_resolve() {
+    assert!(TriState::On.resolve(false));
+    assert!(!TriState::Off.resolve(true));
+    assert!(TriState::Unset.resolve(true));
+    assert!(!TriState::Unset.resolve(false));
+
+    assert_eq!(Some(false), TriState::Off.get());
+    assert_eq!(TriState::Unset, TriState::from(None));
+    assert_eq!(TriState::On, TriState::from(true));
+}
+
+#[test]
+fn test_from_flags() {
+    assert_eq!(TriState::Unset, TriState::from_flags(false, false));
+    assert_eq!(TriState::On, TriState::from_flags(true, false));
+    assert_eq!(TriState::Off, TriState::from_flags(false, true));
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!(Ok(TriState::On), "yes".parse());
+    assert_eq!(Ok(TriState::Off), "false".parse());
+    assert_eq!(Ok(TriState::Unset), "unset".parse());
+    assert!("maybe".parse::<TriState>().is_err());
+    assert_eq!("off", TriState::Off.to_string());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde() {
+    #[derive(Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+    #[serde(default)]
+    struct S {
+        color: TriState,
+        verbose: TriState,
+    }
+
+    let s: S = serde_json::from_str(r#"{"color": false}"#).unwrap();
+    assert_eq!(
+        S {
+            color: TriState::Off,
+            verbose: TriState::Unset,
+        },
+        s
+    );
+    assert_eq!(
+        r#"{"color":false,"verbose":null}"#,
+        serde_json::to_string(&s).unwrap()
+    );
+}
+
+#[cfg(feature = "clap")]
+#[test]
+fn test_clap() {
+    use clap::Parser;
+    use conflate::bool::{Flag, TriStateArgs};
+
+    struct Color;
+
+    impl Flag for Color {
+        const LONG: &'static str = "color";
+        const NO_LONG: &'static str = "no-color";
+        const SHORT: Option<char> = Some('c');
+    }
+
+    #[derive(Debug, Merge, Parser, PartialEq)]
+    struct Args {
+        #[command(flatten)]
+        color: TriStateArgs<Color>,
+    }
+
+    let parse = |args: &[&str]| {
+        Args::try_parse_from(std::iter::once("app").chain(args.iter().copied()))
+            .unwrap()
+            .color
+            .get()
+    };
+    assert_eq!(TriState::Unset, parse(&[]));
+    assert_eq!(TriState::On, parse(&["--color"]));
+    assert_eq!(TriState::On, parse(&["-c"]));
+    assert_eq!(TriState::Off, parse(&["--no-color"]));
+    assert_eq!(TriState::Off, parse(&["--color", "--no-color"]));
+    assert_eq!(TriState::On, parse(&["--no-color", "--color"]));
+
+    let mut args = Args::parse_from(["app"]);
+    args.merge(Args {
+        color: TriState::Off.into(),
+    });
+    assert_eq!(TriState::Off, *args.color);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_deserialize_merge() {
+    use conflate::DeserializeMerge;
+
+    #[derive(Debug, Default, PartialEq, serde::Deserialize, DeserializeMerge, Merge)]
+    struct S {
+        color: TriState,
+        verbose: TriState,
+    }
+
+    let mut s = S {
+        color: TriState::On,
+        verbose: TriState::Off,
+    };
+    s.deserialize_merge(&mut serde_json::Deserializer::from_str(
+        r#"{"color": false, "verbose": null}"#,
+    ))
+    .unwrap();
+    assert_eq!(
+        S {
+            color: TriState::Off,
+            verbose: TriState::Off,
+        },
+        s
+    );
+
+    s.deserialize_merge(&mut serde_json::Deserializer::from_str(
+        r#"{"verbose": true}"#,
+    ))
+    .unwrap();
+    assert_eq!(TriState::Off, s.color);
+    assert_eq!(TriState::On, s.verbose);
+}
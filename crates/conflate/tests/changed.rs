@@ -0,0 +1,309 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+#![cfg(feature = "derive")]
+
+use conflate::{FieldPath, MergeChanged};
+
+fn test<T: std::fmt::Debug + MergeChanged + PartialEq>(
+    expected: T,
+    expected_changed: bool,
+    mut left: T,
+    right: T,
+) {
+    assert_eq!(expected_changed, left.merge_changed(right));
+    assert_eq!(expected, left);
+}
+
+#[test]
+fn test_option() {
+    #[derive(Debug, MergeChanged, PartialEq)]
+    struct S(
+        #[merge(strategy_changed = conflate::option::overwrite_none_changed)] Option<u8>,
+        #[merge(strategy_changed = conflate::option::overwrite_with_some_changed)] Option<u8>,
+    );
+
+    test(
+        S(Some(1), Some(2)),
+        true,
+        S(None, Some(1)),
+        S(Some(1), Some(2)),
+    );
+    test(
+        S(Some(1), Some(1)),
+        false,
+        S(Some(1), Some(1)),
+        S(Some(2), Some(1)),
+    );
+    test(S(None, Some(1)), false, S(None, Some(1)), S(None, None));
+}
+
+#[test]
+fn test_option_recurse() {
+    #[derive(Debug, MergeChanged, PartialEq)]
+    struct N(#[merge(strategy_changed = conflate::ord::max_changed)] u8);
+
+    #[derive(Debug, MergeChanged, PartialEq)]
+    struct S(#[merge(strategy_changed = conflate::option::recurse_changed)] Option<N>);
+
+    test(S(Some(N(2))), true, S(Some(N(1))), S(Some(N(2))));
+    test(S(Some(N(2))), false, S(Some(N(2))), S(Some(N(1))));
+    test(S(Some(N(1))), true, S(None), S(Some(N(1))));
+    test(S(None), false, S(None), S(None));
+}
+
+#[test]
+fn test_bool() {
+    #[derive(Debug, MergeChanged, PartialEq)]
+    struct S(
+        #[merge(strategy_changed = conflate::bool::overwrite_false_changed)] bool,
+        #[merge(strategy_changed = conflate::bool::overwrite_true_changed)] bool,
+    );
+
+    test(S(true, false), true, S(false, true), S(true, false));
+    test(S(true, false), false, S(true, false), S(false, true));
+    test(S(false, true), false, S(false, true), S(false, true));
+}
+
+#[cfg(feature = "num")]
+#[test]
+fn test_num() {
+    #[derive(Debug, MergeChanged, PartialEq)]
+    struct S(
+        #[merge(strategy_changed = conflate::num::saturating_add_changed)] u8,
+        #[merge(strategy_changed = conflate::num::overwrite_zero_changed)] u8,
+    );
+
+    test(S(3, 2), true, S(1, 0), S(2, 2));
+    test(S(255, 1), false, S(255, 1), S(10, 2));
+    test(S(1, 0), false, S(1, 0), S(0, 0));
+
+    #[derive(Debug, MergeChanged, PartialEq)]
+    struct T(
+        #[merge(strategy_changed = conflate::num::saturating_sub_changed)] u8,
+        #[merge(strategy_changed = conflate::num::saturating_mul_changed)] u8,
+        #[merge(strategy_changed = conflate::num::overwrite_default_changed)] u8,
+    );
+
+    test(T(1, 6, 2), true, T(3, 2, 0), T(2, 3, 2));
+    test(T(0, 0, 1), false, T(0, 0, 1), T(2, 3, 2));
+}
+
+#[test]
+fn test_ord() {
+    #[derive(Debug, MergeChanged, PartialEq)]
+    struct S(
+        #[merge(strategy_changed = conflate::ord::max_changed)] u8,
+        #[merge(strategy_changed = conflate::ord::min_changed)] u8,
+    );
+
+    test(S(2, 1), true, S(1, 1), S(2, 2));
+    test(S(2, 1), false, S(2, 1), S(1, 2));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_vec() {
+    #[derive(Debug, MergeChanged, PartialEq)]
+    struct S(
+        #[merge(strategy_changed = conflate::vec::append_changed)] Vec<u8>,
+        #[merge(strategy_changed = conflate::vec::prepend_changed)] Vec<u8>,
+        #[merge(strategy_changed = conflate::vec::overwrite_empty_changed)] Vec<u8>,
+    );
+
+    test(
+        S(vec![1, 2], vec![2, 1], vec![3]),
+        true,
+        S(vec![1], vec![1], vec![]),
+        S(vec![2], vec![2], vec![3]),
+    );
+    test(
+        S(vec![1], vec![1], vec![1]),
+        false,
+        S(vec![1], vec![1], vec![1]),
+        S(vec![], vec![], vec![3]),
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_maps() {
+    use std::collections::{BTreeMap, HashMap};
+
+    #[derive(Debug, MergeChanged, PartialEq)]
+    struct N(#[merge(strategy_changed = conflate::ord::max_changed)] u8);
+
+    #[derive(Debug, MergeChanged, PartialEq)]
+    struct S {
+        #[merge(strategy_changed = conflate::hashmap::append_or_overwrite_changed)]
+        overwrite: HashMap<u8, u8>,
+        #[merge(strategy_changed = conflate::btreemap::append_or_ignore_changed)]
+        ignore: BTreeMap<u8, u8>,
+        #[merge(strategy_changed = conflate::btreemap::append_or_recurse_changed)]
+        recurse: BTreeMap<u8, N>,
+    }
+
+    let s = |overwrite: &[(u8, u8)], ignore: &[(u8, u8)], recurse: &[(u8, u8)]| S {
+        overwrite: overwrite.iter().copied().collect(),
+        ignore: ignore.iter().copied().collect(),
+        recurse: recurse.iter().map(|(k, v)| (*k, N(*v))).collect(),
+    };
+
+    test(
+        s(&[(1, 1)], &[(1, 1)], &[(1, 1)]),
+        false,
+        s(&[(1, 1)], &[(1, 1)], &[(1, 1)]),
+        s(&[(1, 1)], &[(1, 2)], &[(1, 0)]),
+    );
+    test(
+        s(&[(1, 2)], &[(1, 1)], &[(1, 1)]),
+        true,
+        s(&[(1, 1)], &[(1, 1)], &[(1, 1)]),
+        s(&[(1, 2)], &[], &[]),
+    );
+    test(
+        s(&[], &[(1, 1), (2, 2)], &[]),
+        true,
+        s(&[], &[(1, 1)], &[]),
+        s(&[], &[(1, 2), (2, 2)], &[]),
+    );
+    test(
+        s(&[], &[], &[(1, 2), (2, 2)]),
+        true,
+        s(&[], &[], &[(1, 1)]),
+        s(&[], &[], &[(1, 2), (2, 2)]),
+    );
+
+    let mut left = HashMap::from([(1, N(1))]);
+    assert!(conflate::hashmap::append_or_recurse_changed(
+        &mut left,
+        HashMap::from([(1, N(2))])
+    ));
+    assert!(!conflate::hashmap::append_or_ignore_changed(
+        &mut HashMap::from([(1, 1)]),
+        HashMap::from([(1, 2)])
+    ));
+}
+
+#[test]
+fn test_strategy_fallback() {
+    #[derive(Debug, MergeChanged, PartialEq)]
+    #[merge(strategy = conflate::option::overwrite_none)]
+    struct S {
+        field1: Option<u8>,
+        #[merge(strategy = conflate::ord::max)]
+        field2: u8,
+        #[merge(skip)]
+        field3: u8,
+    }
+
+    test(
+        S {
+            field1: Some(1),
+            field2: 1,
+            field3: 0,
+        },
+        true,
+        S {
+            field1: None,
+            field2: 1,
+            field3: 0,
+        },
+        S {
+            field1: Some(1),
+            field2: 0,
+            field3: 1,
+        },
+    );
+    test(
+        S {
+            field1: Some(1),
+            field2: 1,
+            field3: 0,
+        },
+        false,
+        S {
+            field1: Some(1),
+            field2: 1,
+            field3: 0,
+        },
+        S {
+            field1: Some(2),
+            field2: 0,
+            field3: 1,
+        },
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_changed_paths() {
+    #[derive(Default, MergeChanged)]
+    #[merge(strategy_changed = conflate::option::overwrite_with_some_changed)]
+    struct N {
+        value: Option<u8>,
+        other: Option<u8>,
+    }
+
+    #[derive(Default, MergeChanged)]
+    struct S {
+        nested: N,
+        #[merge(strategy_changed = conflate::bool::overwrite_false_changed)]
+        flag: bool,
+    }
+
+    let mut s = S::default();
+    let changed = s.merge_changed_paths(S {
+        nested: N {
+            value: None,
+            other: Some(1),
+        },
+        flag: true,
+    });
+    assert_eq!(vec!["nested.other", "flag"], changed);
+    assert!(s.merge_changed_paths(S::default()).is_empty());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_keys() {
+    #[derive(Default, serde::Deserialize, MergeChanged)]
+    #[serde(rename_all = "kebab-case")]
+    struct N {
+        #[merge(strategy_changed = conflate::vec::append_changed)]
+        run_before: Vec<String>,
+    }
+
+    #[derive(Default, serde::Deserialize, MergeChanged)]
+    struct S {
+        #[serde(rename = "global-hooks")]
+        hooks: N,
+        #[serde(flatten)]
+        flattened: N,
+    }
+
+    let mut s = S::default();
+    let changed = s.merge_changed_paths(S {
+        hooks: N {
+            run_before: vec!["a".to_owned()],
+        },
+        flattened: N {
+            run_before: vec!["b".to_owned()],
+        },
+    });
+    assert_eq!(vec!["global-hooks.run-before", "run-before"], changed);
+}
+
+#[test]
+fn test_field_path() {
+    let root = FieldPath::Root;
+    let parent = root.join("parent");
+    let child = parent.join("child");
+
+    assert!(root.is_root());
+    assert_eq!(None, root.key());
+    assert_eq!(Some("child"), child.key());
+    assert_eq!("", root.to_string());
+    assert_eq!("parent", parent.to_string());
+    assert_eq!("parent.child", child.to_string());
+}
@@ -0,0 +1,12 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+use conflate::Merge;
+
+#[derive(Merge)]
+struct S {
+    #[merge(strategy(Option = conflate::option::overwrite_none))]
+    field1: Option<u8>,
+}
+
+fn main() {}
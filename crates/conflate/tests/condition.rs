@@ -0,0 +1,201 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+#![cfg(feature = "derive")]
+
+use conflate::{Merge, MergeChanged, MergeRef};
+
+fn test<T: std::fmt::Debug + Merge + PartialEq>(expected: T, mut left: T, right: T) {
+    left.merge(right);
+    assert_eq!(expected, left);
+}
+
+#[derive(Clone, Debug, PartialEq, Merge, MergeRef, MergeChanged)]
+#[cfg_attr(feature = "std", derive(conflate::MergePreview))]
+#[merge(strategy = conflate::option::overwrite_with_some)]
+struct S {
+    #[merge(strategy = conflate::bool::overwrite_false)]
+    locked: bool,
+    #[merge(skip_if = is_locked)]
+    repository: Option<&'static str>,
+    #[merge(only_if = is_positive)]
+    retries: Option<i8>,
+    #[merge(skip_if = is_locked, only_if = is_positive)]
+    timeout: Option<i8>,
+}
+
+fn is_locked<T>(s: &S, _: &T) -> bool {
+    s.locked
+}
+
+fn is_positive(_: &S, value: &Option<i8>) -> bool {
+    value.map_or(false, |value| value > 0)
+}
+
+#[test]
+fn test_skip_if() {
+    test(
+        S {
+            locked: true,
+            repository: Some("/srv"),
+            retries: None,
+            timeout: None,
+        },
+        S {
+            locked: true,
+            repository: Some("/srv"),
+            retries: None,
+            timeout: None,
+        },
+        S {
+            locked: false,
+            repository: Some("/tmp"),
+            retries: None,
+            timeout: Some(1),
+        },
+    );
+
+    // the predicates see the value of locked before the merge
+    test(
+        S {
+            locked: true,
+            repository: Some("/tmp"),
+            retries: None,
+            timeout: Some(1),
+        },
+        S {
+            locked: false,
+            repository: Some("/srv"),
+            retries: None,
+            timeout: None,
+        },
+        S {
+            locked: true,
+            repository: Some("/tmp"),
+            retries: None,
+            timeout: Some(1),
+        },
+    );
+}
+
+#[test]
+fn test_only_if() {
+    test(
+        S {
+            locked: false,
+            repository: None,
+            retries: Some(1),
+            timeout: Some(1),
+        },
+        S {
+            locked: false,
+            repository: None,
+            retries: Some(1),
+            timeout: Some(1),
+        },
+        S {
+            locked: false,
+            repository: None,
+            retries: Some(-1),
+            timeout: Some(-1),
+        },
+    );
+    test(
+        S {
+            locked: false,
+            repository: None,
+            retries: Some(2),
+            timeout: Some(2),
+        },
+        S {
+            locked: false,
+            repository: None,
+            retries: Some(1),
+            timeout: Some(1),
+        },
+        S {
+            locked: false,
+            repository: None,
+            retries: Some(2),
+            timeout: Some(2),
+        },
+    );
+}
+
+#[test]
+fn test_other_derives() {
+    let left = S {
+        locked: true,
+        repository: Some("/srv"),
+        retries: None,
+        timeout: None,
+    };
+    let right = S {
+        locked: false,
+        repository: Some("/tmp"),
+        retries: Some(-1),
+        timeout: Some(1),
+    };
+
+    let mut merged = left.clone();
+    merged.merge_ref(&right);
+    assert_eq!(left, merged);
+
+    #[cfg(feature = "std")]
+    {
+        use conflate::MergePreview;
+
+        assert!(left.merge_preview(&right).is_empty());
+    }
+
+    let mut merged = left.clone();
+    assert!(!merged.merge_changed(right));
+    assert_eq!(left, merged);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_deserialize_merge() {
+    use conflate::DeserializeMerge;
+
+    #[derive(Debug, PartialEq, Merge, DeserializeMerge)]
+    #[merge(strategy = conflate::option::overwrite_with_some)]
+    struct S {
+        #[merge(strategy = conflate::bool::overwrite_false)]
+        locked: bool,
+        #[merge(skip_if = is_locked)]
+        repository: Option<String>,
+    }
+
+    fn is_locked(s: &S, _: &Option<String>) -> bool {
+        s.locked
+    }
+
+    let mut s = S {
+        locked: true,
+        repository: Some("/srv".to_owned()),
+    };
+    let mut de = serde_json::Deserializer::from_str(r#"{"repository": "/tmp"}"#);
+    s.deserialize_merge(&mut de).unwrap();
+    assert_eq!(Some("/srv"), s.repository.as_deref());
+
+    s.locked = false;
+    let mut de = serde_json::Deserializer::from_str(r#"{"repository": "/tmp"}"#);
+    s.deserialize_merge(&mut de).unwrap();
+    assert_eq!(Some("/tmp"), s.repository.as_deref());
+
+    // the predicates see the value of locked before the merge, regardless of the key order
+    for input in [
+        r#"{"locked": true, "repository": "/home"}"#,
+        r#"{"repository": "/home", "locked": true}"#,
+    ] {
+        let mut s = S {
+            locked: false,
+            repository: Some("/srv".to_owned()),
+        };
+        let mut de = serde_json::Deserializer::from_str(input);
+        s.deserialize_merge(&mut de).unwrap();
+        assert!(s.locked);
+        assert_eq!(Some("/home"), s.repository.as_deref());
+    }
+}
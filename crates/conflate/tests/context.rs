@@ -0,0 +1,173 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+#![cfg(all(feature = "derive", feature = "std"))]
+
+use conflate::{Locked, Merge, MergeContext, MergeWithContext};
+
+#[derive(Clone, Debug, Default, PartialEq, Merge, MergeWithContext)]
+struct Backup {
+    #[merge(strategy = conflate::vec::append)]
+    paths: Vec<String>,
+    #[merge(strategy = conflate::ord::max)]
+    retries: u8,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Merge, MergeWithContext)]
+#[merge(precedence = "left")]
+struct Config {
+    output: Option<String>,
+    #[merge(lockable)]
+    format: Locked<Option<String>>,
+    backup: Backup,
+}
+
+fn config(output: Option<&str>, paths: &[&str], retries: u8) -> Config {
+    Config {
+        output: output.map(ToOwned::to_owned),
+        format: Locked::default(),
+        backup: Backup {
+            paths: paths.iter().map(|s| (*s).to_owned()).collect(),
+            retries,
+        },
+    }
+}
+
+#[test]
+fn test_merge_with_ctx() {
+    let mut ctx = MergeContext::new();
+    let mut value = Config::default();
+
+    ctx.set_source("CLI");
+    value.merge_with_ctx(config(Some("out.json"), &["/home"], 3), &mut ctx);
+    assert!(ctx.warnings().is_empty());
+    assert_eq!(Some("CLI"), ctx.source_of("output"));
+    assert_eq!(Some("CLI"), ctx.source_of("backup.retries"));
+
+    ctx.set_source("config.toml");
+    value.merge_with_ctx(config(Some("out.toml"), &["/srv"], 1), &mut ctx);
+    assert_eq!(config(Some("out.json"), &["/home", "/srv"], 3), value);
+    assert_eq!(Some("config.toml"), ctx.source_of("backup.paths"));
+
+    let warnings = ctx.take_warnings();
+    let warnings: Vec<_> = warnings.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        vec![
+            "`output` from config.toml ignored: already set by CLI",
+            "`backup.retries` from config.toml ignored: already set by CLI",
+        ],
+        warnings
+    );
+    assert!(ctx.warnings().is_empty());
+}
+
+#[test]
+fn test_unset_values() {
+    let mut ctx = MergeContext::new();
+    let mut value = config(Some("out.json"), &[], 2);
+
+    // default and equal values are not reported
+    ctx.set_source("defaults");
+    value.merge_with_ctx(config(None, &[], 2), &mut ctx);
+    assert!(ctx.warnings().is_empty());
+
+    // values that were not set using the context have no source
+    ctx.set_source("config.toml");
+    value.merge_with_ctx(config(Some("out.toml"), &[], 0), &mut ctx);
+    assert_eq!(1, ctx.warnings().len());
+    assert_eq!("output", ctx.warnings()[0].path());
+    assert_eq!("config.toml", ctx.warnings()[0].source());
+    assert_eq!(None, ctx.warnings()[0].set_by());
+    assert_eq!(
+        "`output` from config.toml ignored",
+        ctx.warnings()[0].to_string()
+    );
+}
+
+#[test]
+fn test_map() {
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, Default, MergeWithContext)]
+    struct Config {
+        #[merge(strategy = conflate::btreemap::append_or_ignore)]
+        env: BTreeMap<String, String>,
+    }
+
+    let config = |entries: &[(&str, &str)]| Config {
+        env: entries
+            .iter()
+            .map(|(k, v)| ((*k).to_owned(), (*v).to_owned()))
+            .collect(),
+    };
+
+    let mut ctx = MergeContext::new();
+    let mut value = Config::default();
+
+    ctx.set_source("CLI");
+    value.merge_with_ctx(config(&[("HOME", "/home"), ("LANG", "C")]), &mut ctx);
+    assert_eq!(Some("CLI"), ctx.source_of("env.HOME"));
+
+    // the conflicting keys are reported although other keys are appended
+    ctx.set_source("config.toml");
+    value.merge_with_ctx(
+        config(&[("HOME", "/srv"), ("LANG", "C"), ("TERM", "xterm")]),
+        &mut ctx,
+    );
+    assert_eq!(Some("/home"), value.env.get("HOME").map(String::as_str));
+    assert_eq!(Some("xterm"), value.env.get("TERM").map(String::as_str));
+    assert_eq!(Some("config.toml"), ctx.source_of("env"));
+    assert_eq!(Some("config.toml"), ctx.source_of("env.TERM"));
+
+    let warnings: Vec<_> = ctx.warnings().iter().map(ToString::to_string).collect();
+    assert_eq!(
+        vec!["`env.HOME` from config.toml ignored: already set by CLI"],
+        warnings
+    );
+}
+
+#[test]
+fn test_locked() {
+    let mut ctx = MergeContext::new();
+    let mut value = Config::default();
+
+    let right = Config {
+        format: Locked::new_locked(Some("json".to_owned())),
+        ..Config::default()
+    };
+    ctx.set_source("system");
+    value.merge_with_ctx(right, &mut ctx);
+
+    let right = Config {
+        format: Locked::new(Some("toml".to_owned())),
+        ..Config::default()
+    };
+    ctx.set_source("user");
+    value.merge_with_ctx(right, &mut ctx);
+
+    assert_eq!(Some("json"), value.format.as_deref());
+    assert_eq!(
+        "`format` from user ignored: already set by system",
+        ctx.warnings()[0].to_string()
+    );
+}
+
+#[test]
+fn test_layers() {
+    use conflate::layers::{Layers, ValueSource};
+
+    let layers = Layers::new()
+        .with_source(ValueSource::new("CLI", config(Some("out.json"), &[], 0)))
+        .with_source(ValueSource::new(
+            "config.toml",
+            config(Some("out.toml"), &[], 0),
+        ));
+
+    let (value, warnings) = layers.load_with_ctx().unwrap();
+    assert_eq!(config(Some("out.json"), &[], 0), value);
+    assert_eq!(1, warnings.len());
+    assert_eq!(
+        "`output` from config.toml ignored: already set by CLI",
+        warnings[0].to_string()
+    );
+}
@@ -0,0 +1,187 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+#![cfg(all(feature = "derive", feature = "std"))]
+
+use conflate::trust::Trust;
+use conflate::{
+    Merge, MergeChanged, MergeContext, MergePreview, MergeRef, MergeTrusted, MergeWithContext,
+};
+
+fn test<T: std::fmt::Debug + Merge + PartialEq>(expected: T, mut left: T, right: T) {
+    left.merge(right);
+    assert_eq!(expected, left);
+}
+
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Merge,
+    MergeChanged,
+    MergePreview,
+    MergeRef,
+    MergeTrusted,
+    MergeWithContext,
+)]
+#[merge(strategy = conflate::option::overwrite_with_some)]
+struct S {
+    #[merge(deprecated_alias = repo)]
+    repository: Option<&'static str>,
+    #[merge(skip)]
+    repo: Option<&'static str>,
+    #[merge(deprecated_alias = run_before, min_trust = "system")]
+    hooks_run_before: Option<&'static str>,
+    run_before: Option<&'static str>,
+}
+
+fn s(repository: Option<&'static str>, repo: Option<&'static str>) -> S {
+    S {
+        repository,
+        repo,
+        ..S::default()
+    }
+}
+
+#[test]
+fn test_merge() {
+    test(s(Some("/srv"), None), s(None, None), s(None, Some("/srv")));
+    test(s(Some("/srv"), None), s(None, Some("/srv")), s(None, None));
+    test(
+        s(Some("/srv"), None),
+        s(Some("/tmp"), None),
+        s(Some("/srv"), Some("/home")),
+    );
+    test(
+        s(Some("/home"), None),
+        s(Some("/tmp"), None),
+        s(None, Some("/home")),
+    );
+}
+
+#[test]
+fn test_merge_ref() {
+    let test = |expected, mut left: S, right| {
+        left.merge_ref(&right);
+        assert_eq!(expected, left);
+    };
+
+    test(s(Some("/srv"), None), s(None, None), s(None, Some("/srv")));
+    test(s(Some("/srv"), None), s(None, Some("/srv")), s(None, None));
+    test(
+        s(Some("/srv"), None),
+        s(Some("/tmp"), None),
+        s(Some("/srv"), Some("/home")),
+    );
+    test(
+        s(Some("/home"), None),
+        s(Some("/tmp"), None),
+        s(None, Some("/home")),
+    );
+}
+
+#[test]
+fn test_merge_preview() {
+    let paths = |left: S, right| -> Vec<_> {
+        left.merge_preview(&right)
+            .into_iter()
+            .map(|c| (c.path, c.new))
+            .collect()
+    };
+
+    assert_eq!(
+        vec![("repository".to_owned(), "Some(\"/srv\")".to_owned())],
+        paths(s(None, None), s(None, Some("/srv")))
+    );
+    assert_eq!(
+        vec![("repository".to_owned(), "Some(\"/srv\")".to_owned())],
+        paths(s(None, Some("/srv")), s(None, None))
+    );
+    assert!(paths(s(Some("/srv"), None), s(None, Some("/srv"))).is_empty());
+}
+
+#[test]
+fn test_merge_changed() {
+    let mut left = S::default();
+    assert_eq!(
+        vec!["repository"],
+        left.merge_changed_paths(s(None, Some("/srv")))
+    );
+    assert_eq!(s(Some("/srv"), None), left);
+}
+
+#[test]
+fn test_merge_trusted() {
+    let mut left = S::default();
+    let right = S {
+        run_before: Some("rm -rf ~"),
+        ..S::default()
+    };
+    let err = left.merge_trusted(right, Trust::Local).unwrap_err();
+    assert_eq!(["hooks_run_before"], err.paths());
+    assert_eq!(S::default(), left);
+}
+
+#[test]
+fn test_merge_with_ctx() {
+    let mut ctx = MergeContext::new();
+    let mut left = S::default();
+
+    ctx.set_source("config.toml");
+    left.merge_with_ctx(s(None, Some("/srv")), &mut ctx);
+    assert_eq!(s(Some("/srv"), None), left);
+    assert_eq!(Some("config.toml"), ctx.source_of("repository"));
+    assert_eq!(1, ctx.warnings().len());
+    assert_eq!(
+        "`repo` from config.toml is deprecated, use `repository` instead",
+        ctx.warnings()[0].to_string()
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_deserialize_merge() {
+    use conflate::DeserializeMerge;
+
+    #[derive(Debug, Default, PartialEq, DeserializeMerge, Merge)]
+    #[merge(strategy = conflate::option::overwrite_with_some)]
+    struct S {
+        #[merge(deprecated_alias = repo)]
+        repository: Option<String>,
+        #[merge(skip)]
+        repo: Option<String>,
+    }
+
+    let deserialize = |mut s: S, input| {
+        s.deserialize_merge(&mut serde_json::Deserializer::from_str(input))
+            .unwrap();
+        s
+    };
+    let repository = |repository: &str| S {
+        repository: Some(repository.to_owned()),
+        repo: None,
+    };
+
+    assert_eq!(
+        repository("/srv"),
+        deserialize(S::default(), r#"{"repo": "/srv"}"#)
+    );
+    assert_eq!(
+        repository("/srv"),
+        deserialize(repository("/tmp"), r#"{"repo": "/srv"}"#)
+    );
+    // the new key takes precedence regardless of the order of the keys
+    assert_eq!(
+        repository("/srv"),
+        deserialize(S::default(), r#"{"repository": "/srv", "repo": "/home"}"#)
+    );
+    assert_eq!(
+        repository("/srv"),
+        deserialize(S::default(), r#"{"repo": "/home", "repository": "/srv"}"#)
+    );
+    assert_eq!(
+        repository("/tmp"),
+        deserialize(repository("/tmp"), r#"{"repository": null}"#)
+    );
+}
@@ -0,0 +1,154 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+#![cfg(all(feature = "derive", feature = "flags"))]
+
+use conflate::flags::SetUnset;
+use conflate::{Merge, MergeChanged};
+use enumset::{EnumSet, EnumSetType};
+
+fn test<T: std::fmt::Debug + Merge + PartialEq>(expected: T, mut left: T, right: T) {
+    left.merge(right);
+    assert_eq!(expected, left);
+}
+
+bitflags::bitflags! {
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Features: u8 {
+        const A = 1;
+        const B = 1 << 1;
+        const C = 1 << 2;
+    }
+}
+
+#[derive(Debug, EnumSetType)]
+enum Feature {
+    A,
+    B,
+    C,
+}
+
+#[test]
+fn test_union() {
+    #[derive(Debug, Merge, PartialEq)]
+    struct S(#[merge(strategy = conflate::flags::union)] Features);
+
+    test(
+        S(Features::empty()),
+        S(Features::empty()),
+        S(Features::empty()),
+    );
+    test(S(Features::A), S(Features::empty()), S(Features::A));
+    test(S(Features::A | Features::B), S(Features::A), S(Features::B));
+    test(
+        S(Features::all()),
+        S(Features::A | Features::B),
+        S(Features::C),
+    );
+}
+
+#[test]
+fn test_intersection() {
+    #[derive(Debug, Merge, PartialEq)]
+    struct S(#[merge(strategy = conflate::flags::intersection)] Features);
+
+    test(S(Features::empty()), S(Features::A), S(Features::empty()));
+    test(S(Features::empty()), S(Features::A), S(Features::B));
+    test(
+        S(Features::B),
+        S(Features::A | Features::B),
+        S(Features::B | Features::C),
+    );
+    test(S(Features::all()), S(Features::all()), S(Features::all()));
+}
+
+#[test]
+fn test_changed() {
+    let mut left = Features::A;
+    assert!(!conflate::flags::union_changed(&mut left, Features::A));
+    assert!(conflate::flags::union_changed(&mut left, Features::B));
+    assert_eq!(Features::A | Features::B, left);
+
+    assert!(!conflate::flags::intersection_changed(
+        &mut left,
+        Features::all()
+    ));
+    assert!(conflate::flags::intersection_changed(
+        &mut left,
+        Features::B
+    ));
+    assert_eq!(Features::B, left);
+}
+
+#[test]
+fn test_set_unset() {
+    let set_unset = |set, unset| SetUnset::new(set, unset);
+
+    test(
+        set_unset(Features::A, Features::B),
+        SetUnset::default(),
+        set_unset(Features::A, Features::B),
+    );
+    test(
+        set_unset(Features::A | Features::B, Features::C),
+        set_unset(Features::A, Features::C),
+        set_unset(Features::B, Features::empty()),
+    );
+    // explicit bits of the higher layer override the lower layer
+    test(
+        set_unset(Features::B, Features::A),
+        set_unset(Features::A, Features::B),
+        set_unset(Features::B, Features::A),
+    );
+
+    let value = set_unset(Features::C, Features::A);
+    assert_eq!(
+        Features::B | Features::C,
+        value.apply(Features::A | Features::B)
+    );
+    assert_eq!(Features::C, value.apply(Features::empty()));
+
+    let mut left = value;
+    assert!(!left.merge_changed(set_unset(Features::C, Features::empty())));
+    assert!(left.merge_changed(set_unset(Features::A, Features::empty())));
+    assert_eq!(
+        set_unset(Features::A | Features::C, Features::empty()),
+        left
+    );
+}
+
+#[test]
+fn test_enumset() {
+    #[derive(Debug, Merge, MergeChanged, PartialEq)]
+    struct S {
+        features: EnumSet<Feature>,
+    }
+
+    test(
+        S {
+            features: Feature::A | Feature::B,
+        },
+        S {
+            features: EnumSet::only(Feature::A),
+        },
+        S {
+            features: EnumSet::only(Feature::B),
+        },
+    );
+
+    let mut left = S {
+        features: Feature::A | Feature::B,
+    };
+    assert!(left
+        .merge_changed_paths(S {
+            features: EnumSet::only(Feature::A),
+        })
+        .is_empty());
+    assert_eq!(
+        vec!["features"],
+        left.merge_changed_paths(S {
+            features: EnumSet::only(Feature::C),
+        })
+    );
+    assert_eq!(EnumSet::all(), left.features);
+}
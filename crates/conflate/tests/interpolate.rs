@@ -0,0 +1,272 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+#![cfg(all(feature = "derive", feature = "interpolate"))]
+
+use std::collections::BTreeMap;
+
+use conflate::interpolate::{Interpolate, Reason, Unresolved};
+
+fn env(name: &str) -> Option<String> {
+    match name {
+        "HOME" => Some("/home/ferris".to_owned()),
+        "USER" => Some("ferris".to_owned()),
+        _ => None,
+    }
+}
+
+fn unresolved(path: &str, reference: &str, reason: Reason) -> Unresolved {
+    Unresolved {
+        path: path.to_owned(),
+        reference: reference.to_owned(),
+        reason,
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Interpolate)]
+struct Repository {
+    path: String,
+    cache: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Interpolate)]
+struct Config {
+    name: String,
+    repository: Repository,
+    retries: u8,
+}
+
+#[test]
+fn test_references() {
+    let mut config = Config {
+        name: "${env:USER}'s backup".to_owned(),
+        repository: Repository {
+            path: "${env:HOME}/backup".to_owned(),
+            cache: Some("${repository.path}/cache".to_owned()),
+        },
+        retries: 3,
+    };
+    config.interpolate_with(env).unwrap();
+    assert_eq!(
+        Config {
+            name: "ferris's backup".to_owned(),
+            repository: Repository {
+                path: "/home/ferris/backup".to_owned(),
+                cache: Some("/home/ferris/backup/cache".to_owned()),
+            },
+            retries: 3,
+        },
+        config
+    );
+}
+
+#[test]
+fn test_chained_references() {
+    let mut config = Config {
+        name: "${repository.cache}".to_owned(),
+        repository: Repository {
+            path: "/srv".to_owned(),
+            cache: Some("${repository.path}/cache".to_owned()),
+        },
+        retries: 0,
+    };
+    config.interpolate_with(env).unwrap();
+    assert_eq!("/srv/cache", config.name);
+}
+
+#[test]
+fn test_escape() {
+    let mut config = Config {
+        name: "$${env:HOME} costs $5".to_owned(),
+        ..Default::default()
+    };
+    config.interpolate_with(env).unwrap();
+    assert_eq!("${env:HOME} costs $5", config.name);
+}
+
+#[test]
+fn test_undefined() {
+    let mut config = Config {
+        name: "${env:MISSING}".to_owned(),
+        repository: Repository {
+            path: "${repository.missing}".to_owned(),
+            cache: Some("${repository.path}".to_owned()),
+        },
+        retries: 0,
+    };
+    let original = config.clone();
+    let err = config.interpolate_with(env).unwrap_err();
+    assert_eq!(
+        &[
+            unresolved("name", "env:MISSING", Reason::Undefined),
+            unresolved("repository.path", "repository.missing", Reason::Undefined),
+        ],
+        err.unresolved()
+    );
+    assert_eq!(
+        "failed to resolve references: ${env:MISSING} in name is not defined, \
+         ${repository.missing} in repository.path is not defined",
+        err.to_string()
+    );
+    assert_eq!(original, config);
+}
+
+#[test]
+fn test_cycle() {
+    let mut config = Config {
+        name: "${repository.path}".to_owned(),
+        repository: Repository {
+            path: "${repository.cache}".to_owned(),
+            cache: Some("${name}".to_owned()),
+        },
+        retries: 0,
+    };
+    let original = config.clone();
+    let err = config.interpolate_with(env).unwrap_err();
+    assert_eq!(
+        &[unresolved("repository.cache", "name", Reason::Cycle)],
+        err.unresolved()
+    );
+    assert_eq!(original, config);
+}
+
+#[test]
+fn test_unterminated() {
+    let mut config = Config {
+        name: "${env:HOME".to_owned(),
+        ..Default::default()
+    };
+    let err = config.interpolate_with(env).unwrap_err();
+    assert_eq!(
+        &[unresolved("name", "env:HOME", Reason::Unterminated)],
+        err.unresolved()
+    );
+}
+
+#[test]
+fn test_collections() {
+    #[derive(Debug, Interpolate)]
+    struct S {
+        dirs: Vec<String>,
+        paths: BTreeMap<String, Option<String>>,
+    }
+
+    let mut s = S {
+        dirs: vec!["${env:HOME}".to_owned(), "${dirs.0}/data".to_owned()],
+        paths: vec![
+            ("cache".to_owned(), Some("${dirs.1}/cache".to_owned())),
+            ("logs".to_owned(), None),
+        ]
+        .into_iter()
+        .collect(),
+    };
+    s.interpolate_with(env).unwrap();
+    assert_eq!(vec!["/home/ferris", "/home/ferris/data"], s.dirs);
+    assert_eq!(Some("/home/ferris/data/cache"), s.paths["cache"].as_deref());
+    assert_eq!(None, s.paths["logs"]);
+}
+
+#[test]
+fn test_wrappers() {
+    use conflate::bool::TriState;
+    use conflate::{Locked, Secret};
+
+    #[derive(Debug, Interpolate)]
+    struct S {
+        dir: Locked<String>,
+        password: Option<Secret<String>>,
+        color: TriState,
+    }
+
+    let mut s = S {
+        dir: Locked::new_locked("${env:HOME}".to_owned()),
+        password: Some(Secret::new("${env:USER}-secret".to_owned())),
+        color: TriState::On,
+    };
+    s.interpolate_with(env).unwrap();
+    assert_eq!("/home/ferris", s.dir.get());
+    assert!(s.dir.is_locked());
+    assert_eq!("ferris-secret", s.password.unwrap().expose());
+    assert_eq!(TriState::On, s.color);
+}
+
+#[test]
+fn test_merge_visit() {
+    use std::path::PathBuf;
+
+    use conflate::Merge;
+
+    #[derive(Debug, Merge)]
+    #[merge(visit, strategy = conflate::option::overwrite_none)]
+    struct S {
+        dir: Option<PathBuf>,
+        cache: Option<String>,
+        #[merge(skip)]
+        template: String,
+    }
+
+    let mut s = S {
+        dir: Some("${env:HOME}".into()),
+        cache: Some("${dir}/cache".to_owned()),
+        template: "${missing}".to_owned(),
+    };
+    s.interpolate_with(env).unwrap();
+    assert_eq!(Some(PathBuf::from("/home/ferris")), s.dir);
+    assert_eq!(Some("/home/ferris/cache"), s.cache.as_deref());
+    assert_eq!("${missing}", s.template);
+}
+
+#[test]
+fn test_skip() {
+    #[derive(Debug, Interpolate)]
+    struct S {
+        dir: String,
+        #[merge(skip_interpolate)]
+        template: String,
+    }
+
+    let mut s = S {
+        dir: "${env:HOME}".to_owned(),
+        template: "${missing}".to_owned(),
+    };
+    s.interpolate_with(env).unwrap();
+    assert_eq!("/home/ferris", s.dir);
+    assert_eq!("${missing}", s.template);
+
+    s.dir = "${template}".to_owned();
+    let err = s.interpolate_with(env).unwrap_err();
+    assert_eq!(
+        &[unresolved("dir", "template", Reason::Undefined)],
+        err.unresolved()
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_keys() {
+    #[derive(Debug, Interpolate, serde::Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    struct Repository {
+        base_dir: String,
+        #[serde(rename = "dir")]
+        data_dir: String,
+    }
+
+    #[derive(Debug, Interpolate, serde::Deserialize)]
+    struct S {
+        #[serde(flatten)]
+        repository: Repository,
+        cache: String,
+    }
+
+    let mut s = S {
+        repository: Repository {
+            base_dir: "/srv".to_owned(),
+            data_dir: "${base-dir}/data".to_owned(),
+        },
+        cache: "${dir}/cache".to_owned(),
+    };
+    s.interpolate_with(env).unwrap();
+    assert_eq!("/srv/data", s.repository.data_dir);
+    assert_eq!("/srv/data/cache", s.cache);
+}
@@ -0,0 +1,192 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+#![cfg(all(feature = "derive", feature = "async"))]
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use conflate::layers::{AsyncSource, BoxFuture, Error, ErrorKind, Layers, ValueSource};
+use conflate::trust::Trust;
+use conflate::{Merge, MergeTrusted};
+
+#[derive(Clone, Debug, Default, PartialEq, Merge)]
+struct Config {
+    #[merge(strategy = conflate::vec::append)]
+    layers: Vec<&'static str>,
+}
+
+/// A source that returns its name as the only layer after the given delay.
+struct Delayed {
+    name: &'static str,
+    delay: u64,
+}
+
+impl AsyncSource<Config> for Delayed {
+    fn name(&self) -> String {
+        self.name.to_owned()
+    }
+
+    fn load(&self) -> BoxFuture<'_, Result<Option<Config>, Error>> {
+        Box::pin(async move {
+            tokio::time::sleep(Duration::from_millis(self.delay)).await;
+            match self.name {
+                "missing" => Ok(None),
+                "broken" => Err(Error::new(self.name(), ErrorKind::Parse("broken".into()))),
+                name => Ok(Some(Config { layers: vec![name] })),
+            }
+        })
+    }
+}
+
+fn delayed(name: &'static str, delay: u64) -> Delayed {
+    Delayed { name, delay }
+}
+
+#[tokio::test]
+async fn test_deterministic_order() {
+    let layers = Layers::new()
+        .with_async_source(delayed("remote", 30))
+        .with_source(ValueSource::new(
+            "value",
+            Config {
+                layers: vec!["value"],
+            },
+        ))
+        .with_async_source(delayed("missing", 20))
+        .with_async_source(delayed("secrets", 0));
+
+    assert_eq!(
+        vec!["remote", "value", "secrets"],
+        layers.load_async().await.unwrap().layers
+    );
+    assert_eq!(
+        vec!["remote", "value", "missing", "secrets"],
+        layers.names().collect::<Vec<_>>()
+    );
+}
+
+#[tokio::test]
+async fn test_concurrent() {
+    struct Wait(Arc<AtomicBool>);
+    struct Signal(Arc<AtomicBool>);
+
+    impl AsyncSource<Config> for Wait {
+        fn name(&self) -> String {
+            "wait".to_owned()
+        }
+
+        fn load(&self) -> BoxFuture<'_, Result<Option<Config>, Error>> {
+            Box::pin(async move {
+                while !self.0.load(Ordering::SeqCst) {
+                    tokio::task::yield_now().await;
+                }
+                Ok(Some(Config {
+                    layers: vec!["wait"],
+                }))
+            })
+        }
+    }
+
+    impl AsyncSource<Config> for Signal {
+        fn name(&self) -> String {
+            "signal".to_owned()
+        }
+
+        fn load(&self) -> BoxFuture<'_, Result<Option<Config>, Error>> {
+            Box::pin(async move {
+                self.0.store(true, Ordering::SeqCst);
+                Ok(Some(Config {
+                    layers: vec!["signal"],
+                }))
+            })
+        }
+    }
+
+    // the first source only finishes after the second source has been started
+    let flag = Arc::new(AtomicBool::new(false));
+    let layers = Layers::new()
+        .with_async_source(Wait(flag.clone()))
+        .with_async_source(Signal(flag));
+    let config = tokio::time::timeout(Duration::from_secs(10), layers.load_async())
+        .await
+        .expect("sources are not loaded concurrently")
+        .unwrap();
+    assert_eq!(vec!["wait", "signal"], config.layers);
+}
+
+#[tokio::test]
+async fn test_errors() {
+    // the first error in merge order is returned, even if a later source fails first
+    let layers = Layers::new()
+        .with_async_source(delayed("broken", 20))
+        .with_async_source(delayed("broken", 0));
+    let err = layers.load_async().await.unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::Parse(_)));
+    assert_eq!("broken", err.source_name());
+
+    let layers = Layers::new()
+        .with_source(ValueSource::new("value", Config::default()))
+        .with_async_source(delayed("remote", 0));
+    let err = layers.load().unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::Async));
+    assert_eq!("remote can only be loaded asynchronously", err.to_string());
+}
+
+#[tokio::test]
+async fn test_trusted() {
+    #[derive(Clone, Debug, Default, PartialEq, Merge, MergeTrusted)]
+    #[merge(strategy = conflate::option::overwrite_with_some)]
+    struct Config {
+        repository: Option<String>,
+        #[merge(min_trust = "system")]
+        run_before: Option<String>,
+    }
+
+    struct Remote(Trust);
+
+    impl AsyncSource<Config> for Remote {
+        fn name(&self) -> String {
+            "remote".to_owned()
+        }
+
+        fn load(&self) -> BoxFuture<'_, Result<Option<Config>, Error>> {
+            Box::pin(async move {
+                Ok(Some(Config {
+                    repository: Some("/remote".to_owned()),
+                    run_before: Some("rm".to_owned()),
+                }))
+            })
+        }
+
+        fn trust(&self) -> Trust {
+            self.0
+        }
+    }
+
+    let system = Config {
+        repository: Some("/srv".to_owned()),
+        run_before: Some("mount".to_owned()),
+    };
+    let layers = Layers::new()
+        .with_source(ValueSource::new("system", system.clone()).with_trust(Trust::System))
+        .with_async_source(Remote(Trust::Local));
+    let (value, rejected) = layers.load_trusted_async().await.unwrap();
+    assert_eq!(Some("/remote"), value.repository.as_deref());
+    assert_eq!(Some("mount"), value.run_before.as_deref());
+    assert_eq!(1, rejected.len());
+    assert_eq!("remote", rejected[0].0);
+    assert_eq!(["run_before"], rejected[0].1.paths());
+
+    // trusted async sources can set all fields
+    let layers = Layers::new()
+        .with_source(ValueSource::new("system", system).with_trust(Trust::System))
+        .with_async_source(Remote(Trust::System));
+    let (value, rejected) = layers.load_trusted_async().await.unwrap();
+    assert_eq!(Some("rm"), value.run_before.as_deref());
+    assert!(rejected.is_empty());
+
+    let err = layers.load_trusted().unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::Async));
+}
@@ -0,0 +1,212 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+#![cfg(all(feature = "derive", feature = "std"))]
+
+use conflate::{Locked, Merge, MergeChanged, MergePreview, MergeRef, TryMerge};
+
+#[derive(Clone, Debug, PartialEq, Merge, MergeRef, MergeChanged, MergePreview, TryMerge)]
+struct Repository {
+    #[merge(lockable, strategy = conflate::option::overwrite_with_some)]
+    path: Locked<Option<String>>,
+    #[merge(strategy = conflate::ord::max)]
+    retries: u8,
+}
+
+#[derive(Clone, Debug, PartialEq, Merge, MergeRef, MergeChanged, MergePreview, TryMerge)]
+#[merge(precedence = "right")]
+struct Config {
+    #[merge(lockable)]
+    name: Locked<Option<String>>,
+    repository: Repository,
+    backup: Locked<Repository>,
+}
+
+fn repository(path: Option<&str>, locked: bool, retries: u8) -> Repository {
+    let path = path.map(ToOwned::to_owned);
+    Repository {
+        path: if locked {
+            Locked::new_locked(path)
+        } else {
+            Locked::new(path)
+        },
+        retries,
+    }
+}
+
+fn config(name: Option<&str>, locked: bool) -> Config {
+    let name = name.map(ToOwned::to_owned);
+    Config {
+        name: if locked {
+            Locked::new_locked(name)
+        } else {
+            Locked::new(name)
+        },
+        repository: repository(None, false, 0),
+        backup: Locked::new(repository(None, false, 0)),
+    }
+}
+
+#[test]
+fn test_merge() {
+    let mut left = config(Some("left"), false);
+    left.merge(config(Some("admin"), true));
+    assert_eq!(Some("admin"), left.name.as_deref());
+    assert!(left.name.is_locked());
+
+    left.merge(config(Some("user"), false));
+    assert_eq!(Some("admin"), left.name.as_deref());
+    assert!(left.name.is_locked());
+
+    left.merge(config(None, true));
+    assert_eq!(Some("admin"), left.name.as_deref());
+}
+
+#[test]
+fn test_merge_lock_keeps_right_value() {
+    // the strategy would keep the value of left, so the locking layer did not set it
+    let mut left = repository(Some("/home"), false, 0);
+    left.merge(repository(Some("/srv"), true, 0));
+    assert_eq!(Some("/srv"), left.path.as_deref());
+    assert!(left.path.is_locked());
+
+    let mut left = Locked::new(Some(1));
+    conflate::locked::merge_with(
+        &mut left,
+        Locked::new_locked(Some(2)),
+        conflate::option::overwrite_none,
+    );
+    assert_eq!(Locked::new_locked(Some(2)), left);
+
+    let mut left = Locked::new(Some(1));
+    conflate::locked::merge_with(
+        &mut left,
+        Locked::new(Some(2)),
+        conflate::option::overwrite_none,
+    );
+    assert_eq!(Locked::new(Some(1)), left);
+
+    let mut left = config(Some("user"), false);
+    left.merge_ref(&config(Some("admin"), true));
+    assert_eq!(config(Some("admin"), true), left);
+
+    let mut left = config(None, false);
+    left.backup = Locked::new(repository(Some("/home"), false, 0));
+    let mut right = config(None, false);
+    right.backup = Locked::new_locked(repository(Some("/srv"), false, 0));
+    let paths: Vec<_> = left
+        .merge_preview(&right)
+        .into_iter()
+        .map(|c| c.path)
+        .collect();
+    assert_eq!(["backup.path"], paths.as_slice());
+}
+
+#[test]
+fn test_merge_locked_struct() {
+    let mut left = config(None, false);
+    let mut right = config(None, false);
+    right.backup = Locked::new_locked(repository(Some("/srv"), false, 1));
+    left.merge(right);
+    assert_eq!(repository(Some("/srv"), false, 1), *left.backup);
+    assert!(left.backup.is_locked());
+
+    let mut right = config(None, false);
+    right.backup = Locked::new(repository(Some("/tmp"), false, 2));
+    left.merge(right);
+    assert_eq!(repository(Some("/srv"), false, 1), *left.backup);
+}
+
+#[test]
+fn test_try_merge() {
+    let mut left = config(Some("admin"), true);
+    left.repository = repository(Some("/srv"), true, 0);
+    left.backup = Locked::new_locked(repository(None, false, 0));
+
+    let mut right = config(Some("admin"), false);
+    right.repository = repository(Some("/tmp"), false, 3);
+    right.backup = Locked::new(repository(None, false, 1));
+
+    let err = left.clone().try_merge(right.clone()).unwrap_err();
+    assert_eq!(["repository.path", "backup"], err.paths());
+    assert_eq!(
+        "cannot change fields: repository.path, backup",
+        err.to_string()
+    );
+
+    left.try_merge(right).unwrap_err();
+    assert_eq!(Some("/srv"), left.repository.path.as_deref());
+    assert_eq!(3, left.repository.retries);
+    assert_eq!(0, left.backup.retries);
+
+    let mut right = config(None, false);
+    right.repository = repository(None, false, 0);
+    left.try_merge(right).unwrap();
+}
+
+#[test]
+fn test_other_derives() {
+    let left = config(Some("admin"), true);
+    let right = config(Some("user"), false);
+
+    let mut merged = left.clone();
+    merged.merge_ref(&right);
+    assert_eq!(left, merged);
+
+    assert!(left.merge_preview(&right).is_empty());
+
+    let mut merged = left.clone();
+    assert!(!merged.merge_changed(right));
+    assert_eq!(left, merged);
+
+    let mut merged = config(Some("user"), false);
+    assert_eq!(vec!["name"], merged.merge_changed_paths(left.clone()));
+    assert_eq!(left, merged);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_deserialize_merge() {
+    use conflate::DeserializeMerge;
+
+    #[derive(Debug, Default, PartialEq, DeserializeMerge, serde::Deserialize)]
+    #[merge(strategy = conflate::option::overwrite_with_some)]
+    struct Repository {
+        path: Option<String>,
+    }
+
+    #[derive(Debug, Default, PartialEq, DeserializeMerge)]
+    struct Config {
+        #[merge(lockable, strategy = conflate::option::overwrite_with_some)]
+        name: Locked<Option<String>>,
+        repository: Locked<Repository>,
+    }
+
+    let mut config = Config::default();
+    let input = r#"{"name": "user", "repository": {"path": "/tmp"}}"#;
+    config
+        .deserialize_merge(&mut serde_json::Deserializer::from_str(input))
+        .unwrap();
+    assert_eq!(Some("user"), config.name.as_deref());
+    assert_eq!(Some("/tmp"), config.repository.path.as_deref());
+    assert!(!config.name.is_locked());
+
+    config.name.lock();
+    config.repository.lock();
+    let input = r#"{"name": "other", "repository": {"path": "/srv"}}"#;
+    config
+        .deserialize_merge(&mut serde_json::Deserializer::from_str(input))
+        .unwrap();
+    assert_eq!(Some("user"), config.name.as_deref());
+    assert_eq!(Some("/tmp"), config.repository.path.as_deref());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_deserialize_unlocked() {
+    // configuration files cannot lock values
+    let value: Locked<Option<String>> = serde_json::from_str(r#""/srv""#).unwrap();
+    assert_eq!(Locked::new(Some("/srv".to_owned())), value);
+    assert!(!value.is_locked());
+    assert!(serde_json::from_str::<Locked<u8>>(r#"{"value": 1, "locked": true}"#).is_err());
+}
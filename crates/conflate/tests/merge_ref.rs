@@ -0,0 +1,194 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+#![cfg(feature = "derive")]
+
+use conflate::{Merge, MergeRef};
+
+fn test<T: std::fmt::Debug + MergeRef + PartialEq>(expected: T, mut left: T, right: &T) {
+    left.merge_ref(right);
+    assert_eq!(expected, left);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_strategy_ref() {
+    #[derive(Debug, MergeRef, PartialEq)]
+    struct S {
+        #[merge(strategy_ref = conflate::option::overwrite_none_ref)]
+        field1: Option<String>,
+        #[merge(strategy_ref = conflate::vec::append_cloned)]
+        field2: Vec<String>,
+    }
+
+    impl S {
+        pub fn new(field1: Option<&str>, field2: &[&str]) -> S {
+            S {
+                field1: field1.map(ToOwned::to_owned),
+                field2: field2.iter().copied().map(ToOwned::to_owned).collect(),
+            }
+        }
+    }
+
+    let defaults = S::new(Some("default"), &["a"]);
+    test(
+        S::new(Some("own"), &["b", "a"]),
+        S::new(Some("own"), &["b"]),
+        &defaults,
+    );
+    test(
+        S::new(Some("default"), &["a"]),
+        S::new(None, &[]),
+        &defaults,
+    );
+    test(
+        S::new(None, &["b"]),
+        S::new(None, &["b"]),
+        &S::new(None, &[]),
+    );
+}
+
+#[test]
+fn test_strategy_fallback() {
+    #[derive(Debug, MergeRef, PartialEq)]
+    struct S {
+        #[merge(strategy = conflate::option::overwrite_with_some)]
+        field1: Option<String>,
+        #[merge(skip)]
+        field2: String,
+        #[merge(strategy = conflate::bool::overwrite_false)]
+        field3: bool,
+    }
+
+    test(
+        S {
+            field1: Some("new".to_owned()),
+            field2: "own".to_owned(),
+            field3: true,
+        },
+        S {
+            field1: Some("old".to_owned()),
+            field2: "own".to_owned(),
+            field3: false,
+        },
+        &S {
+            field1: Some("new".to_owned()),
+            field2: "other".to_owned(),
+            field3: true,
+        },
+    );
+}
+
+#[test]
+fn test_default_strategy() {
+    #[derive(Debug, MergeRef, PartialEq)]
+    #[merge(strategy_ref = conflate::option::overwrite_none_ref)]
+    struct S {
+        field1: Option<String>,
+        #[merge(strategy = conflate::option::overwrite_with_some)]
+        field2: Option<String>,
+        #[merge(strategy_ref = conflate::option::overwrite_with_some_ref)]
+        field3: Option<String>,
+    }
+
+    let some = |s: &str| Some(s.to_owned());
+    test(
+        S {
+            field1: some("left"),
+            field2: some("right"),
+            field3: some("right"),
+        },
+        S {
+            field1: some("left"),
+            field2: some("left"),
+            field3: some("left"),
+        },
+        &S {
+            field1: some("right"),
+            field2: some("right"),
+            field3: some("right"),
+        },
+    );
+}
+
+#[test]
+fn test_nested() {
+    #[derive(Clone, Debug, Merge, MergeRef, PartialEq)]
+    struct N(#[merge(strategy = conflate::ord::max)] u8);
+
+    #[derive(Debug, Merge, MergeRef, PartialEq)]
+    struct S {
+        nested: N,
+        #[merge(
+            strategy = conflate::option::recurse,
+            strategy_ref = conflate::option::recurse_ref
+        )]
+        optional: Option<N>,
+    }
+
+    test(
+        S {
+            nested: N(2),
+            optional: Some(N(3)),
+        },
+        S {
+            nested: N(1),
+            optional: Some(N(2)),
+        },
+        &S {
+            nested: N(2),
+            optional: Some(N(3)),
+        },
+    );
+    test(
+        S {
+            nested: N(1),
+            optional: Some(N(3)),
+        },
+        S {
+            nested: N(1),
+            optional: None,
+        },
+        &S {
+            nested: N(0),
+            optional: Some(N(3)),
+        },
+    );
+
+    let mut left = S {
+        nested: N(1),
+        optional: None,
+    };
+    left.merge(S {
+        nested: N(1),
+        optional: Some(N(1)),
+    });
+    assert_eq!(
+        S {
+            nested: N(1),
+            optional: Some(N(1)),
+        },
+        left
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_unnamed_fields() {
+    #[derive(Debug, MergeRef, PartialEq)]
+    struct S(
+        #[merge(strategy_ref = conflate::vec::prepend_cloned)] Vec<u8>,
+        #[merge(strategy_ref = conflate::vec::overwrite_empty_cloned)] Vec<u8>,
+    );
+
+    test(
+        S(vec![3, 1, 2], vec![4]),
+        S(vec![1, 2], vec![]),
+        &S(vec![3], vec![4]),
+    );
+    test(
+        S(vec![1], vec![5]),
+        S(vec![1], vec![5]),
+        &S(vec![], vec![4]),
+    );
+}
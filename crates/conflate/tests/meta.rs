@@ -0,0 +1,210 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+#![cfg(feature = "derive")]
+
+use conflate::{Merge, MergeMeta};
+
+#[test]
+fn test_named_fields() {
+    #[derive(Merge, MergeMeta)]
+    #[allow(dead_code)]
+    struct S {
+        /// The first field.
+        ///
+        /// It is merged using a strategy.
+        #[merge(strategy = conflate::option::overwrite_none)]
+        field1: Option<usize>,
+        #[merge(skip)]
+        field2: std::collections::HashMap<String, Vec<u8>>,
+        #[merge(strategy = ::conflate::ord::max)]
+        r#type: u8,
+    }
+
+    let names: Vec<_> = S::FIELDS.iter().map(|f| f.name).collect();
+    assert_eq!(vec!["field1", "field2", "type"], names);
+
+    let field1 = &S::FIELDS[0];
+    assert_eq!(Some("field1"), field1.key);
+    assert_eq!("Option<usize>", field1.ty);
+    assert_eq!(Some("conflate::option::overwrite_none"), field1.strategy);
+    assert!(!field1.skip);
+    assert_eq!(
+        "The first field.\n\nIt is merged using a strategy.",
+        field1.doc
+    );
+    assert!(field1.nested.is_none());
+
+    let field2 = &S::FIELDS[1];
+    assert_eq!("std::collections::HashMap<String, Vec<u8>>", field2.ty);
+    assert_eq!(None, field2.strategy);
+    assert!(field2.skip);
+    assert_eq!("", field2.doc);
+
+    assert_eq!(Some("::conflate::ord::max"), S::FIELDS[2].strategy);
+}
+
+#[test]
+fn test_unnamed_fields() {
+    #[derive(Merge, MergeMeta)]
+    #[allow(dead_code)]
+    struct S(
+        #[merge(strategy = conflate::option::overwrite_none)] Option<usize>,
+        #[merge(skip)] &'static str,
+    );
+
+    let names: Vec<_> = S::FIELDS.iter().map(|f| f.name).collect();
+    assert_eq!(vec!["0", "1"], names);
+    assert_eq!(Some("1"), S::FIELDS[1].key);
+    assert_eq!("&'static str", S::FIELDS[1].ty);
+}
+
+#[test]
+fn test_default_strategy() {
+    #[derive(Merge, MergeMeta)]
+    #[merge(strategy = conflate::option::overwrite_none)]
+    #[allow(dead_code)]
+    struct S {
+        field1: Option<usize>,
+        #[merge(strategy = conflate::option::overwrite_with_some)]
+        field2: Option<usize>,
+    }
+
+    assert_eq!(
+        Some("conflate::option::overwrite_none"),
+        S::FIELDS[0].strategy
+    );
+    assert_eq!(
+        Some("conflate::option::overwrite_with_some"),
+        S::FIELDS[1].strategy
+    );
+}
+
+#[test]
+fn test_nested() {
+    #[derive(Merge, MergeMeta)]
+    #[allow(dead_code)]
+    struct N {
+        #[merge(strategy = conflate::bool::overwrite_false)]
+        flag: bool,
+    }
+
+    #[derive(Merge, MergeMeta)]
+    #[allow(dead_code)]
+    struct S {
+        nested: N,
+        #[merge(strategy = conflate::option::recurse)]
+        optional: Option<N>,
+    }
+
+    assert_eq!(None, S::FIELDS[0].strategy);
+    assert_eq!(vec!["flag"], names(S::FIELDS[0].fields()));
+    assert!(S::FIELDS[1].fields().is_empty());
+
+    let flag = S::field(&["nested", "flag"]).unwrap();
+    assert_eq!("bool", flag.ty);
+    assert!(S::field(&["nested"]).is_some());
+    assert!(S::field(&["optional", "flag"]).is_none());
+    assert!(S::field(&["nested", "missing"]).is_none());
+    assert!(S::field(&[]).is_none());
+}
+
+#[test]
+fn test_manual_impl() {
+    #[derive(Debug, PartialEq)]
+    struct N(usize);
+
+    impl Merge for N {
+        fn merge(&mut self, other: Self) {
+            self.0 += other.0;
+        }
+    }
+
+    impl MergeMeta for N {}
+
+    #[derive(Debug, Merge, MergeMeta, PartialEq)]
+    struct S {
+        nested: N,
+    }
+
+    let mut s = S { nested: N(1) };
+    s.merge(S { nested: N(2) });
+    assert_eq!(S { nested: N(3) }, s);
+    assert!(S::FIELDS[0].fields().is_empty());
+}
+
+#[test]
+fn test_merge_without_meta() {
+    #[derive(Debug, PartialEq)]
+    struct N(usize);
+
+    impl Merge for N {
+        fn merge(&mut self, other: Self) {
+            self.0 += other.0;
+        }
+    }
+
+    // deriving Merge does not require MergeMeta for the field types
+    #[derive(Debug, Merge, PartialEq)]
+    struct S {
+        nested: N,
+    }
+
+    let mut s = S { nested: N(1) };
+    s.merge(S { nested: N(2) });
+    assert_eq!(S { nested: N(3) }, s);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_keys() {
+    #[derive(serde::Deserialize, Merge, MergeMeta)]
+    #[serde(rename_all = "kebab-case")]
+    #[allow(dead_code)]
+    struct N {
+        #[merge(strategy = conflate::vec::append)]
+        run_before: Vec<String>,
+    }
+
+    #[derive(serde::Deserialize, Merge, MergeMeta)]
+    #[allow(dead_code)]
+    struct S {
+        #[serde(rename = "global-hooks")]
+        hooks: N,
+        #[serde(flatten)]
+        flattened: N,
+    }
+
+    assert_eq!(Some("global-hooks"), S::FIELDS[0].key);
+    assert_eq!(None, S::FIELDS[1].key);
+    assert_eq!(Some("run-before"), S::FIELDS[0].fields()[0].key);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_keys_match_field_names() {
+    use conflate::DeserializeMerge;
+
+    #[derive(serde::Deserialize, Merge, MergeMeta, DeserializeMerge)]
+    #[serde(rename_all = "kebab-case")]
+    #[allow(dead_code)]
+    struct S {
+        #[merge(strategy = conflate::option::overwrite_none)]
+        repository: Option<String>,
+        #[merge(strategy = conflate::option::overwrite_none)]
+        #[serde(rename = "hook")]
+        run_before: Option<String>,
+        #[merge(skip)]
+        #[serde(skip)]
+        cache: Option<String>,
+    }
+
+    let keys: Vec<_> = S::FIELDS.iter().map(|f| f.key).collect();
+    let field_names: Vec<_> = S::FIELD_NAMES.iter().map(|f| f.key).collect();
+    assert_eq!(vec![Some("repository"), Some("hook"), None], keys);
+    assert_eq!(field_names, keys);
+}
+
+fn names(fields: &[conflate::FieldMeta]) -> Vec<&'static str> {
+    fields.iter().map(|f| f.name).collect()
+}
@@ -0,0 +1,190 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+#![cfg(all(feature = "derive", feature = "std"))]
+
+use std::collections::{BTreeMap, HashMap};
+
+use conflate::{Merge, MergeChanged, MergeMeta, MergeRef};
+
+fn test<T: std::fmt::Debug + Merge + PartialEq>(expected: T, mut left: T, right: T) {
+    left.merge(right);
+    assert_eq!(expected, left);
+}
+
+fn map<M: FromIterator<(String, u8)>>(entries: &[(&str, u8)]) -> M {
+    entries.iter().map(|(k, v)| ((*k).to_owned(), *v)).collect()
+}
+
+#[derive(Clone, Debug, PartialEq, Merge, MergeRef, MergeChanged, MergeMeta)]
+#[merge(precedence = "left")]
+struct Left {
+    option: Option<u8>,
+    hashmap: HashMap<String, u8>,
+    btreemap: BTreeMap<String, u8>,
+    flag: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Merge, MergeRef, MergeChanged, MergeMeta)]
+#[merge(precedence = "right")]
+struct Right {
+    option: Option<u8>,
+    hashmap: HashMap<String, u8>,
+    btreemap: BTreeMap<String, u8>,
+    flag: bool,
+}
+
+#[test]
+fn test_left() {
+    test(
+        Left {
+            option: Some(1),
+            hashmap: map(&[("a", 1), ("b", 2)]),
+            btreemap: map(&[("a", 1), ("b", 2)]),
+            flag: true,
+        },
+        Left {
+            option: Some(1),
+            hashmap: map(&[("a", 1)]),
+            btreemap: map(&[("a", 1)]),
+            flag: true,
+        },
+        Left {
+            option: Some(2),
+            hashmap: map(&[("a", 2), ("b", 2)]),
+            btreemap: map(&[("a", 2), ("b", 2)]),
+            flag: false,
+        },
+    );
+    test(
+        Left {
+            option: Some(2),
+            hashmap: map(&[]),
+            btreemap: map(&[]),
+            flag: true,
+        },
+        Left {
+            option: None,
+            hashmap: map(&[]),
+            btreemap: map(&[]),
+            flag: false,
+        },
+        Left {
+            option: Some(2),
+            hashmap: map(&[]),
+            btreemap: map(&[]),
+            flag: true,
+        },
+    );
+}
+
+#[test]
+fn test_right() {
+    test(
+        Right {
+            option: Some(2),
+            hashmap: map(&[("a", 2), ("b", 2)]),
+            btreemap: map(&[("a", 2), ("b", 2)]),
+            flag: true,
+        },
+        Right {
+            option: Some(1),
+            hashmap: map(&[("a", 1)]),
+            btreemap: map(&[("a", 1)]),
+            flag: true,
+        },
+        Right {
+            option: Some(2),
+            hashmap: map(&[("a", 2), ("b", 2)]),
+            btreemap: map(&[("a", 2), ("b", 2)]),
+            flag: false,
+        },
+    );
+    test(
+        Right {
+            option: Some(1),
+            hashmap: map(&[]),
+            btreemap: map(&[]),
+            flag: true,
+        },
+        Right {
+            option: Some(1),
+            hashmap: map(&[]),
+            btreemap: map(&[]),
+            flag: false,
+        },
+        Right {
+            option: None,
+            hashmap: map(&[]),
+            btreemap: map(&[]),
+            flag: true,
+        },
+    );
+}
+
+#[test]
+fn test_other_derives() {
+    let mut left = Right {
+        option: Some(1),
+        hashmap: map(&[("a", 1)]),
+        btreemap: map(&[]),
+        flag: false,
+    };
+    let right = Right {
+        option: Some(2),
+        hashmap: map(&[("a", 1)]),
+        btreemap: map(&[]),
+        flag: false,
+    };
+
+    let mut merged = left.clone();
+    merged.merge_ref(&right);
+    assert_eq!(right, merged);
+
+    assert!(left.merge_changed(right.clone()));
+    assert_eq!(right, left);
+    assert!(!left.merge_changed(right));
+
+    let option = &Right::FIELDS[0];
+    assert_eq!(
+        Some("::conflate::option::overwrite_with_some"),
+        option.strategy
+    );
+    let flag = &Left::FIELDS[3];
+    assert_eq!(Some("::conflate::bool::overwrite_false"), flag.strategy);
+}
+
+#[test]
+fn test_overrides() {
+    #[derive(Debug, PartialEq, Merge)]
+    #[merge(precedence = "right", strategy = conflate::vec::append)]
+    struct S {
+        #[merge(strategy = conflate::option::overwrite_none)]
+        pinned: Option<u8>,
+        option: Option<u8>,
+        list: Vec<u8>,
+        #[merge(strategy = conflate::bool::overwrite_false)]
+        flag: bool,
+    }
+
+    test(
+        S {
+            pinned: Some(1),
+            option: Some(2),
+            list: vec![1, 2],
+            flag: true,
+        },
+        S {
+            pinned: Some(1),
+            option: Some(1),
+            list: vec![1],
+            flag: false,
+        },
+        S {
+            pinned: Some(2),
+            option: Some(2),
+            list: vec![2],
+            flag: true,
+        },
+    );
+}
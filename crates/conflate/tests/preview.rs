@@ -0,0 +1,180 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+#![cfg(all(feature = "derive", feature = "std"))]
+
+use conflate::preview::Change;
+use conflate::{Merge, MergePreview};
+
+fn test<T: MergePreview>(expected: &[(&str, &str, &str)], left: &T, right: &T) {
+    let changes: Vec<_> = left
+        .merge_preview(right)
+        .into_iter()
+        .map(|c| (c.path, c.old, c.new))
+        .collect();
+    let expected: Vec<_> = expected
+        .iter()
+        .map(|(p, o, n)| ((*p).to_owned(), (*o).to_owned(), (*n).to_owned()))
+        .collect();
+    assert_eq!(expected, changes);
+}
+
+#[test]
+fn test_strategies() {
+    #[derive(Merge, MergePreview)]
+    #[allow(dead_code)]
+    struct S {
+        #[merge(strategy = conflate::option::overwrite_none)]
+        field1: Option<u8>,
+        #[merge(strategy = conflate::vec::append)]
+        field2: Vec<u8>,
+        #[merge(skip)]
+        field3: u8,
+    }
+
+    let left = S {
+        field1: None,
+        field2: vec![1],
+        field3: 1,
+    };
+    let right = S {
+        field1: Some(2),
+        field2: vec![2],
+        field3: 2,
+    };
+    test(
+        &[("field1", "None", "Some(2)"), ("field2", "[1]", "[1, 2]")],
+        &left,
+        &right,
+    );
+    test(
+        &[],
+        &right,
+        &S {
+            field1: None,
+            field2: vec![],
+            field3: 3,
+        },
+    );
+
+    // the values are not modified
+    assert_eq!(None, left.field1);
+    assert_eq!(vec![2], right.field2);
+}
+
+#[test]
+fn test_consistent_with_merge() {
+    #[derive(Clone, Debug, Default, Merge, MergePreview, PartialEq)]
+    #[merge(strategy = conflate::option::overwrite_none)]
+    struct S {
+        field1: Option<u8>,
+        #[merge(strategy = conflate::ord::max)]
+        field2: u8,
+        #[merge(strategy_ref = conflate::option::overwrite_with_some_ref)]
+        field3: Option<u8>,
+    }
+
+    let left = S {
+        field1: Some(1),
+        field2: 5,
+        field3: Some(1),
+    };
+    let right = S {
+        field1: Some(2),
+        field2: 7,
+        field3: Some(2),
+    };
+    test(
+        &[("field2", "5", "7"), ("field3", "Some(1)", "Some(2)")],
+        &left,
+        &right,
+    );
+
+    let mut merged = left.clone();
+    merged.merge(right.clone());
+    assert_eq!(Some(1), merged.field1);
+    assert_eq!(7, merged.field2);
+}
+
+#[test]
+fn test_nested() {
+    #[derive(Default, Merge, MergePreview)]
+    struct N {
+        #[merge(strategy = conflate::bool::overwrite_false)]
+        flag: bool,
+    }
+
+    #[derive(Default, Merge, MergePreview)]
+    struct S {
+        nested: N,
+        #[merge(strategy = conflate::option::overwrite_none)]
+        value: Option<u8>,
+    }
+
+    let right = S {
+        nested: N { flag: true },
+        value: Some(1),
+    };
+    test(
+        &[
+            ("nested.flag", "false", "true"),
+            ("value", "None", "Some(1)"),
+        ],
+        &S::default(),
+        &right,
+    );
+}
+
+#[test]
+fn test_unnamed_fields() {
+    #[derive(Merge, MergePreview)]
+    struct S(
+        #[merge(strategy = conflate::ord::min)] u8,
+        #[merge(strategy = conflate::ord::max)] u8,
+    );
+
+    test(&[("1", "1", "2")], &S(1, 1), &S(2, 2));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_keys() {
+    #[derive(Default, serde::Deserialize, Merge, MergePreview)]
+    #[serde(rename_all = "kebab-case")]
+    struct N {
+        #[merge(strategy = conflate::vec::append)]
+        run_before: Vec<String>,
+    }
+
+    #[derive(Default, serde::Deserialize, Merge, MergePreview)]
+    struct S {
+        #[serde(rename = "global-hooks")]
+        hooks: N,
+        #[serde(flatten)]
+        flattened: N,
+    }
+
+    let right = S {
+        hooks: N {
+            run_before: vec!["a".to_owned()],
+        },
+        flattened: N {
+            run_before: vec!["b".to_owned()],
+        },
+    };
+    test(
+        &[
+            ("global-hooks.run-before", "[]", r#"["a"]"#),
+            ("run-before", "[]", r#"["b"]"#),
+        ],
+        &S::default(),
+        &right,
+    );
+}
+
+#[test]
+fn test_change_display() {
+    let change = Change::new("path", &Some(1), &None).with_parent("parent");
+    assert_eq!("parent.path: Some(1) -> None", change.to_string());
+    assert_eq!(change.clone(), change.with_parent(""));
+}
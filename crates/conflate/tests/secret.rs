@@ -0,0 +1,113 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+#![cfg(all(feature = "derive", feature = "std"))]
+
+use conflate::secret::SecretValue;
+use conflate::{Merge, MergeChanged, MergePreview, Secret};
+
+#[derive(Debug, Default, Merge, MergeChanged, MergePreview, PartialEq)]
+#[merge(strategy = conflate::option::overwrite_none)]
+struct Credentials {
+    user: Option<String>,
+    password: Option<Secret<String>>,
+}
+
+impl SecretValue for Credentials {}
+
+fn credentials(user: Option<&str>, password: Option<&str>) -> Credentials {
+    Credentials {
+        user: user.map(ToOwned::to_owned),
+        password: password.map(|p| Secret::new(p.to_owned())),
+    }
+}
+
+#[test]
+fn test_redacted() {
+    let secret = Secret::new("hunter2".to_owned());
+    assert_eq!("<redacted>", format!("{:?}", secret));
+    assert_eq!("<redacted>", secret.to_string());
+    assert_eq!("hunter2", secret.expose());
+
+    let credentials = credentials(Some("ferris"), Some("hunter2"));
+    let debug = format!("{:?}", credentials);
+    assert!(!debug.contains("hunter2"));
+    assert!(debug.contains("ferris"));
+}
+
+#[test]
+fn test_merge() {
+    let mut left = credentials(Some("ferris"), None);
+    left.merge(credentials(Some("crab"), Some("hunter2")));
+    assert_eq!(credentials(Some("ferris"), Some("hunter2")), left);
+
+    let mut left = credentials(None, Some("hunter2"));
+    assert!(!left.merge_changed(credentials(None, Some("other"))));
+    assert_eq!("hunter2", left.password.unwrap().expose());
+}
+
+#[test]
+fn test_wrapped_struct() {
+    #[derive(Debug, Default, Merge, MergeChanged, MergePreview, PartialEq)]
+    struct Config {
+        user: Credentials,
+        secret: Secret<Credentials>,
+    }
+
+    let mut config = Config::default();
+    let other = Config {
+        user: credentials(Some("ferris"), None),
+        secret: Secret::new(credentials(Some("admin"), Some("hunter2"))),
+    };
+
+    let changes: Vec<_> = config
+        .merge_preview(&other)
+        .into_iter()
+        .map(|c| c.to_string())
+        .collect();
+    assert_eq!(
+        vec![
+            r#"user.user: None -> Some("ferris")"#,
+            "secret.user: <redacted> -> <redacted>",
+            "secret.password: <redacted> -> <redacted>",
+        ],
+        changes
+    );
+    assert_eq!(changes.len(), config.diff(&other).len());
+    assert!(config
+        .diff(&other)
+        .iter()
+        .all(|c| !c.new.contains("admin") && !c.new.contains("hunter2")));
+
+    config.merge(other);
+    assert_eq!(Some("admin"), config.secret.expose().user.as_deref());
+}
+
+#[test]
+fn test_preview_option() {
+    let changes = credentials(None, None).merge_preview(&credentials(None, Some("hunter2")));
+    assert_eq!(1, changes.len());
+    assert_eq!("password: None -> Some(<redacted>)", changes[0].to_string());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_deserialize() {
+    let secret: Secret<String> = serde_json::from_str(r#""hunter2""#).unwrap();
+    assert_eq!("hunter2", secret.expose());
+}
+
+#[test]
+fn test_wipe() {
+    let mut password = "hunter2".to_owned();
+    password.wipe();
+    #[cfg(feature = "zeroize")]
+    assert!(password.is_empty());
+    #[cfg(not(feature = "zeroize"))]
+    assert_eq!("hunter2", password);
+
+    let mut option = Some(42_u32);
+    option.wipe();
+    #[cfg(feature = "zeroize")]
+    assert_eq!(Some(0), option);
+}
@@ -0,0 +1,370 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+#![cfg(all(feature = "derive", feature = "serde"))]
+
+use conflate::{DeserializeMerge, Merge};
+
+fn test<T: std::fmt::Debug + DeserializeMerge + PartialEq>(expected: T, mut left: T, right: &str) {
+    let mut deserializer = serde_json::Deserializer::from_str(right);
+    left.deserialize_merge(&mut deserializer).unwrap();
+    assert_eq!(expected, left);
+}
+
+#[test]
+fn test_strategy_fields() {
+    #[derive(Debug, DeserializeMerge, Merge, PartialEq)]
+    struct S {
+        #[merge(strategy = conflate::option::overwrite_with_some)]
+        field1: Option<usize>,
+        #[merge(strategy = conflate::num::saturating_add)]
+        field2: u8,
+    }
+
+    impl S {
+        pub fn new(field1: Option<usize>, field2: u8) -> S {
+            S { field1, field2 }
+        }
+    }
+
+    test(S::new(Some(1), 1), S::new(Some(1), 1), r#"{}"#);
+    test(S::new(Some(2), 1), S::new(Some(1), 1), r#"{ "field1": 2 }"#);
+    test(
+        S::new(Some(1), 1),
+        S::new(Some(1), 1),
+        r#"{ "field1": null }"#,
+    );
+    test(S::new(Some(1), 3), S::new(Some(1), 1), r#"{ "field2": 2 }"#);
+    test(
+        S::new(Some(2), 255),
+        S::new(None, 254),
+        r#"{ "field2": 2, "field1": 2 }"#,
+    );
+}
+
+#[test]
+fn test_absent_keys_keep_defaults() {
+    #[derive(Debug, DeserializeMerge, Merge, PartialEq)]
+    struct S {
+        #[merge(strategy = overwrite)]
+        field1: usize,
+        #[merge(strategy = overwrite)]
+        field2: usize,
+    }
+
+    fn overwrite<T>(left: &mut T, right: T) {
+        *left = right;
+    }
+
+    // Deserializing `{ "field1": 0 }` as a whole struct would reset `field2` to its default value,
+    // too.
+    test(
+        S {
+            field1: 0,
+            field2: 2,
+        },
+        S {
+            field1: 1,
+            field2: 2,
+        },
+        r#"{ "field1": 0 }"#,
+    );
+}
+
+#[test]
+fn test_default_strategy() {
+    #[derive(Debug, DeserializeMerge, Merge, PartialEq)]
+    #[merge(strategy = conflate::option::overwrite_none)]
+    struct S {
+        field1: Option<usize>,
+        field2: Option<usize>,
+    }
+
+    test(
+        S {
+            field1: Some(1),
+            field2: Some(2),
+        },
+        S {
+            field1: Some(1),
+            field2: None,
+        },
+        r#"{ "field1": 3, "field2": 2 }"#,
+    );
+}
+
+#[test]
+fn test_nested() {
+    #[derive(Debug, DeserializeMerge, Merge, PartialEq)]
+    struct N {
+        #[merge(strategy = conflate::option::overwrite_with_some)]
+        field1: Option<usize>,
+        #[merge(strategy = conflate::vec::append)]
+        field2: Vec<usize>,
+    }
+
+    #[derive(Debug, DeserializeMerge, Merge, PartialEq)]
+    struct S {
+        #[merge(strategy = conflate::option::overwrite_with_some)]
+        field1: Option<usize>,
+        nested: N,
+    }
+
+    let left = || S {
+        field1: Some(1),
+        nested: N {
+            field1: Some(1),
+            field2: vec![1],
+        },
+    };
+
+    test(left(), left(), r#"{ "nested": {} }"#);
+    test(
+        S {
+            field1: Some(1),
+            nested: N {
+                field1: Some(1),
+                field2: vec![1, 2],
+            },
+        },
+        left(),
+        r#"{ "nested": { "field2": [2] } }"#,
+    );
+    test(
+        S {
+            field1: Some(2),
+            nested: N {
+                field1: Some(2),
+                field2: vec![1],
+            },
+        },
+        left(),
+        r#"{ "nested": { "field1": 2 }, "field1": 2 }"#,
+    );
+}
+
+#[test]
+fn test_skip_and_unknown_keys() {
+    #[derive(Debug, DeserializeMerge, Merge, PartialEq)]
+    struct S {
+        #[merge(strategy = conflate::option::overwrite_with_some)]
+        field1: Option<usize>,
+        #[merge(skip)]
+        field2: Option<usize>,
+    }
+
+    test(
+        S {
+            field1: Some(2),
+            field2: Some(1),
+        },
+        S {
+            field1: Some(1),
+            field2: Some(1),
+        },
+        r#"{ "field1": 2, "field2": 2, "field3": { "a": [1, 2] } }"#,
+    );
+}
+
+#[test]
+fn test_invalid_input() {
+    #[derive(Debug, DeserializeMerge, Merge, PartialEq)]
+    struct S {
+        #[merge(strategy = conflate::option::overwrite_with_some)]
+        field1: Option<usize>,
+    }
+
+    let mut value = S { field1: None };
+    let mut deserializer = serde_json::Deserializer::from_str(r#"{ "field1": "one" }"#);
+    assert!(value.deserialize_merge(&mut deserializer).is_err());
+
+    let mut deserializer = serde_json::Deserializer::from_str(r#"[1]"#);
+    assert!(value.deserialize_merge(&mut deserializer).is_err());
+}
+
+#[test]
+fn test_rename() {
+    #[derive(Debug, serde::Deserialize, DeserializeMerge, Merge, PartialEq)]
+    #[serde(rename_all = "kebab-case")]
+    struct S {
+        #[merge(strategy = conflate::option::overwrite_with_some)]
+        field_one: Option<usize>,
+        #[merge(strategy = conflate::option::overwrite_with_some)]
+        #[serde(rename = "second", alias = "zwei")]
+        field_two: Option<usize>,
+        #[merge(strategy = conflate::option::overwrite_with_some)]
+        #[serde(skip)]
+        field_three: Option<usize>,
+    }
+
+    impl S {
+        pub fn new(field_one: usize, field_two: usize, field_three: usize) -> S {
+            S {
+                field_one: Some(field_one),
+                field_two: Some(field_two),
+                field_three: Some(field_three),
+            }
+        }
+    }
+
+    test(S::new(2, 1, 1), S::new(1, 1, 1), r#"{ "field-one": 2 }"#);
+    test(S::new(1, 1, 1), S::new(1, 1, 1), r#"{ "field_one": 2 }"#);
+    test(S::new(1, 2, 1), S::new(1, 1, 1), r#"{ "second": 2 }"#);
+    test(S::new(1, 2, 1), S::new(1, 1, 1), r#"{ "zwei": 2 }"#);
+    test(S::new(1, 1, 1), S::new(1, 1, 1), r#"{ "field-three": 2 }"#);
+}
+
+#[test]
+fn test_rename_rules() {
+    #[derive(Debug, serde::Deserialize, DeserializeMerge, Merge, PartialEq)]
+    #[serde(rename_all = "camelCase")]
+    struct Camel {
+        #[merge(strategy = conflate::option::overwrite_with_some)]
+        some_field: Option<usize>,
+    }
+
+    #[derive(Debug, serde::Deserialize, DeserializeMerge, Merge, PartialEq)]
+    #[serde(rename_all = "SCREAMING-KEBAB-CASE")]
+    struct ScreamingKebab {
+        #[merge(strategy = conflate::option::overwrite_with_some)]
+        some_field: Option<usize>,
+    }
+
+    #[derive(Debug, serde::Deserialize, DeserializeMerge, Merge, PartialEq)]
+    #[serde(rename_all(serialize = "UPPERCASE", deserialize = "PascalCase"))]
+    struct Pascal {
+        #[merge(strategy = conflate::option::overwrite_with_some)]
+        some_field: Option<usize>,
+    }
+
+    test(
+        Camel {
+            some_field: Some(1),
+        },
+        Camel { some_field: None },
+        r#"{ "someField": 1 }"#,
+    );
+    test(
+        ScreamingKebab {
+            some_field: Some(1),
+        },
+        ScreamingKebab { some_field: None },
+        r#"{ "SOME-FIELD": 1 }"#,
+    );
+    test(
+        Pascal {
+            some_field: Some(1),
+        },
+        Pascal { some_field: None },
+        r#"{ "SomeField": 1 }"#,
+    );
+}
+
+#[test]
+fn test_flatten() {
+    #[derive(Debug, serde::Deserialize, DeserializeMerge, Merge, PartialEq)]
+    struct N {
+        #[merge(strategy = conflate::option::overwrite_with_some)]
+        inner: Option<usize>,
+    }
+
+    #[derive(Debug, serde::Deserialize, DeserializeMerge, Merge, PartialEq)]
+    struct S {
+        #[merge(strategy = conflate::option::overwrite_with_some)]
+        outer: Option<usize>,
+        #[serde(flatten)]
+        nested: N,
+    }
+
+    test(
+        S {
+            outer: Some(2),
+            nested: N { inner: Some(2) },
+        },
+        S {
+            outer: Some(1),
+            nested: N { inner: Some(1) },
+        },
+        r#"{ "outer": 2, "inner": 2, "nested": { "inner": 3 } }"#,
+    );
+}
+
+#[test]
+fn test_deny_unknown_fields() {
+    #[derive(Debug, serde::Deserialize, DeserializeMerge, Merge, PartialEq)]
+    #[serde(deny_unknown_fields)]
+    struct S {
+        #[merge(strategy = conflate::option::overwrite_with_some)]
+        field1: Option<usize>,
+        #[merge(skip)]
+        field2: Option<usize>,
+    }
+
+    test(
+        S {
+            field1: Some(2),
+            field2: None,
+        },
+        S {
+            field1: None,
+            field2: None,
+        },
+        r#"{ "field1": 2, "field2": 2 }"#,
+    );
+
+    let mut value = S {
+        field1: None,
+        field2: None,
+    };
+    let mut deserializer = serde_json::Deserializer::from_str(r#"{ "field3": 2 }"#);
+    let err = value.deserialize_merge(&mut deserializer).unwrap_err();
+    assert!(err.to_string().contains("unknown field `field3`"));
+}
+
+#[test]
+fn test_field_path() {
+    #[derive(serde::Deserialize, DeserializeMerge, Merge)]
+    #[serde(rename_all = "kebab-case")]
+    struct Hooks {
+        #[merge(strategy = conflate::vec::append)]
+        run_before: Vec<String>,
+    }
+
+    #[derive(serde::Deserialize, DeserializeMerge, Merge)]
+    struct Options {
+        #[merge(strategy = conflate::bool::overwrite_false)]
+        #[serde(rename = "dry-run")]
+        dry_run: bool,
+    }
+
+    #[derive(serde::Deserialize, DeserializeMerge, Merge)]
+    struct Config {
+        #[serde(rename = "global-hooks")]
+        hooks: Hooks,
+        #[serde(flatten)]
+        options: Options,
+        #[merge(strategy = conflate::option::overwrite_none)]
+        #[serde(skip)]
+        internal: Option<usize>,
+    }
+
+    assert_eq!(
+        Some("global-hooks.run-before".to_owned()),
+        Config::field_path(&["hooks", "run_before"])
+    );
+    assert_eq!(
+        Some("global-hooks".to_owned()),
+        Config::field_path(&["hooks"])
+    );
+    assert_eq!(
+        Some("dry-run".to_owned()),
+        Config::field_path(&["options", "dry_run"])
+    );
+    assert_eq!(None, Config::field_path(&["internal"]));
+    assert_eq!(None, Config::field_path(&["hooks", "missing"]));
+    assert_eq!(None, Config::field_path(&["options", "dry_run", "nested"]));
+
+    let names: Vec<_> = Config::FIELD_NAMES.iter().map(|f| f.ident).collect();
+    assert_eq!(vec!["hooks", "options", "internal"], names);
+    assert!(Config::FIELD_NAMES[1].flatten);
+}
@@ -88,6 +88,160 @@ fn test_num_overwrite_zero() {
     test(S(255), S(255), S(10));
 }
 
+#[cfg(feature = "num")]
+#[test]
+fn test_num_saturating_sub() {
+    #[derive(Debug, Merge, PartialEq)]
+    struct S(#[merge(strategy = conflate::num::saturating_sub)] u8);
+
+    test(S(0), S(0), S(0));
+    test(S(0), S(0), S(1));
+    test(S(20), S(30), S(10));
+}
+
+#[cfg(feature = "num")]
+#[test]
+fn test_num_saturating_mul() {
+    #[derive(Debug, Merge, PartialEq)]
+    struct S(#[merge(strategy = conflate::num::saturating_mul)] u8);
+
+    test(S(0), S(0), S(2));
+    test(S(6), S(2), S(3));
+    test(S(255), S(128), S(2));
+}
+
+#[cfg(feature = "num")]
+#[test]
+fn test_num_checked_add() {
+    use conflate::num::{checked_add, Overflow};
+
+    let mut value = 250u8;
+    assert_eq!(Ok(()), checked_add(&mut value, 5));
+    assert_eq!(255, value);
+    assert_eq!(Err(Overflow), checked_add(&mut value, 1));
+    assert_eq!(255, value);
+}
+
+#[cfg(all(feature = "num", feature = "std"))]
+#[test]
+fn test_num_checked_add_try_merge() {
+    use conflate::TryMerge;
+
+    #[derive(Debug, Merge, PartialEq, TryMerge)]
+    struct S {
+        #[merge(
+            strategy = conflate::num::saturating_add,
+            strategy_try = conflate::num::checked_add
+        )]
+        count: u8,
+        #[merge(strategy = conflate::ord::max)]
+        level: u8,
+    }
+
+    let mut s = S {
+        count: 250,
+        level: 1,
+    };
+    s.try_merge(S { count: 5, level: 0 }).unwrap();
+    assert_eq!(
+        S {
+            count: 255,
+            level: 1
+        },
+        s
+    );
+
+    let err = s.try_merge(S { count: 1, level: 2 }).unwrap_err();
+    assert_eq!(["count"], err.paths());
+    assert_eq!(
+        S {
+            count: 255,
+            level: 2
+        },
+        s
+    );
+}
+
+#[cfg(feature = "num")]
+#[test]
+fn test_num_overwrite_default() {
+    #[derive(Debug, Merge, PartialEq)]
+    struct S(#[merge(strategy = conflate::num::overwrite_default)] &'static str);
+
+    test(S(""), S(""), S(""));
+    test(S("a"), S(""), S("a"));
+    test(S("a"), S("a"), S("b"));
+}
+
+#[cfg(feature = "num")]
+#[test]
+fn test_num_clamp() {
+    #[derive(Debug, Merge, PartialEq)]
+    struct S(#[merge(strategy = conflate::num::clamp(1, 10))] u8);
+
+    test(S(5), S(1), S(5));
+    test(S(1), S(5), S(0));
+    test(S(10), S(5), S(64));
+
+    #[derive(Debug, Merge, PartialEq)]
+    struct T(
+        #[merge(strategy = conflate::num::clamp_with(0, 100, conflate::num::saturating_add))] u8,
+    );
+
+    test(T(30), T(10), T(20));
+    test(T(100), T(90), T(20));
+}
+
+#[cfg(feature = "num")]
+#[test]
+fn test_num_max_f() {
+    use conflate::num::NanPolicy;
+
+    #[derive(Debug, Merge, PartialEq)]
+    struct S(#[merge(strategy = conflate::num::max_f)] f64);
+
+    test(S(2.0), S(1.0), S(2.0));
+    test(S(2.0), S(2.0), S(-1.0));
+    test(S(2.0), S(f64::NAN), S(2.0));
+    test(S(2.0), S(2.0), S(f64::NAN));
+
+    #[derive(Debug, Merge)]
+    struct T(#[merge(strategy = conflate::num::max_f_with(NanPolicy::Propagate))] f32);
+
+    let mut value = T(1.0);
+    value.merge(T(2.0));
+    assert_eq!(2.0, value.0);
+    value.merge(T(f32::NAN));
+    assert!(value.0.is_nan());
+    value.merge(T(3.0));
+    assert!(value.0.is_nan());
+}
+
+#[cfg(feature = "num")]
+#[test]
+fn test_num_min_f() {
+    use conflate::num::NanPolicy;
+
+    #[derive(Debug, Merge, PartialEq)]
+    struct S(#[merge(strategy = conflate::num::min_f)] f32);
+
+    test(S(1.0), S(1.0), S(2.0));
+    test(S(-1.0), S(2.0), S(-1.0));
+    test(S(2.0), S(f32::NAN), S(2.0));
+
+    #[derive(Debug, Merge, PartialEq)]
+    struct T(#[merge(strategy = conflate::num::min_f_with(NanPolicy::Ignore))] f64);
+
+    test(T(1.0), T(1.0), T(f64::NAN));
+
+    #[derive(Debug, Merge)]
+    struct U(#[merge(strategy = conflate::num::min_f_with(NanPolicy::Propagate))] f64);
+
+    let mut value = U(f64::NAN);
+    value.merge(U(1.0));
+    assert!(value.0.is_nan());
+}
+
 #[test]
 fn test_ord_max() {
     #[derive(Debug, Merge, PartialEq)]
@@ -114,6 +268,69 @@ fn test_ord_min() {
     test(S(11), S(33), S(11));
 }
 
+#[derive(Debug, PartialEq)]
+struct Retention(u32, &'static str);
+
+fn retention_days(retention: &Retention) -> u32 {
+    retention.0
+}
+
+#[test]
+fn test_ord_by_key() {
+    #[derive(Debug, Merge, PartialEq)]
+    struct S(
+        #[merge(strategy = conflate::ord::max_by_key(retention_days))] Retention,
+        #[merge(strategy = conflate::ord::min_by_key(|r: &Retention| r.0))] Retention,
+    );
+
+    test(
+        S(Retention(30, "b"), Retention(7, "a")),
+        S(Retention(7, "a"), Retention(7, "a")),
+        S(Retention(30, "b"), Retention(30, "b")),
+    );
+    test(
+        S(Retention(7, "a"), Retention(7, "a")),
+        S(Retention(7, "a"), Retention(7, "a")),
+        S(Retention(7, "b"), Retention(7, "b")),
+    );
+}
+
+#[test]
+fn test_ord_by() {
+    #[derive(Debug, Merge, PartialEq)]
+    struct S(
+        #[merge(strategy = conflate::ord::max_by(Ord::cmp))] &'static str,
+        #[merge(strategy = conflate::ord::min_by(|a: &&str, b: &&str| a.len().cmp(&b.len())))]
+        &'static str,
+    );
+
+    test(S("b", "a"), S("a", "a"), S("b", "bb"));
+    test(S("b", "b"), S("b", "bb"), S("a", "b"));
+}
+
+#[test]
+fn test_ord_partial() {
+    use conflate::ord::Incomparable;
+
+    #[derive(Debug, Merge)]
+    struct S(
+        #[merge(strategy = conflate::ord::partial_max(Incomparable::Keep))] f64,
+        #[merge(strategy = conflate::ord::partial_min(Incomparable::Overwrite))] f64,
+    );
+
+    let mut value = S(1.0, 1.0);
+    value.merge(S(2.0, 2.0));
+    assert_eq!((2.0, 1.0), (value.0, value.1));
+
+    value.merge(S(f64::NAN, f64::NAN));
+    assert_eq!(2.0, value.0);
+    assert!(value.1.is_nan());
+
+    value.merge(S(3.0, 3.0));
+    assert_eq!(3.0, value.0);
+    assert_eq!(3.0, value.1);
+}
+
 #[cfg(feature = "std")]
 #[test]
 fn test_vec_overwrite_empty() {
@@ -222,6 +439,79 @@ mod hashmap {
             S(map! {1 => N(2)}),
         );
     }
+
+    #[test]
+    fn test_retain_keys() {
+        #[derive(Debug, Merge, PartialEq)]
+        struct S(
+            #[merge(strategy = conflate::hashmap::retain_keys(|k: &u8| *k < 10))] HashMap<u8, u8>,
+        );
+
+        test(
+            S(map! {1 => 2}),
+            S(map! {1 => 1}),
+            S(map! {1 => 2, 10 => 2}),
+        );
+        test(
+            S(map! {1 => 1}),
+            S(map! {1 => 1, 10 => 1}),
+            S(map! {10 => 2}),
+        );
+    }
+
+    #[test]
+    fn test_remove_keys_marked() {
+        #[derive(Debug, Merge, PartialEq)]
+        struct S(
+            #[merge(strategy = conflate::hashmap::remove_keys_marked)] HashMap<u8, Option<u8>>,
+        );
+
+        test(
+            S(map! {1 => Some(2), 3 => Some(3)}),
+            S(map! {1 => Some(1), 2 => Some(2)}),
+            S(map! {1 => Some(2), 2 => None, 3 => Some(3), 4 => None}),
+        );
+
+        let mut left = map! {1 => Some(1)};
+        assert!(!conflate::hashmap::remove_keys_marked_changed(
+            &mut left,
+            map! {1 => Some(1), 2 => None}
+        ));
+        assert!(conflate::hashmap::remove_keys_marked_changed(
+            &mut left,
+            map! {1 => None}
+        ));
+        assert!(left.is_empty());
+    }
+
+    #[test]
+    fn test_values_with() {
+        #[derive(Debug, Merge, PartialEq)]
+        struct S(
+            #[merge(strategy = conflate::hashmap::values_with(conflate::ord::max))] HashMap<u8, u8>,
+        );
+
+        test(S(map! {1 => 2}), S(map! {1 => 1}), S(map! {1 => 2, 2 => 2}));
+        test(S(map! {1 => 2}), S(map! {1 => 2}), S(map! {1 => 1}));
+        // keys that only exist in right are not inserted
+        test(S(map! {2 => 2}), S(map! {2 => 2}), S(map! {1 => 1}));
+    }
+
+    #[test]
+    fn test_append_or_recurse_with() {
+        #[derive(Debug, Merge, PartialEq)]
+        struct S(
+            #[merge(strategy = conflate::hashmap::append_or_recurse_with(conflate::ord::min))]
+            HashMap<u8, u8>,
+        );
+
+        test(
+            S(map! {1 => 1, 2 => 2}),
+            S(map! {1 => 1}),
+            S(map! {1 => 2, 2 => 2}),
+        );
+        test(S(map! {1 => 1}), S(map! {1 => 2}), S(map! {1 => 1}));
+    }
 }
 
 #[cfg(feature = "std")]
@@ -316,4 +606,144 @@ mod btreemap {
             S(btreemap! {1 => N(2)}),
         );
     }
+
+    #[test]
+    fn test_map_strategies() {
+        #[derive(Debug, Merge, PartialEq)]
+        struct S {
+            #[merge(strategy = conflate::btreemap::retain_keys(|k: &&str| !k.starts_with('_')))]
+            retain: BTreeMap<&'static str, u8>,
+            #[merge(strategy = conflate::btreemap::remove_keys_marked)]
+            remove: BTreeMap<&'static str, Option<u8>>,
+            #[merge(strategy = conflate::btreemap::values_with(conflate::ord::max))]
+            values: BTreeMap<&'static str, u8>,
+            #[merge(strategy = conflate::btreemap::append_or_recurse_with(conflate::ord::max))]
+            recurse: BTreeMap<&'static str, u8>,
+        }
+
+        test(
+            S {
+                retain: btreemap! {"a" => 2, "b" => 2},
+                remove: btreemap! {"b" => Some(2)},
+                values: btreemap! {"a" => 2},
+                recurse: btreemap! {"a" => 2, "b" => 2},
+            },
+            S {
+                retain: btreemap! {"a" => 1, "_d" => 1},
+                remove: btreemap! {"a" => Some(1)},
+                values: btreemap! {"a" => 1},
+                recurse: btreemap! {"a" => 1},
+            },
+            S {
+                retain: btreemap! {"a" => 2, "b" => 2, "_c" => 2},
+                remove: btreemap! {"a" => None, "b" => Some(2)},
+                values: btreemap! {"a" => 2, "b" => 2},
+                recurse: btreemap! {"a" => 2, "b" => 2},
+            },
+        );
+    }
+}
+
+#[cfg(feature = "indexmap")]
+mod indexmap {
+    use super::test;
+    use crate::Merge;
+    use indexmap::IndexMap;
+
+    fn map<V: Clone>(entries: &[(u8, V)]) -> IndexMap<u8, V> {
+        entries.iter().cloned().collect()
+    }
+
+    fn keys<V>(map: &IndexMap<u8, V>) -> Vec<u8> {
+        map.keys().copied().collect()
+    }
+
+    #[test]
+    fn test_append_or_overwrite() {
+        let mut left = map(&[(3, 1), (1, 1)]);
+        conflate::indexmap::append_or_overwrite(&mut left, map(&[(2, 2), (1, 2)]));
+        assert_eq!(map(&[(3, 1), (1, 2), (2, 2)]), left);
+        assert_eq!(vec![3, 1, 2], keys(&left));
+    }
+
+    #[test]
+    fn test_append_or_ignore() {
+        #[derive(Debug, Merge, PartialEq)]
+        struct S(#[merge(strategy = conflate::indexmap::append_or_ignore)] IndexMap<u8, u8>);
+
+        test(
+            S(map(&[(3, 1), (1, 1), (2, 2)])),
+            S(map(&[(3, 1), (1, 1)])),
+            S(map(&[(2, 2), (1, 2)])),
+        );
+    }
+
+    #[test]
+    fn test_remove_keys_marked() {
+        let mut left = map(&[(3, Some(3)), (1, Some(1)), (2, Some(2))]);
+        conflate::indexmap::remove_keys_marked(&mut left, map(&[(1, None), (4, Some(4))]));
+        assert_eq!(vec![3, 2, 4], keys(&left));
+    }
+
+    #[test]
+    fn test_retain_keys() {
+        let strategy = conflate::indexmap::retain_keys(|k: &u8| *k < 10);
+        let mut left = map(&[(3, 1), (10, 1), (1, 1)]);
+        strategy(&mut left, map(&[(11, 2), (2, 2), (1, 2)]));
+        assert_eq!(vec![3, 1, 2], keys(&left));
+        assert_eq!(map(&[(3, 1), (1, 2), (2, 2)]), left);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize_merge() {
+        use conflate::DeserializeMerge;
+
+        #[derive(Debug, Default, serde::Deserialize, DeserializeMerge, Merge, PartialEq)]
+        struct S {
+            #[merge(strategy = conflate::indexmap::append_or_overwrite)]
+            hooks: IndexMap<String, u8>,
+        }
+
+        let mut s: S = serde_json::from_str(r#"{"hooks": {"b": 1, "a": 1}}"#).unwrap();
+        s.deserialize_merge(&mut serde_json::Deserializer::from_str(
+            r#"{"hooks": {"c": 2, "a": 2}}"#,
+        ))
+        .unwrap();
+        let keys: Vec<_> = s.hooks.keys().map(String::as_str).collect();
+        assert_eq!(vec!["b", "a", "c"], keys);
+        assert_eq!(Some(&2), s.hooks.get("a"));
+    }
+
+    #[test]
+    fn test_values_with() {
+        let strategy = conflate::indexmap::values_with(conflate::ord::max);
+        let mut left = map(&[(3, 1), (1, 1)]);
+        strategy(&mut left, map(&[(4, 4), (1, 2), (3, 0)]));
+        // the key 4 only exists in right and is not inserted
+        assert_eq!(map(&[(3, 1), (1, 2)]), left);
+        assert_eq!(vec![3, 1], keys(&left));
+    }
+
+    #[test]
+    fn test_append_or_recurse_with() {
+        let strategy = conflate::indexmap::append_or_recurse_with(conflate::ord::max);
+        let mut left = map(&[(3, 1), (1, 1)]);
+        strategy(&mut left, map(&[(4, 4), (1, 2), (3, 0)]));
+        assert_eq!(map(&[(3, 1), (1, 2), (4, 4)]), left);
+        assert_eq!(vec![3, 1, 4], keys(&left));
+    }
+
+    #[test]
+    fn test_precedence() {
+        #[derive(Debug, Merge, PartialEq)]
+        #[merge(precedence = "right")]
+        struct S(IndexMap<u8, u8>);
+
+        test(
+            S(map(&[(1, 2), (2, 2)])),
+            S(map(&[(1, 1)])),
+            S(map(&[(2, 2), (1, 2)])),
+        );
+    }
 }
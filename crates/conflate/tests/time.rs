@@ -0,0 +1,113 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+#![cfg(feature = "derive")]
+
+use std::time::Duration;
+
+use conflate::{Merge, MergeChanged};
+
+fn test<T: std::fmt::Debug + Merge + PartialEq>(expected: T, mut left: T, right: T) {
+    left.merge(right);
+    assert_eq!(expected, left);
+}
+
+const fn secs(secs: u64) -> Duration {
+    Duration::from_secs(secs)
+}
+
+#[derive(Debug, PartialEq, Merge)]
+struct S(
+    #[merge(strategy = conflate::time::max)] Duration,
+    #[merge(strategy = conflate::time::min)] Duration,
+    #[merge(strategy = conflate::time::saturating_add)] Duration,
+    #[merge(strategy = conflate::time::overwrite_zero)] Duration,
+);
+
+#[test]
+fn test_duration() {
+    test(
+        S(secs(2), secs(1), secs(3), secs(2)),
+        S(secs(1), secs(1), secs(1), secs(0)),
+        S(secs(2), secs(2), secs(2), secs(2)),
+    );
+    test(
+        S(secs(2), secs(1), Duration::MAX, secs(1)),
+        S(secs(2), secs(1), Duration::MAX, secs(1)),
+        S(secs(1), secs(2), secs(1), secs(2)),
+    );
+}
+
+#[test]
+fn test_changed() {
+    #[derive(Debug, PartialEq, MergeChanged)]
+    struct S(
+        #[merge(strategy_changed = conflate::time::max_changed)] Duration,
+        #[merge(strategy_changed = conflate::time::min_changed)] Duration,
+        #[merge(strategy_changed = conflate::time::saturating_add_changed)] Duration,
+        #[merge(strategy_changed = conflate::time::overwrite_zero_changed)] Duration,
+    );
+
+    let mut value = S(secs(1), secs(1), secs(1), secs(0));
+    assert!(value.merge_changed(S(secs(2), secs(2), secs(0), secs(0))));
+    assert_eq!(S(secs(2), secs(1), secs(1), secs(0)), value);
+
+    let mut value = S(secs(2), secs(1), Duration::MAX, secs(1));
+    assert!(!value.merge_changed(S(secs(1), secs(2), secs(1), secs(2))));
+}
+
+#[cfg(feature = "humantime")]
+#[test]
+fn test_humantime() {
+    #[derive(Debug, PartialEq, Merge)]
+    struct S(
+        #[merge(strategy = conflate::time::max)] humantime::Duration,
+        #[merge(strategy = conflate::time::overwrite_zero)] humantime::Duration,
+    );
+
+    let parse = |s: &str| s.parse::<humantime::Duration>().unwrap();
+    test(
+        S(parse("1h 30m"), parse("10s")),
+        S(parse("1h 30m"), parse("0s")),
+        S(parse("45m"), parse("10s")),
+    );
+}
+
+#[cfg(feature = "bytesize")]
+#[test]
+fn test_bytesize() {
+    use bytesize::ByteSize;
+
+    #[derive(Debug, PartialEq, Merge)]
+    struct S(
+        #[merge(strategy = conflate::time::min)] ByteSize,
+        #[merge(strategy = conflate::time::saturating_add)] ByteSize,
+    );
+
+    test(
+        S(ByteSize::mib(256), ByteSize::mib(768)),
+        S(ByteSize::mib(512), ByteSize::mib(512)),
+        S(ByteSize::mib(256), ByteSize::mib(256)),
+    );
+    test(
+        S(ByteSize::b(0), ByteSize::b(u64::MAX)),
+        S(ByteSize::b(0), ByteSize::b(u64::MAX)),
+        S(ByteSize::b(1), ByteSize::b(1)),
+    );
+}
+
+#[cfg(all(feature = "bytesize", feature = "serde"))]
+#[test]
+fn test_bytesize_deserialize() {
+    use bytesize::ByteSize;
+
+    #[derive(Debug, PartialEq, Merge, serde::Deserialize)]
+    struct Config {
+        #[merge(strategy = conflate::time::max)]
+        cache: ByteSize,
+    }
+
+    let mut config: Config = serde_json::from_str(r#"{"cache": "512 MiB"}"#).unwrap();
+    config.merge(serde_json::from_str(r#"{"cache": "1 GiB"}"#).unwrap());
+    assert_eq!(ByteSize::gib(1), config.cache);
+}
@@ -0,0 +1,157 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+#![cfg(all(feature = "derive", feature = "std"))]
+
+use conflate::trust::Trust;
+use conflate::{Locked, Merge, MergeTrusted};
+
+#[derive(Clone, Debug, Default, PartialEq, Merge, MergeTrusted)]
+#[merge(strategy = conflate::option::overwrite_with_some)]
+struct Hooks {
+    #[merge(min_trust = "system")]
+    run_before: Option<String>,
+    #[merge(min_trust = "user")]
+    run_after: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Merge, MergeTrusted)]
+#[merge(precedence = "right")]
+struct Config {
+    repository: Option<String>,
+    hooks: Hooks,
+    #[merge(lockable, min_trust = "user")]
+    password: Locked<Option<String>>,
+}
+
+fn config(repository: &str, run_before: &str, run_after: &str) -> Config {
+    Config {
+        repository: Some(repository.to_owned()),
+        hooks: Hooks {
+            run_before: Some(run_before.to_owned()),
+            run_after: Some(run_after.to_owned()),
+        },
+        password: Locked::default(),
+    }
+}
+
+#[test]
+fn test_merge_trusted() {
+    let mut left = Config::default();
+    left.merge_trusted(config("/srv", "mount", "umount"), Trust::System)
+        .unwrap();
+    assert_eq!(config("/srv", "mount", "umount"), left);
+
+    left.merge_trusted(config("/home", "mount", "sync"), Trust::User)
+        .unwrap();
+    assert_eq!(config("/home", "mount", "sync"), left);
+
+    let err = left
+        .merge_trusted(config("/tmp", "rm", "rm"), Trust::Local)
+        .unwrap_err();
+    assert_eq!(["hooks.run_before", "hooks.run_after"], err.paths());
+    assert_eq!(Trust::Local, err.trust());
+    assert_eq!(
+        "fields cannot be set with trust level local: hooks.run_before, hooks.run_after",
+        err.to_string()
+    );
+    assert_eq!(config("/tmp", "mount", "sync"), left);
+
+    // untrusted values that do not change the field are not reported
+    left.merge_trusted(
+        Config {
+            repository: None,
+            hooks: Hooks::default(),
+            password: Locked::default(),
+        },
+        Trust::Local,
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_lockable() {
+    let mut left = Config::default();
+    let mut right = Config {
+        password: Locked::new(Some("secret".to_owned())),
+        ..Config::default()
+    };
+    let err = left.merge_trusted(right.clone(), Trust::Local).unwrap_err();
+    assert_eq!(["password"], err.paths());
+    assert_eq!(None, *left.password);
+
+    right.password.lock();
+    left.merge_trusted(right, Trust::User).unwrap();
+    assert_eq!(Some("secret"), left.password.as_deref());
+    assert!(left.password.is_locked());
+}
+
+#[test]
+fn test_merge() {
+    let mut left = Config::default();
+    left.merge(config("/tmp", "rm", "rm"));
+    assert_eq!(config("/tmp", "rm", "rm"), left);
+}
+
+#[test]
+fn test_trust() {
+    assert!(Trust::Local < Trust::User);
+    assert!(Trust::User < Trust::System);
+    for trust in [Trust::Local, Trust::User, Trust::System] {
+        assert_eq!(Ok(trust), trust.to_string().parse());
+    }
+    assert!("root".parse::<Trust>().is_err());
+}
+
+#[test]
+fn test_layers() {
+    use conflate::layers::{Layers, ValueSource};
+
+    let layers = Layers::new()
+        .with_source(
+            ValueSource::new("system", config("/srv", "mount", "umount")).with_trust(Trust::System),
+        )
+        .with_source(ValueSource::new(
+            "repository",
+            config("/tmp", "rm", "umount"),
+        ));
+
+    let (value, rejected) = layers.load_trusted().unwrap();
+    assert_eq!(config("/tmp", "mount", "umount"), value);
+    assert_eq!(1, rejected.len());
+    assert_eq!("repository", rejected[0].0);
+    assert_eq!(["hooks.run_before"], rejected[0].1.paths());
+
+    assert_eq!(config("/tmp", "rm", "umount"), layers.load().unwrap());
+}
+
+#[test]
+fn test_layers_default_trust() {
+    use conflate::layers::{Layers, Source, ValueSource};
+
+    struct Untrusted;
+
+    impl Source<Config> for Untrusted {
+        fn name(&self) -> String {
+            "untrusted".to_owned()
+        }
+
+        fn load(&self) -> Result<Option<Config>, conflate::layers::Error> {
+            Ok(Some(config("/tmp", "rm", "rm")))
+        }
+    }
+
+    // sources without a configured trust level cannot set protected fields
+    let layers = Layers::new()
+        .with_source(ValueSource::new("value", config("/srv", "mount", "umount")))
+        .with_source(Untrusted);
+    assert_eq!(Trust::Local, Untrusted.trust());
+    let (value, rejected) = layers.load_trusted().unwrap();
+    assert_eq!(Some("/tmp"), value.repository.as_deref());
+    assert_eq!(Hooks::default(), value.hooks);
+    assert_eq!(2, rejected.len());
+    assert_eq!(
+        ["hooks.run_before", "hooks.run_after"],
+        rejected[1].1.paths()
+    );
+}
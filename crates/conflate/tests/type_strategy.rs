@@ -0,0 +1,106 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+#![cfg(all(feature = "derive", feature = "std"))]
+
+use conflate::{option, vec, Merge, MergeChanged, MergeMeta, MergeRef};
+
+fn test<T: std::fmt::Debug + Merge + PartialEq>(expected: T, mut left: T, right: T) {
+    left.merge(right);
+    assert_eq!(expected, left);
+}
+
+#[derive(Clone, Debug, PartialEq, Merge, MergeRef, MergeChanged, MergeMeta)]
+#[merge(strategy(Option = option::overwrite_none, Vec = vec::append, u8 = conflate::ord::max))]
+struct S {
+    option: Option<String>,
+    list: Vec<u8>,
+    number: u8,
+    #[merge(strategy = option::overwrite_with_some)]
+    latest: std::option::Option<u8>,
+}
+
+#[test]
+fn test_type_strategies() {
+    test(
+        S {
+            option: Some("1".to_owned()),
+            list: vec![1, 2],
+            number: 2,
+            latest: Some(2),
+        },
+        S {
+            option: Some("1".to_owned()),
+            list: vec![1],
+            number: 1,
+            latest: Some(1),
+        },
+        S {
+            option: Some("2".to_owned()),
+            list: vec![2],
+            number: 2,
+            latest: Some(2),
+        },
+    );
+}
+
+#[test]
+fn test_other_derives() {
+    let left = S {
+        option: None,
+        list: vec![1],
+        number: 1,
+        latest: None,
+    };
+    let right = S {
+        option: Some("2".to_owned()),
+        list: vec![],
+        number: 2,
+        latest: None,
+    };
+    let expected = S {
+        option: Some("2".to_owned()),
+        list: vec![1],
+        number: 2,
+        latest: None,
+    };
+
+    let mut merged = left.clone();
+    merged.merge_ref(&right);
+    assert_eq!(expected, merged);
+
+    let mut merged = left;
+    assert_eq!(vec!["option", "number"], merged.merge_changed_paths(right));
+    assert_eq!(expected, merged);
+
+    assert_eq!(Some("vec::append"), S::FIELDS[1].strategy);
+}
+
+#[test]
+fn test_fallbacks() {
+    #[derive(Debug, PartialEq, Merge)]
+    #[merge(auto, strategy(Vec = vec::overwrite_empty), strategy = conflate::ord::min)]
+    struct S {
+        option: Option<u8>,
+        list: Vec<u8>,
+        number: u8,
+    }
+
+    test(
+        S {
+            option: Some(1),
+            list: vec![1],
+            number: 1,
+        },
+        S {
+            option: Some(1),
+            list: vec![1],
+            number: 2,
+        },
+        S {
+            option: Some(2),
+            list: vec![2],
+            number: 1,
+        },
+    );
+}
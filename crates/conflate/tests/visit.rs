@@ -0,0 +1,193 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+#![cfg(all(feature = "derive", feature = "std"))]
+
+use std::any::Any;
+use std::collections::HashMap;
+
+use conflate::{FieldPath, Merge, MergeVisit, Secret};
+
+fn describe(value: &dyn Any) -> String {
+    if let Some(s) = value.downcast_ref::<String>() {
+        format!("{s:?}")
+    } else if let Some(n) = value.downcast_ref::<u8>() {
+        n.to_string()
+    } else if let Some(b) = value.downcast_ref::<bool>() {
+        b.to_string()
+    } else if value.is::<Secret<String>>() {
+        "secret".to_owned()
+    } else {
+        "?".to_owned()
+    }
+}
+
+fn test<T: MergeVisit>(expected: &[(&str, &str)], value: &T) {
+    let mut leaves = Vec::new();
+    value.visit(&mut |path: &FieldPath<'_>, value: &dyn Any| {
+        leaves.push((path.to_string(), describe(value)));
+    });
+    let expected: Vec<_> = expected
+        .iter()
+        .map(|(p, v)| ((*p).to_owned(), (*v).to_owned()))
+        .collect();
+    assert_eq!(expected, leaves);
+}
+
+#[derive(Merge)]
+#[merge(visit)]
+struct Repository {
+    #[merge(strategy = conflate::option::overwrite_none)]
+    path: Option<String>,
+    #[merge(strategy = conflate::ord::max)]
+    retries: u8,
+}
+
+#[test]
+fn test_nested() {
+    #[derive(Merge)]
+    #[merge(visit)]
+    struct S {
+        #[merge(strategy = conflate::bool::overwrite_false)]
+        flag: bool,
+        repository: Repository,
+        #[merge(strategy = conflate::option::recurse)]
+        backup: Option<Repository>,
+    }
+
+    test(
+        &[
+            ("flag", "true"),
+            ("repository.path", r#""/srv""#),
+            ("repository.retries", "3"),
+            ("backup.retries", "0"),
+        ],
+        &S {
+            flag: true,
+            repository: Repository {
+                path: Some("/srv".to_owned()),
+                retries: 3,
+            },
+            backup: Some(Repository {
+                path: None,
+                retries: 0,
+            }),
+        },
+    );
+}
+
+#[test]
+fn test_collections() {
+    #[derive(Merge)]
+    #[merge(visit)]
+    struct S {
+        #[merge(strategy = conflate::vec::append)]
+        groups: Vec<String>,
+        #[merge(strategy = conflate::hashmap::append_or_recurse)]
+        repositories: HashMap<String, Repository>,
+    }
+
+    test(
+        &[
+            ("groups.0", r#""rust""#),
+            ("groups.1", r#""mascot""#),
+            ("repositories.home.path", r#""/home""#),
+            ("repositories.home.retries", "1"),
+        ],
+        &S {
+            groups: vec!["rust".to_owned(), "mascot".to_owned()],
+            repositories: vec![(
+                "home".to_owned(),
+                Repository {
+                    path: Some("/home".to_owned()),
+                    retries: 1,
+                },
+            )]
+            .into_iter()
+            .collect(),
+        },
+    );
+}
+
+#[test]
+fn test_skip_and_secret() {
+    struct Handle;
+
+    #[derive(Merge)]
+    #[merge(visit)]
+    struct S {
+        #[merge(strategy = conflate::option::overwrite_none)]
+        password: Option<Secret<String>>,
+        #[merge(skip)]
+        #[allow(dead_code)]
+        handle: Handle,
+    }
+
+    test(
+        &[("password", "secret")],
+        &S {
+            password: Some(Secret::new("hunter2".to_owned())),
+            handle: Handle,
+        },
+    );
+}
+
+#[test]
+fn test_visit_mut() {
+    #[derive(Merge)]
+    #[merge(visit)]
+    struct S {
+        #[merge(strategy = conflate::vec::append)]
+        groups: Vec<String>,
+        repository: Repository,
+    }
+
+    let mut s = S {
+        groups: vec!["Rust".to_owned()],
+        repository: Repository {
+            path: Some("/SRV".to_owned()),
+            retries: 2,
+        },
+    };
+    s.visit_mut(&mut |_: &FieldPath<'_>, value: &mut dyn Any| {
+        if let Some(s) = value.downcast_mut::<String>() {
+            *s = s.to_lowercase();
+        } else if let Some(n) = value.downcast_mut::<u8>() {
+            *n *= 2;
+        }
+    });
+    assert_eq!(vec!["rust"], s.groups);
+    assert_eq!(Some("/srv"), s.repository.path.as_deref());
+    assert_eq!(4, s.repository.retries);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_keys() {
+    #[derive(Merge, serde::Deserialize)]
+    #[merge(visit, strategy = conflate::option::overwrite_none)]
+    #[serde(rename_all = "kebab-case")]
+    struct Paths {
+        cache_dir: Option<String>,
+    }
+
+    #[derive(Merge, serde::Deserialize)]
+    #[merge(visit)]
+    struct S {
+        #[serde(flatten)]
+        paths: Paths,
+        #[serde(rename = "tries")]
+        #[merge(strategy = conflate::ord::max)]
+        retries: u8,
+    }
+
+    test(
+        &[("cache-dir", r#""/tmp""#), ("tries", "0")],
+        &S {
+            paths: Paths {
+                cache_dir: Some("/tmp".to_owned()),
+            },
+            retries: 0,
+        },
+    );
+}
@@ -0,0 +1,274 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+#![cfg(all(feature = "derive", feature = "watch"))]
+
+use std::fs::{self, File};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use conflate::layers::{ErrorKind, FileSource, Layers, ValueSource};
+use conflate::watch::Watcher;
+use conflate::{Merge, MergePreview};
+
+#[derive(Clone, Debug, Default, PartialEq, Merge, MergePreview, serde::Deserialize)]
+#[serde(default)]
+struct Repository {
+    #[merge(strategy = conflate::option::overwrite_none)]
+    path: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Merge, MergePreview, serde::Deserialize)]
+#[serde(default)]
+struct Config {
+    #[merge(strategy = conflate::ord::max)]
+    verbosity: u8,
+    #[merge(strategy = conflate::vec::append)]
+    tags: Vec<String>,
+    repository: Repository,
+}
+
+fn source(path: &Path) -> FileSource<Config> {
+    FileSource::new(path, |s| serde_json::from_str(s))
+}
+
+// the tests are not built with the minimum supported Rust version
+#[allow(clippy::incompatible_msrv)]
+fn write(path: &Path, content: &str, modified: u64) {
+    fs::write(path, content).unwrap();
+    // set the modification time explicitly, as the resolution of the file system might be too
+    // low to distinguish consecutive writes
+    File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(modified))
+        .unwrap();
+}
+
+#[test]
+fn test_layers() {
+    let dir = tempfile::tempdir().unwrap();
+    let system = dir.path().join("system.json");
+    let user = dir.path().join("user.json");
+    write(&system, r#"{ "tags": ["system"], "verbosity": 1 }"#, 1);
+
+    let cli = Config {
+        verbosity: 2,
+        tags: vec!["cli".to_owned()],
+        repository: Repository { path: None },
+    };
+    let layers = Layers::new()
+        .with_source(ValueSource::new("command line", cli))
+        .with_source(source(&user).optional())
+        .with_source(source(&system));
+
+    assert_eq!(
+        Config {
+            verbosity: 2,
+            tags: vec!["cli".to_owned(), "system".to_owned()],
+            repository: Repository { path: None },
+        },
+        layers.load().unwrap()
+    );
+
+    write(&user, r#"{ "repository": { "path": "/srv" } }"#, 1);
+    assert_eq!(
+        Some("/srv"),
+        layers.load().unwrap().repository.path.as_deref()
+    );
+
+    let names: Vec<_> = layers.names().collect();
+    assert_eq!("command line", names[0]);
+    assert_eq!(user.display().to_string(), names[1]);
+}
+
+#[test]
+fn test_layers_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let missing = dir.path().join("missing.json");
+    let invalid = dir.path().join("invalid.json");
+    write(&invalid, "{", 1);
+
+    let err = Layers::new()
+        .with_source(source(&missing))
+        .load()
+        .unwrap_err();
+    assert_eq!(missing.display().to_string(), err.source_name());
+    assert!(matches!(err.kind(), ErrorKind::Io(_)));
+
+    let err = Layers::new()
+        .with_source(source(&invalid))
+        .load()
+        .unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::Parse(_)));
+    assert_eq!(
+        format!("failed to parse {}", invalid.display()),
+        err.to_string()
+    );
+}
+
+#[test]
+fn test_watcher_reload() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.json");
+    write(&path, r#"{ "verbosity": 1 }"#, 1);
+
+    let mut watcher = Watcher::new(Layers::new().with_source(source(&path))).unwrap();
+    let handle = watcher.handle();
+    let updates = watcher.subscribe();
+    assert_eq!(1, handle.load().verbosity);
+
+    // unchanged value
+    assert!(watcher.reload().unwrap().is_none());
+
+    write(
+        &path,
+        r#"{ "verbosity": 1, "repository": { "path": "/srv" }, "tags": ["a"] }"#,
+        2,
+    );
+    let update = watcher.reload().unwrap().unwrap();
+    assert_eq!(
+        vec!["tags", "repository.path"],
+        update.changed_paths().collect::<Vec<_>>()
+    );
+    assert_eq!(r#"Some("/srv")"#, update.changes[1].new);
+    assert_eq!(update.value, handle.load());
+
+    let received = updates.try_recv().unwrap();
+    assert_eq!(update.changes, received.changes);
+    assert!(updates.try_recv().is_err());
+
+    // the current value is kept on errors
+    write(&path, "{", 3);
+    assert!(watcher.reload().is_err());
+    assert_eq!(update.value, handle.load());
+}
+
+#[test]
+fn test_watcher_skip() {
+    #[derive(Clone, Debug, Default, PartialEq, Merge, MergePreview, serde::Deserialize)]
+    #[serde(default)]
+    struct S {
+        #[merge(strategy = conflate::ord::max)]
+        verbosity: u8,
+        #[merge(skip)]
+        generation: u8,
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.json");
+    write(&path, r#"{ "verbosity": 1 }"#, 1);
+
+    let source = FileSource::new(&path, |s| serde_json::from_str::<S>(s));
+    let mut watcher = Watcher::new(Layers::new().with_source(source)).unwrap();
+
+    // skipped fields are neither merged nor reported
+    write(&path, r#"{ "verbosity": 1, "generation": 2 }"#, 2);
+    assert!(watcher.reload().unwrap().is_none());
+
+    write(&path, r#"{ "verbosity": 2, "generation": 3 }"#, 3);
+    let update = watcher.reload().unwrap().unwrap();
+    assert_eq!(
+        vec!["verbosity"],
+        update.changed_paths().collect::<Vec<_>>()
+    );
+    assert_eq!(0, update.value.generation);
+}
+
+#[test]
+fn test_watcher_poll() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.json");
+    write(&path, r#"{ "verbosity": 1 }"#, 1);
+
+    let mut watcher = Watcher::new(Layers::new().with_source(source(&path).optional())).unwrap();
+    let handle = watcher.handle();
+    assert!(watcher.poll().unwrap().is_none());
+
+    // the file is not reloaded if the modification time did not change
+    write(&path, r#"{ "verbosity": 2 }"#, 1);
+    assert!(watcher.poll().unwrap().is_none());
+    assert_eq!(1, handle.load().verbosity);
+
+    write(&path, r#"{ "verbosity": 3 }"#, 2);
+    let update = watcher.poll().unwrap().unwrap();
+    assert_eq!(
+        vec!["verbosity"],
+        update.changed_paths().collect::<Vec<_>>()
+    );
+    assert_eq!(3, handle.load().verbosity);
+
+    // removing an optional file is a change, too
+    fs::remove_file(&path).unwrap();
+    assert!(watcher.poll().unwrap().is_some());
+    assert_eq!(Config::default(), *handle.load());
+
+    // a file that cannot be parsed, e.g. because it is still being written, is loaded again
+    write(&path, "{", 3);
+    assert!(watcher.poll().is_err());
+    assert!(watcher.poll().is_err());
+    write(&path, r#"{ "verbosity": 4 }"#, 3);
+    assert!(watcher.poll().unwrap().is_some());
+    assert_eq!(4, handle.load().verbosity);
+    assert!(watcher.poll().unwrap().is_none());
+}
+
+#[test]
+fn test_dropped_subscriber() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.json");
+    write(&path, r#"{ "verbosity": 1 }"#, 1);
+
+    let mut watcher = Watcher::new(Layers::new().with_source(source(&path))).unwrap();
+    drop(watcher.subscribe());
+    let updates = watcher.subscribe();
+
+    write(&path, r#"{ "verbosity": 2 }"#, 2);
+    assert!(watcher.reload().unwrap().is_some());
+    assert_eq!(2, updates.try_recv().unwrap().value.verbosity);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_watcher_async() {
+    use conflate::layers::{AsyncSource, BoxFuture, Error};
+    use std::sync::atomic::{AtomicU8, Ordering};
+    use std::sync::Arc;
+
+    struct Remote(Arc<AtomicU8>);
+
+    impl AsyncSource<Config> for Remote {
+        fn name(&self) -> String {
+            "remote".to_owned()
+        }
+
+        fn load(&self) -> BoxFuture<'_, Result<Option<Config>, Error>> {
+            Box::pin(async move {
+                Ok(Some(Config {
+                    verbosity: self.0.load(Ordering::SeqCst),
+                    ..Config::default()
+                }))
+            })
+        }
+    }
+
+    let verbosity = Arc::new(AtomicU8::new(1));
+    let layers = Layers::new().with_async_source(Remote(Arc::clone(&verbosity)));
+    let err = Watcher::new(layers).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::Async));
+
+    let layers = Layers::new().with_async_source(Remote(Arc::clone(&verbosity)));
+    let mut watcher = Watcher::new_async(layers).await.unwrap();
+    let handle = watcher.handle();
+    assert_eq!(1, handle.load().verbosity);
+
+    verbosity.store(2, Ordering::SeqCst);
+    let update = watcher.reload_async().await.unwrap().unwrap();
+    assert_eq!(
+        vec!["verbosity"],
+        update.changed_paths().collect::<Vec<_>>()
+    );
+    assert_eq!(2, handle.load().verbosity);
+    assert!(watcher.reload().is_err());
+}
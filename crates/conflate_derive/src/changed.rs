@@ -0,0 +1,99 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Implementation of the `MergeChanged` derive macro.
+
+use proc_macro2::TokenStream;
+use proc_macro_error2::{abort_call_site, dummy::set_dummy};
+use quote::{quote, quote_spanned};
+
+use crate::serde_attr::SerdeContainerAttrs;
+use crate::{gen_conditions, gen_deprecated_aliases, Field, FieldAttrs};
+
+pub fn impl_merge_changed(ast: &syn::DeriveInput) -> TokenStream {
+    let name = &ast.ident;
+    let default_strategy = FieldAttrs::from(ast.attrs.iter());
+    let container = SerdeContainerAttrs::from(ast.attrs.iter());
+
+    set_dummy(quote! {
+        impl ::conflate::MergeChanged for #name {
+            fn merge_changed_with(
+                &mut self,
+                other: Self,
+                path: &::conflate::FieldPath<'_>,
+                changed: &mut dyn FnMut(&::conflate::FieldPath<'_>),
+            ) {
+                unimplemented!()
+            }
+        }
+    });
+
+    if let syn::Data::Struct(syn::DataStruct { ref fields, .. }) = ast.data {
+        let fields: Vec<_> = fields.iter().enumerate().map(Field::from).collect();
+        let aliases = gen_deprecated_aliases(&fields, |_, _| None);
+        let conditions = gen_conditions(&fields, |f| f.value_of(&quote!(other)));
+        let assignments = fields
+            .iter()
+            .filter(|f| !f.attrs.skip)
+            .map(|f| f.if_condition(gen_assignment(f, &default_strategy, &container)));
+
+        quote! {
+            impl ::conflate::MergeChanged for #name {
+                fn merge_changed_with(
+                    &mut self,
+                    other: Self,
+                    path: &::conflate::FieldPath<'_>,
+                    changed: &mut dyn FnMut(&::conflate::FieldPath<'_>),
+                ) {
+                    #aliases
+                    #conditions
+                    #( #assignments )*
+                }
+            }
+        }
+    } else {
+        abort_call_site!("conflate::MergeChanged can only be derived for structs")
+    }
+}
+
+fn gen_assignment(
+    field: &Field,
+    default_strategy: &FieldAttrs,
+    container: &SerdeContainerAttrs,
+) -> TokenStream {
+    use syn::spanned::Spanned;
+
+    let name = &field.name;
+    let field_path = match field.key(container) {
+        Some(key) => quote!(&path.join(#key)),
+        None => quote!(path),
+    };
+
+    if let Some(strategy) = field.strategy_changed(default_strategy) {
+        quote_spanned! {strategy.span()=>
+            if #strategy(&mut self.#name, other.#name) {
+                changed(#field_path);
+            }
+        }
+    } else if let Some(strategy) = field.strategy(default_strategy) {
+        let merge = field.call_strategy(&strategy, quote!(&mut self.#name), quote!(other.#name));
+        quote_spanned! {strategy.span()=>
+            {
+                let old = ::core::clone::Clone::clone(&self.#name);
+                #merge;
+                if self.#name != old {
+                    changed(#field_path);
+                }
+            }
+        }
+    } else {
+        quote_spanned! {field.span=>
+            ::conflate::MergeChanged::merge_changed_with(
+                &mut self.#name,
+                other.#name,
+                #field_path,
+                changed,
+            );
+        }
+    }
+}
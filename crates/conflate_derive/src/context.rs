@@ -0,0 +1,109 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Implementation of the `MergeWithContext` derive macro.
+
+use proc_macro2::TokenStream;
+use proc_macro_error2::{abort_call_site, dummy::set_dummy};
+use quote::{quote, quote_spanned};
+
+use crate::serde_attr::SerdeContainerAttrs;
+use crate::{gen_conditions, gen_deprecated_aliases, type_name, Field, FieldAttrs};
+
+pub fn impl_merge_with_context(ast: &syn::DeriveInput) -> TokenStream {
+    let name = &ast.ident;
+    let default_strategy = FieldAttrs::from(ast.attrs.iter());
+    let container = SerdeContainerAttrs::from(ast.attrs.iter());
+
+    set_dummy(quote! {
+        impl ::conflate::MergeWithContext for #name {
+            fn merge_with_ctx_at(
+                &mut self,
+                other: Self,
+                path: &::conflate::FieldPath<'_>,
+                ctx: &mut ::conflate::MergeContext,
+            ) {
+                unimplemented!()
+            }
+        }
+    });
+
+    if let syn::Data::Struct(syn::DataStruct { ref fields, .. }) = ast.data {
+        let fields: Vec<_> = fields.iter().enumerate().map(Field::from).collect();
+        let aliases = gen_deprecated_aliases(&fields, |old, new| {
+            let old_path = field_path(old, &container);
+            let new_path = field_path(new, &container);
+            Some(quote!(ctx.deprecated(#old_path, #new_path);))
+        });
+        let conditions = gen_conditions(&fields, |f| f.value_of(&quote!(other)));
+        let assignments = fields
+            .iter()
+            .filter(|f| !f.attrs.skip)
+            .map(|f| f.if_condition(gen_assignment(f, &default_strategy, &container)));
+
+        quote! {
+            impl ::conflate::MergeWithContext for #name {
+                fn merge_with_ctx_at(
+                    &mut self,
+                    other: Self,
+                    path: &::conflate::FieldPath<'_>,
+                    ctx: &mut ::conflate::MergeContext,
+                ) {
+                    #aliases
+                    #conditions
+                    #( #assignments )*
+                }
+            }
+        }
+    } else {
+        abort_call_site!("conflate::MergeWithContext can only be derived for structs")
+    }
+}
+
+fn gen_assignment(
+    field: &Field,
+    default_strategy: &FieldAttrs,
+    container: &SerdeContainerAttrs,
+) -> TokenStream {
+    let name = &field.name;
+    let field_path = field_path(field, container);
+
+    if let Some(strategy) = field.strategy(default_strategy) {
+        let merge = if field.attrs.lockable {
+            let merge = field.call_strategy(&strategy, quote!(left), quote!(right));
+            quote!(|left, right| #merge)
+        } else {
+            quote!(#strategy)
+        };
+        let is_map = !field.attrs.lockable
+            && matches!(
+                type_name(&field.ty).as_deref(),
+                Some("HashMap" | "BTreeMap" | "IndexMap")
+            );
+        if is_map {
+            quote_spanned! {field.span=>
+                ctx.merge_entries(#field_path, &mut self.#name, other.#name, #merge);
+            }
+        } else {
+            quote_spanned! {field.span=>
+                ctx.merge_field(#field_path, &mut self.#name, other.#name, #merge);
+            }
+        }
+    } else {
+        quote_spanned! {field.span=>
+            ::conflate::MergeWithContext::merge_with_ctx_at(
+                &mut self.#name,
+                other.#name,
+                #field_path,
+                ctx,
+            );
+        }
+    }
+}
+
+fn field_path(field: &Field, container: &SerdeContainerAttrs) -> TokenStream {
+    match field.key(container) {
+        Some(key) => quote!(&path.join(#key)),
+        None => quote!(path),
+    }
+}
@@ -0,0 +1,379 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Implementation of the `DeserializeMerge` derive macro.
+
+use proc_macro2::TokenStream;
+use proc_macro_error2::{abort, abort_call_site, dummy::set_dummy};
+use quote::{quote, quote_spanned};
+
+use crate::serde_attr::SerdeContainerAttrs;
+use crate::{Field, FieldAttrs};
+
+pub fn impl_deserialize_merge(ast: &syn::DeriveInput) -> TokenStream {
+    let name = &ast.ident;
+    let default_strategy = FieldAttrs::from(ast.attrs.iter());
+    let container = SerdeContainerAttrs::from(ast.attrs.iter());
+
+    set_dummy(quote! {
+        impl ::conflate::serde::DeserializeMerge for #name {
+            fn merge_field<'de, A>(
+                &mut self,
+                key: &str,
+                map: &mut A,
+            ) -> ::core::result::Result<bool, A::Error>
+            where
+                A: ::conflate::serde::__private::MapAccess<'de>,
+            {
+                unimplemented!()
+            }
+        }
+    });
+
+    match ast.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(ref fields),
+            ..
+        }) => impl_deserialize_merge_for_struct(name, fields, &default_strategy, &container),
+        _ => abort_call_site!(
+            "conflate::DeserializeMerge can only be derived for structs with named fields"
+        ),
+    }
+}
+
+fn impl_deserialize_merge_for_struct(
+    name: &syn::Ident,
+    fields: &syn::FieldsNamed,
+    default_strategy: &FieldAttrs,
+    container: &SerdeContainerAttrs,
+) -> TokenStream {
+    let fields: Vec<_> = fields.named.iter().enumerate().map(Field::from).collect();
+
+    for field in fields.iter().filter(|f| f.serde.flatten) {
+        if let Some(strategy) = &field.attrs.strategy {
+            abort!(strategy, "Flattened fields cannot have a merge strategy");
+        }
+    }
+    for field in fields.iter().filter(|f| !f.attrs.skip) {
+        let predicate = field
+            .attrs
+            .skip_if
+            .as_ref()
+            .or(field.attrs.only_if.as_ref());
+        if let Some(predicate) = predicate {
+            if field.serde.flatten || field.strategy(default_strategy).is_none() {
+                abort!(
+                    predicate,
+                    "Fields with a skip_if or only_if predicate must have a merge strategy"
+                );
+            }
+        }
+    }
+
+    let names = fields
+        .iter()
+        .map(|f| gen_field_name(f, default_strategy, container));
+    let arms = fields
+        .iter()
+        .filter(|f| !f.serde.skip && !f.serde.flatten)
+        .map(|f| {
+            let (key, aliases) = gen_keys(f, container);
+            let body = match replacement(f, &fields) {
+                Some(field) => {
+                    let name = &field.name;
+                    let old_name = &f.name;
+                    let merge = gen_merge(field, default_strategy);
+                    quote_spanned! {f.span=>
+                        ::conflate::deprecated::fold(&mut self.#name, &mut self.#old_name);
+                        #merge
+                    }
+                }
+                None => gen_merge(f, default_strategy),
+            };
+            quote!(#key #( | #aliases )* => { #body })
+        });
+    let flattened = fields
+        .iter()
+        .filter(|f| f.serde.flatten && !f.serde.skip && !f.attrs.skip)
+        .map(|f| {
+            let name = &f.name;
+            quote_spanned! {f.span=>
+                if ::conflate::serde::DeserializeMerge::merge_field(&mut self.#name, key, map)? {
+                    return ::core::result::Result::Ok(true);
+                }
+            }
+        });
+    let unknown = if container.deny_unknown_fields {
+        let keys = fields.iter().filter_map(|f| f.serde_key(container));
+        quote! {
+            return ::core::result::Result::Err(
+                <A::Error as ::conflate::serde::__private::Error>::unknown_field(
+                    key,
+                    &[ #( #keys ),* ],
+                ),
+            );
+        }
+    } else {
+        quote! {
+            return ::core::result::Result::Ok(false);
+        }
+    };
+    let merge_map = gen_merge_map(&fields, default_strategy, container);
+
+    quote! {
+        impl ::conflate::serde::DeserializeMerge for #name {
+            const FIELD_NAMES: &'static [::conflate::serde::FieldName] = &[ #( #names ),* ];
+
+            #merge_map
+
+            fn merge_field<'de, A>(
+                &mut self,
+                key: &str,
+                map: &mut A,
+            ) -> ::core::result::Result<bool, A::Error>
+            where
+                A: ::conflate::serde::__private::MapAccess<'de>,
+            {
+                match key {
+                    #( #arms )*
+                    _ => {
+                        #( #flattened )*
+                        #unknown
+                    }
+                }
+                ::core::result::Result::Ok(true)
+            }
+        }
+    }
+}
+
+/// Returns the field that replaces the given deprecated field, see the `deprecated_alias`
+/// attribute.
+fn replacement<'a>(field: &Field, fields: &'a [Field]) -> Option<&'a Field> {
+    fields
+        .iter()
+        .find(|f| f.deprecated_field(fields).map(|old| &old.name) == Some(&field.name))
+}
+
+/// Generates an implementation of `DeserializeMerge::merge_map` that reads all entries of the map
+/// before merging the fields with a strategy, or nothing if the default implementation can be
+/// used.
+///
+/// The values of the fields with a strategy are stored in local variables while the map is read.
+/// Afterwards, the values of the deprecated keys are moved into the fields that replace them, the
+/// `skip_if` and `only_if` predicates are evaluated for all stored values and the values are
+/// merged.  All other keys are passed to `merge_field`.
+fn gen_merge_map(
+    fields: &[Field],
+    default_strategy: &FieldAttrs,
+    container: &SerdeContainerAttrs,
+) -> Option<TokenStream> {
+    let has_condition = |f: &Field| !f.attrs.skip && f.condition(&TokenStream::new()).is_some();
+    if !fields
+        .iter()
+        .any(|f| f.deprecated_field(fields).is_some() || has_condition(f))
+    {
+        return None;
+    }
+
+    let deferred: Vec<_> = fields
+        .iter()
+        .filter(|f| !f.serde.skip && !f.serde.flatten && !f.attrs.skip)
+        .filter(|f| f.strategy(default_strategy).is_some() && replacement(f, fields).is_none())
+        .collect();
+    // the deprecated fields whose values are stored in the variable of the field replacing them
+    let aliases: Vec<_> = deferred
+        .iter()
+        .filter_map(|f| Some((*f, f.deprecated_field(fields)?)))
+        .filter(|(_, old)| !old.serde.skip && !old.serde.flatten)
+        .collect();
+
+    let vars = deferred.iter().map(|f| {
+        let var = f.alias_var("value");
+        let ty = &f.ty;
+        quote!(let mut #var: ::core::option::Option<#ty> = ::core::option::Option::None;)
+    });
+    let alias_vars = aliases.iter().map(|(f, _)| {
+        let var = f.alias_var("alias");
+        let ty = &f.ty;
+        quote!(let mut #var: ::core::option::Option<#ty> = ::core::option::Option::None;)
+    });
+    let arms = deferred
+        .iter()
+        .map(|f| gen_store_arm(f, &f.alias_var("value"), container))
+        .chain(
+            aliases
+                .iter()
+                .map(|(f, old)| gen_store_arm(old, &f.alias_var("alias"), container)),
+        );
+    let folds = fields.iter().filter_map(|f| {
+        let old = f.deprecated_field(fields)?;
+        let name = &f.name;
+        let old_name = &old.name;
+        Some(quote!(::conflate::deprecated::fold(&mut self.#name, &mut self.#old_name);))
+    });
+    let alias_folds = aliases.iter().map(|(f, _)| {
+        let var = f.alias_var("value");
+        let alias_var = f.alias_var("alias");
+        quote! {
+            if let ::core::option::Option::Some(mut old) = #alias_var {
+                match &mut #var {
+                    ::core::option::Option::Some(value) => {
+                        ::conflate::deprecated::fold(value, &mut old);
+                    }
+                    ::core::option::Option::None => #var = ::core::option::Option::Some(old),
+                }
+            }
+        }
+    });
+    let conditions = deferred.iter().filter_map(|f| {
+        let var = f.alias_var("value");
+        let condition_var = f.condition_var();
+        let condition = f.condition(&quote!(value))?;
+        Some(quote! {
+            let #condition_var = match &#var {
+                ::core::option::Option::Some(value) => #condition,
+                ::core::option::Option::None => false,
+            };
+        })
+    });
+    let merges = deferred.iter().map(|f| {
+        let var = f.alias_var("value");
+        let name = &f.name;
+        let strategy = f
+            .strategy(default_strategy)
+            .expect("deferred fields have a strategy");
+        let merge = f.call_strategy(&strategy, quote!(&mut self.#name), quote!(value));
+        f.if_condition(quote! {
+            if let ::core::option::Option::Some(value) = #var {
+                #merge;
+            }
+        })
+    });
+
+    Some(quote! {
+        fn merge_map<'de, A>(&mut self, mut map: A) -> ::core::result::Result<(), A::Error>
+        where
+            A: ::conflate::serde::__private::MapAccess<'de>,
+        {
+            #( #vars )*
+            #( #alias_vars )*
+            while let ::core::option::Option::Some(key) =
+                ::conflate::serde::__private::MapAccess::next_key::<::std::string::String>(&mut map)?
+            {
+                match key.as_str() {
+                    #( #arms )*
+                    _ => {
+                        if !::conflate::serde::DeserializeMerge::merge_field(self, &key, &mut map)? {
+                            let _: ::conflate::serde::__private::IgnoredAny =
+                                ::conflate::serde::__private::MapAccess::next_value(&mut map)?;
+                        }
+                    }
+                }
+            }
+            #( #folds )*
+            #( #alias_folds )*
+            #( #conditions )*
+            #( #merges )*
+            ::core::result::Result::Ok(())
+        }
+    })
+}
+
+/// Generates a match arm that stores the next value of the map in the given variable if the key
+/// matches the key or an alias of the field.
+fn gen_store_arm(field: &Field, var: &syn::Ident, container: &SerdeContainerAttrs) -> TokenStream {
+    let (key, aliases) = gen_keys(field, container);
+    quote_spanned! {field.span=>
+        #key #( | #aliases )* => {
+            if #var.is_some() {
+                return ::core::result::Result::Err(
+                    <A::Error as ::conflate::serde::__private::Error>::duplicate_field(#key),
+                );
+            }
+            #var = ::core::option::Option::Some(
+                ::conflate::serde::__private::MapAccess::next_value(&mut map)?,
+            );
+        }
+    }
+}
+
+/// Returns the key and the aliases of the given field that is not flattened.
+fn gen_keys<'a>(field: &'a Field, container: &SerdeContainerAttrs) -> (String, &'a [String]) {
+    let key = field
+        .key(container)
+        .expect("flattened fields are merged separately");
+    (key, &field.serde.aliases)
+}
+
+fn gen_field_name(
+    field: &Field,
+    default_strategy: &FieldAttrs,
+    container: &SerdeContainerAttrs,
+) -> TokenStream {
+    let ident = field.ident();
+    let key = match field.serde_key(container) {
+        Some(key) => quote!(::core::option::Option::Some(#key)),
+        None => quote!(::core::option::Option::None),
+    };
+    let aliases = &field.serde.aliases;
+    let flatten = field.serde.flatten;
+    let nested = if field.attrs.skip || (field.strategy(default_strategy).is_some() && !flatten) {
+        quote!(::core::option::Option::None)
+    } else {
+        let ty = &field.ty;
+        quote! {
+            ::core::option::Option::Some(
+                || <#ty as ::conflate::serde::DeserializeMerge>::FIELD_NAMES
+            )
+        }
+    };
+
+    quote! {
+        ::conflate::serde::FieldName {
+            ident: #ident,
+            key: #key,
+            aliases: &[ #( #aliases ),* ],
+            flatten: #flatten,
+            nested: #nested,
+        }
+    }
+}
+
+/// Generates statements that deserialize the next value of the map into the given field.
+fn gen_merge(field: &Field, default_strategy: &FieldAttrs) -> TokenStream {
+    use syn::spanned::Spanned;
+
+    let name = &field.name;
+
+    if field.attrs.skip {
+        quote_spanned! {field.span=>
+            let _: ::conflate::serde::__private::IgnoredAny =
+                ::conflate::serde::__private::MapAccess::next_value(map)?;
+        }
+    } else if let Some(strategy) = field.strategy(default_strategy) {
+        if let Some(condition) = field.condition(&quote!(&value)) {
+            let merge = field.call_strategy(&strategy, quote!(&mut self.#name), quote!(value));
+            quote_spanned! {strategy.span()=>
+                let value = ::conflate::serde::__private::MapAccess::next_value(map)?;
+                if #condition {
+                    #merge;
+                }
+            }
+        } else {
+            let merge = field.call_strategy(
+                &strategy,
+                quote!(&mut self.#name),
+                quote!(::conflate::serde::__private::MapAccess::next_value(map)?),
+            );
+            quote_spanned!(strategy.span()=> #merge;)
+        }
+    } else {
+        quote_spanned! {field.span=>
+            ::conflate::serde::__private::MapAccess::next_value_seed(
+                map,
+                ::conflate::serde::MergeSeed(&mut self.#name),
+            )?;
+        }
+    }
+}
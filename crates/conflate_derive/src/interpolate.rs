@@ -0,0 +1,47 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Implementation of the `Interpolate` derive macro.
+//!
+//! `Interpolate` is implemented for all types that implement `MergeVisit`, so the derive macro
+//! generates the same `MergeVisit` implementation as the `visit` attribute of the `Merge` derive
+//! macro.
+
+use proc_macro2::TokenStream;
+use proc_macro_error2::{abort_call_site, dummy::set_dummy};
+use quote::quote;
+
+use crate::serde_attr::SerdeContainerAttrs;
+use crate::Field;
+
+pub fn impl_interpolate(ast: &syn::DeriveInput) -> TokenStream {
+    let name = &ast.ident;
+    let container = SerdeContainerAttrs::from(ast.attrs.iter());
+
+    set_dummy(quote! {
+        impl ::conflate::MergeVisit for #name {
+            fn visit_with(
+                &self,
+                path: &::conflate::FieldPath<'_>,
+                visitor: &mut dyn ::conflate::visit::Visitor,
+            ) {
+                unimplemented!()
+            }
+
+            fn visit_mut_with(
+                &mut self,
+                path: &::conflate::FieldPath<'_>,
+                visitor: &mut dyn ::conflate::visit::VisitorMut,
+            ) {
+                unimplemented!()
+            }
+        }
+    });
+
+    if let syn::Data::Struct(syn::DataStruct { ref fields, .. }) = ast.data {
+        let fields: Vec<_> = fields.iter().enumerate().map(Field::from).collect();
+        crate::visit::impl_merge_visit(name, &fields, &container)
+    } else {
+        abort_call_site!("conflate::Interpolate can only be derived for structs")
+    }
+}
@@ -10,26 +10,79 @@
 
 extern crate proc_macro;
 
+mod changed;
+mod context;
+mod de;
+mod interpolate;
+mod merge_ref;
+mod meta;
+mod preview;
+mod serde_attr;
+mod trust;
+mod try_merge;
+mod visit;
+
+use std::borrow::Cow;
+
 use proc_macro2::TokenStream;
 use proc_macro_error2::{abort, abort_call_site, dummy::set_dummy, proc_macro_error, ResultExt};
 use quote::{quote, quote_spanned};
+use syn::ext::IdentExt;
 use syn::Token;
 
+use crate::serde_attr::{SerdeContainerAttrs, SerdeFieldAttrs};
+
 struct Field {
     name: syn::Member,
+    ty: syn::Type,
     span: proc_macro2::Span,
+    doc: String,
     attrs: FieldAttrs,
+    serde: SerdeFieldAttrs,
 }
 
 #[derive(Default)]
 struct FieldAttrs {
     skip: bool,
-    strategy: Option<syn::Path>,
+    skip_interpolate: bool,
+    visit: bool,
+    auto: bool,
+    precedence: Option<Precedence>,
+    strategy: Option<syn::Expr>,
+    type_strategies: Vec<(syn::Ident, syn::Path)>,
+    strategy_ref: Option<syn::Path>,
+    strategy_changed: Option<syn::Path>,
+    strategy_try: Option<syn::Path>,
+    skip_if: Option<syn::Path>,
+    only_if: Option<syn::Path>,
+    lockable: bool,
+    min_trust: Option<syn::Ident>,
+    deprecated_alias: Option<syn::Ident>,
+}
+
+/// The side that takes precedence if both sides of a merge are set.
+#[derive(Clone, Copy)]
+enum Precedence {
+    Left,
+    Right,
 }
 
 enum FieldAttr {
     Skip,
-    Strategy(syn::Path),
+    SkipInterpolate,
+    Visit,
+    Auto,
+    Precedence(Precedence),
+    Strategy(syn::Expr),
+    TypeStrategies(Vec<(syn::Ident, syn::Path)>),
+    StrategyRef(syn::Path),
+    StrategyChanged(syn::Path),
+    StrategyTry(syn::Path),
+    SkipIf(syn::Path),
+    OnlyIf(syn::Path),
+    Lockable,
+    MinTrust(syn::Ident),
+    DeprecatedAlias(syn::Ident),
 }
 
 #[proc_macro_derive(Merge, attributes(merge))]
@@ -39,9 +92,73 @@ pub fn merge_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     impl_merge(&ast).into()
 }
 
+#[proc_macro_derive(MergeMeta, attributes(merge, serde))]
+#[proc_macro_error]
+pub fn merge_meta_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast = syn::parse(input).unwrap();
+    meta::impl_merge_meta(&ast).into()
+}
+
+#[proc_macro_derive(MergeRef, attributes(merge))]
+#[proc_macro_error]
+pub fn merge_ref_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast = syn::parse(input).unwrap();
+    merge_ref::impl_merge_ref(&ast).into()
+}
+
+#[proc_macro_derive(MergeChanged, attributes(merge))]
+#[proc_macro_error]
+pub fn merge_changed_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast = syn::parse(input).unwrap();
+    changed::impl_merge_changed(&ast).into()
+}
+
+#[proc_macro_derive(Interpolate, attributes(merge))]
+#[proc_macro_error]
+pub fn interpolate_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast = syn::parse(input).unwrap();
+    interpolate::impl_interpolate(&ast).into()
+}
+
+#[proc_macro_derive(MergePreview, attributes(merge))]
+#[proc_macro_error]
+pub fn merge_preview_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast = syn::parse(input).unwrap();
+    preview::impl_merge_preview(&ast).into()
+}
+
+#[proc_macro_derive(TryMerge, attributes(merge))]
+#[proc_macro_error]
+pub fn try_merge_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast = syn::parse(input).unwrap();
+    try_merge::impl_try_merge(&ast).into()
+}
+
+#[proc_macro_derive(MergeTrusted, attributes(merge))]
+#[proc_macro_error]
+pub fn merge_trusted_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast = syn::parse(input).unwrap();
+    trust::impl_merge_trusted(&ast).into()
+}
+
+#[proc_macro_derive(MergeWithContext, attributes(merge))]
+#[proc_macro_error]
+pub fn merge_with_context_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast = syn::parse(input).unwrap();
+    context::impl_merge_with_context(&ast).into()
+}
+
+#[proc_macro_derive(DeserializeMerge, attributes(merge, serde))]
+#[proc_macro_error]
+pub fn deserialize_merge_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast = syn::parse(input).unwrap();
+    de::impl_deserialize_merge(&ast).into()
+}
+
 fn impl_merge(ast: &syn::DeriveInput) -> TokenStream {
     let name = &ast.ident;
     let default_strategy = FieldAttrs::from(ast.attrs.iter());
+    let container = SerdeContainerAttrs::from(ast.attrs.iter());
 
     set_dummy(quote! {
         impl ::conflate::Merge for #name {
@@ -52,7 +169,17 @@ fn impl_merge(ast: &syn::DeriveInput) -> TokenStream {
     });
 
     if let syn::Data::Struct(syn::DataStruct { ref fields, .. }) = ast.data {
-        impl_merge_for_struct(name, fields, default_strategy)
+        let fields: Vec<_> = fields.iter().enumerate().map(Field::from).collect();
+        let merge = impl_merge_for_struct(name, &fields, &default_strategy);
+        let visit = if default_strategy.visit {
+            visit::impl_merge_visit(name, &fields, &container)
+        } else {
+            TokenStream::new()
+        };
+        quote! {
+            #merge
+            #visit
+        }
     } else {
         abort_call_site!("conflate::Merge can only be derived for structs")
     }
@@ -60,8 +187,8 @@ fn impl_merge(ast: &syn::DeriveInput) -> TokenStream {
 
 fn impl_merge_for_struct(
     name: &syn::Ident,
-    fields: &syn::Fields,
-    default_strategy: FieldAttrs,
+    fields: &[Field],
+    default_strategy: &FieldAttrs,
 ) -> TokenStream {
     let assignments = gen_assignments(fields, default_strategy);
 
@@ -74,51 +201,491 @@ fn impl_merge_for_struct(
     }
 }
 
-fn gen_assignments(fields: &syn::Fields, default_strategy: FieldAttrs) -> TokenStream {
-    let fields = fields.iter().enumerate().map(Field::from);
+fn gen_assignments(fields: &[Field], default_strategy: &FieldAttrs) -> TokenStream {
+    let aliases = gen_deprecated_aliases(fields, |_, _| None);
+    let conditions = gen_conditions(fields, |f| f.value_of(&quote!(other)));
     let assignments = fields
+        .iter()
         .filter(|f| !f.attrs.skip)
-        .map(|f| gen_assignment(&f, &default_strategy));
+        .map(|f| f.if_condition(gen_assignment(f, default_strategy)));
     quote! {
+        #aliases
+        #conditions
         #( #assignments )*
     }
 }
 
+/// Generates statements that move the values of the fields named by the `deprecated_alias`
+/// attributes into the fields that replace them, for both `self` and `other`.
+///
+/// `warn` is called with the deprecated and the new field and may return statements that are
+/// executed if the deprecated field of `other` was set.
+fn gen_deprecated_aliases(
+    fields: &[Field],
+    warn: impl Fn(&Field, &Field) -> Option<TokenStream>,
+) -> TokenStream {
+    let folds: Vec<_> = fields
+        .iter()
+        .filter_map(|field| {
+            let span = field.attrs.deprecated_alias.as_ref()?.span();
+            let old = field.deprecated_field(fields)?;
+            let name = &field.name;
+            let old_name = &old.name;
+            let fold_other = quote_spanned! {span=>
+                ::conflate::deprecated::fold(&mut other.#name, &mut other.#old_name)
+            };
+            let fold_other = match warn(old, field) {
+                Some(warn) => quote!(if #fold_other { #warn }),
+                None => quote!(#fold_other;),
+            };
+            Some(quote_spanned! {span=>
+                ::conflate::deprecated::fold(&mut self.#name, &mut self.#old_name);
+                #fold_other
+            })
+        })
+        .collect();
+    if folds.is_empty() {
+        TokenStream::new()
+    } else {
+        quote! {
+            let mut other = other;
+            #( #folds )*
+        }
+    }
+}
+
+/// Generates statements that bind clones of the fields of `source` that take part in a
+/// `deprecated_alias` to local variables and move the values of the deprecated fields into the
+/// fields that replace them, for derives that take `source` by reference.
+///
+/// The variables are named using the given prefix, see [`Field::value_of`][].
+fn gen_deprecated_aliases_ref(fields: &[Field], source: &TokenStream, prefix: &str) -> TokenStream {
+    let folds = fields.iter().filter_map(|field| {
+        let span = field.attrs.deprecated_alias.as_ref()?.span();
+        let old = field.deprecated_field(fields)?;
+        let name = &field.name;
+        let old_name = &old.name;
+        let var = field.alias_var(prefix);
+        let old_var = old.alias_var(prefix);
+        Some(quote_spanned! {span=>
+            let mut #var = ::core::clone::Clone::clone(&#source.#name);
+            let mut #old_var = ::core::clone::Clone::clone(&#source.#old_name);
+            ::conflate::deprecated::fold(&mut #var, &mut #old_var);
+        })
+    });
+    quote! {
+        #( #folds )*
+    }
+}
+
+/// Generates statements that evaluate the `skip_if` and `only_if` predicates of all fields for
+/// the fields of `other` before any field is merged, see [`Field::if_condition`][].
+///
+/// `value` returns an expression for the value of the given field of `other`.
+fn gen_conditions(fields: &[Field], value: impl Fn(&Field) -> TokenStream) -> TokenStream {
+    let conditions = fields.iter().filter(|f| !f.attrs.skip).filter_map(|f| {
+        let var = f.condition_var();
+        let value = value(f);
+        f.condition(&quote!(&#value))
+            .map(|condition| quote!(let #var = #condition;))
+    });
+    quote! {
+        #( #conditions )*
+    }
+}
+
 fn gen_assignment(field: &Field, default_strategy: &FieldAttrs) -> TokenStream {
     use syn::spanned::Spanned;
 
     let name = &field.name;
-    if let Some(strategy) = &field.attrs.strategy {
-        quote_spanned!(strategy.span()=> #strategy(&mut self.#name, other.#name);)
-    } else if let Some(default) = &default_strategy.strategy {
-        quote_spanned!(default.span()=> #default(&mut self.#name, other.#name);)
+    if let Some(strategy) = field.strategy(default_strategy) {
+        let merge = field.call_strategy(&strategy, quote!(&mut self.#name), quote!(other.#name));
+        quote_spanned!(strategy.span()=> #merge;)
     } else {
         quote_spanned!(field.span=> ::conflate::Merge::merge(&mut self.#name, other.#name);)
     }
 }
 
+impl Field {
+    /// Returns the strategy for this field, falling back to the default strategy for its type and
+    /// to the default strategy of the struct.
+    ///
+    /// `None` means that the field is merged by calling `Merge::merge`.
+    fn strategy<'a>(&'a self, default_strategy: &'a FieldAttrs) -> Option<Cow<'a, syn::Expr>> {
+        if let Some(strategy) = &self.attrs.strategy {
+            Some(Cow::Borrowed(strategy))
+        } else if let Some(path) = default_strategy.type_strategy(self.value_ty(), "") {
+            Some(Cow::Owned(syn::Expr::Path(syn::ExprPath {
+                attrs: Vec::new(),
+                qself: None,
+                path,
+            })))
+        } else {
+            default_strategy.strategy.as_ref().map(Cow::Borrowed)
+        }
+    }
+
+    /// Returns the strategy for merging this field from a reference, falling back to the default
+    /// strategy for its type and to the default strategy of the struct.
+    ///
+    /// A `strategy` set for the field takes precedence over the default `strategy_ref` of the
+    /// struct.  `None` means that the field is merged by cloning it and applying [`strategy`][]
+    /// or, if that is not set either, by calling `MergeRef::merge_ref`.
+    ///
+    /// [`strategy`]: Self::strategy
+    fn strategy_ref<'a>(&'a self, default_strategy: &'a FieldAttrs) -> Option<Cow<'a, syn::Path>> {
+        self.variant(default_strategy, "_ref", |attrs| &attrs.strategy_ref)
+    }
+
+    /// Returns the change-reporting strategy for this field, falling back to the default strategy
+    /// for its type and to the default strategy of the struct.
+    ///
+    /// A `strategy` set for the field takes precedence over the default `strategy_changed` of the
+    /// struct.  `None` means that the field is merged using [`strategy`][] and compared to its
+    /// previous value or, if that is not set either, by calling `MergeChanged::merge_changed_with`.
+    ///
+    /// [`strategy`]: Self::strategy
+    fn strategy_changed<'a>(
+        &'a self,
+        default_strategy: &'a FieldAttrs,
+    ) -> Option<Cow<'a, syn::Path>> {
+        self.variant(default_strategy, "_changed", |attrs| {
+            &attrs.strategy_changed
+        })
+    }
+
+    /// Returns the variant of the strategy for this field selected by `get`, falling back to the
+    /// variant with the given suffix of the default strategy for its type and to the default
+    /// variant of the struct.
+    fn variant<'a>(
+        &'a self,
+        default_strategy: &'a FieldAttrs,
+        suffix: &str,
+        get: impl Fn(&FieldAttrs) -> &Option<syn::Path>,
+    ) -> Option<Cow<'a, syn::Path>> {
+        if self.attrs.lockable {
+            // the variants cannot be applied to the inner value, so lockable fields are always
+            // cloned and merged using the strategy
+            None
+        } else if get(&self.attrs).is_some() || self.attrs.strategy.is_some() {
+            get(&self.attrs).as_ref().map(Cow::Borrowed)
+        } else if default_strategy
+            .type_strategy(self.value_ty(), "")
+            .is_some()
+        {
+            default_strategy
+                .type_strategy(self.value_ty(), suffix)
+                .map(Cow::Owned)
+        } else {
+            get(default_strategy).as_ref().map(Cow::Borrowed)
+        }
+    }
+
+    /// Returns the type of the merged value of this field, i.e. the type of the inner value for
+    /// fields with the `lockable` attribute.
+    fn value_ty(&self) -> &syn::Type {
+        if self.attrs.lockable {
+            if let Some(ty) = inner_type(&self.ty) {
+                return ty;
+            }
+        }
+        &self.ty
+    }
+
+    /// Returns a call of the given strategy that merges `right` into `left`.
+    ///
+    /// The strategy is either a path or an expression that evaluates to a strategy, e.g. a call
+    /// of a function that returns a parameterized strategy.  For fields with the `lockable`
+    /// attribute, the strategy is applied to the inner values using `conflate::locked::merge_with`.
+    fn call_strategy(
+        &self,
+        strategy: &syn::Expr,
+        left: TokenStream,
+        right: TokenStream,
+    ) -> TokenStream {
+        use syn::spanned::Spanned;
+
+        if self.attrs.lockable {
+            quote_spanned!(strategy.span()=> ::conflate::locked::merge_with(#left, #right, #strategy))
+        } else if let syn::Expr::Path(_) | syn::Expr::Call(_) = strategy {
+            quote_spanned!(strategy.span()=> #strategy(#left, #right))
+        } else {
+            quote_spanned!(strategy.span()=> (#strategy)(#left, #right))
+        }
+    }
+
+    /// Returns an expression that evaluates the `only_if` and `skip_if` predicates of this field
+    /// for `self` and the given value of the field, or `None` if the field has no predicates.
+    fn condition(&self, value: &TokenStream) -> Option<TokenStream> {
+        use syn::spanned::Spanned;
+
+        let only_if = self
+            .attrs
+            .only_if
+            .as_ref()
+            .map(|predicate| quote_spanned!(predicate.span()=> #predicate(&*self, #value)));
+        let skip_if = self
+            .attrs
+            .skip_if
+            .as_ref()
+            .map(|predicate| quote_spanned!(predicate.span()=> !#predicate(&*self, #value)));
+        match (only_if, skip_if) {
+            (Some(only_if), Some(skip_if)) => Some(quote!(#only_if && #skip_if)),
+            (only_if, skip_if) => only_if.or(skip_if),
+        }
+    }
+
+    /// Returns the name of the variable that stores the result of [`condition`][].
+    ///
+    /// [`condition`]: Self::condition
+    fn condition_var(&self) -> syn::Ident {
+        quote::format_ident!("merge_{}", self.ident())
+    }
+
+    /// Returns the deprecated field that is replaced by this field, see the `deprecated_alias`
+    /// attribute.
+    fn deprecated_field<'a>(&self, fields: &'a [Field]) -> Option<&'a Field> {
+        let alias = self.attrs.deprecated_alias.as_ref()?;
+        let field = fields
+            .iter()
+            .find(|f| matches!(&f.name, syn::Member::Named(name) if name == alias))
+            .unwrap_or_else(|| abort!(alias, "Unknown field: {}", alias));
+        Some(field)
+    }
+
+    /// Returns whether this field replaces a deprecated field or is replaced by another field.
+    fn is_aliased(&self, fields: &[Field]) -> bool {
+        self.deprecated_field(fields).is_some()
+            || fields
+                .iter()
+                .any(|f| f.deprecated_field(fields).map(|old| &old.name) == Some(&self.name))
+    }
+
+    /// Returns the name of the variable that stores the value of this field after folding the
+    /// deprecated aliases, see [`gen_deprecated_aliases_ref`][].
+    fn alias_var(&self, prefix: &str) -> syn::Ident {
+        quote::format_ident!("{}_{}", prefix, self.ident())
+    }
+
+    /// Returns an expression for the value of this field of `source`.
+    fn value_of(&self, source: &TokenStream) -> TokenStream {
+        let name = &self.name;
+        quote!(#source.#name)
+    }
+
+    /// Returns an expression for the value of this field of `source` after folding the deprecated
+    /// aliases with [`gen_deprecated_aliases_ref`][] using the given prefix.
+    fn folded_value_of(&self, fields: &[Field], source: &TokenStream, prefix: &str) -> TokenStream {
+        if self.is_aliased(fields) {
+            let var = self.alias_var(prefix);
+            quote!(#var)
+        } else {
+            self.value_of(source)
+        }
+    }
+
+    /// Wraps the given statements so that they are only executed if the predicates of this field,
+    /// as evaluated by [`gen_conditions`][], allow merging it.
+    fn if_condition(&self, tokens: TokenStream) -> TokenStream {
+        if self.condition(&TokenStream::new()).is_some() {
+            let var = self.condition_var();
+            quote!(if #var { #tokens })
+        } else {
+            tokens
+        }
+    }
+
+    /// Returns the identifier of this field, or its index for tuple structs.
+    fn ident(&self) -> String {
+        match &self.name {
+            syn::Member::Named(ident) => ident.unraw().to_string(),
+            syn::Member::Unnamed(index) => index.index.to_string(),
+        }
+    }
+
+    /// Returns the key of this field in serialized documents and field paths, taking the `serde`
+    /// attributes into account.
+    ///
+    /// `None` means that the field is flattened into its parent.
+    fn key(&self, container: &SerdeContainerAttrs) -> Option<String> {
+        if self.serde.flatten {
+            None
+        } else if let syn::Member::Named(ident) = &self.name {
+            Some(self.serde.key(ident, container))
+        } else {
+            Some(self.ident())
+        }
+    }
+
+    /// Returns the key of this field in serialized documents, or `None` if the field is
+    /// flattened or skipped by `serde`.
+    ///
+    /// This is the key of the `FIELD_NAMES` table of `DeserializeMerge` and the `FIELDS` table
+    /// of `MergeMeta`.
+    fn serde_key(&self, container: &SerdeContainerAttrs) -> Option<String> {
+        if self.serde.skip {
+            None
+        } else {
+            self.key(container)
+        }
+    }
+}
+
 impl From<(usize, &syn::Field)> for Field {
     fn from(data: (usize, &syn::Field)) -> Self {
         use syn::spanned::Spanned;
 
         let (index, field) = data;
+        let attrs = FieldAttrs::from(field.attrs.iter());
+        if let Some((ty, _)) = attrs.type_strategies.first() {
+            abort!(
+                ty,
+                "Strategies for types can only be set on the struct, use `strategy = ...` for fields"
+            );
+        }
         Field {
             name: if let Some(ident) = &field.ident {
                 syn::Member::Named(ident.clone())
             } else {
                 syn::Member::Unnamed(index.into())
             },
+            ty: field.ty.clone(),
             span: field.span(),
-            attrs: field.attrs.iter().into(),
+            doc: doc_string(&field.attrs),
+            attrs,
+            serde: field.attrs.iter().into(),
         }
     }
 }
 
+/// Returns the name of the given type without its path and generic arguments, e.g. `Option` for
+/// `std::option::Option<String>`.
+fn type_name(ty: &syn::Type) -> Option<String> {
+    match ty {
+        syn::Type::Path(syn::TypePath { qself: None, path }) => path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string()),
+        _ => None,
+    }
+}
+
+/// Returns the generic type argument of the given type, e.g. `String` for `Locked<String>`.
+fn inner_type(ty: &syn::Type) -> Option<&syn::Type> {
+    let segment = match ty {
+        syn::Type::Path(syn::TypePath { qself: None, path }) => path.segments.last()?,
+        _ => return None,
+    };
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(arguments) => {
+            arguments.args.iter().find_map(|argument| match argument {
+                syn::GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Collects the documentation comments of an item into a single string.
+fn doc_string(attrs: &[syn::Attribute]) -> String {
+    let lines: Vec<_> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(lit),
+                        ..
+                    }),
+                ..
+            }) => Some(lit.value()),
+            _ => None,
+        })
+        .collect();
+    lines
+        .iter()
+        .map(|line| line.strip_prefix(' ').unwrap_or(line).trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_owned()
+}
+
 impl FieldAttrs {
+    /// Returns the variant with the given suffix of the default strategy for fields of the given
+    /// type, as determined by the type-keyed `strategy`, the `precedence` and the `auto`
+    /// attributes of the struct.
+    ///
+    /// `None` means that there is no default strategy for the type or that this variant does not
+    /// exist.
+    fn type_strategy(&self, ty: &syn::Type, suffix: &str) -> Option<syn::Path> {
+        let type_name = type_name(ty)?;
+        if let Some((_, path)) = self.type_strategies.iter().find(|(ty, _)| *ty == type_name) {
+            // there are no variants of custom strategies, so these fields are cloned and merged
+            // using the strategy
+            return if suffix.is_empty() {
+                Some(path.clone())
+            } else {
+                None
+            };
+        }
+        if self.precedence.is_none() && !self.auto {
+            return None;
+        }
+        let right = matches!(self.precedence, Some(Precedence::Right));
+        let (module, strategy) = match type_name.as_str() {
+            "Option" if right => ("option", "overwrite_with_some"),
+            "Option" => ("option", "overwrite_none"),
+            "HashMap" if right => ("hashmap", "append_or_overwrite"),
+            "HashMap" => ("hashmap", "append_or_ignore"),
+            "BTreeMap" if right => ("btreemap", "append_or_overwrite"),
+            "BTreeMap" => ("btreemap", "append_or_ignore"),
+            "IndexMap" if right => ("indexmap", "append_or_overwrite"),
+            "IndexMap" => ("indexmap", "append_or_ignore"),
+            "bool" => ("bool", "overwrite_false"),
+            "TriState" if right => ("bool", "overwrite_with_set"),
+            "TriState" => ("bool", "overwrite_unset"),
+            "Vec" if self.auto => ("vec", "append"),
+            _ => return None,
+        };
+        let name = match (module, suffix) {
+            ("option", "_ref") => format!("{strategy}_ref"),
+            ("bool", "_ref") if type_name == "TriState" => format!("{strategy}_ref"),
+            ("vec", "_ref") => format!("{strategy}_cloned"),
+            (_, "_ref") => return None,
+            (_, suffix) => format!("{strategy}{suffix}"),
+        };
+        let module = syn::Ident::new(module, proc_macro2::Span::call_site());
+        let name = syn::Ident::new(&name, proc_macro2::Span::call_site());
+        Some(syn::parse_quote!(::conflate::#module::#name))
+    }
+
     fn apply(&mut self, attr: FieldAttr) {
         match attr {
             FieldAttr::Skip => self.skip = true,
+            FieldAttr::SkipInterpolate => self.skip_interpolate = true,
             FieldAttr::Strategy(path) => self.strategy = Some(path),
+            FieldAttr::TypeStrategies(strategies) => {
+                for (ty, path) in strategies {
+                    if self.type_strategies.iter().any(|(other, _)| *other == ty) {
+                        abort!(ty, "Duplicate strategy for type: {}", ty);
+                    }
+                    self.type_strategies.push((ty, path));
+                }
+            }
+            FieldAttr::StrategyRef(path) => self.strategy_ref = Some(path),
+            FieldAttr::StrategyChanged(path) => self.strategy_changed = Some(path),
+            FieldAttr::StrategyTry(path) => self.strategy_try = Some(path),
+            FieldAttr::SkipIf(path) => self.skip_if = Some(path),
+            FieldAttr::OnlyIf(path) => self.only_if = Some(path),
+            FieldAttr::Lockable => self.lockable = true,
+            FieldAttr::MinTrust(trust) => self.min_trust = Some(trust),
+            FieldAttr::DeprecatedAlias(ident) => self.deprecated_alias = Some(ident),
+            FieldAttr::Visit => self.visit = true,
+            FieldAttr::Auto => self.auto = true,
+            FieldAttr::Precedence(precedence) => self.precedence = Some(precedence),
         }
     }
 }
@@ -142,16 +709,83 @@ impl<'a, I: Iterator<Item = &'a syn::Attribute>> From<I> for FieldAttrs {
     }
 }
 
+/// Parses a `Type = path` entry of a type-keyed `strategy(...)` attribute.
+fn parse_type_strategy(
+    input: syn::parse::ParseStream,
+) -> syn::parse::Result<(syn::Ident, syn::Path)> {
+    let ty = input.call(syn::Ident::parse_any)?;
+    let _: Token![=] = input.parse()?;
+    let path: syn::Path = input.parse()?;
+    Ok((ty, path))
+}
+
 impl syn::parse::Parse for FieldAttr {
     fn parse(input: syn::parse::ParseStream) -> syn::parse::Result<Self> {
         let name: syn::Ident = input.parse()?;
         if name == "skip" {
             // TODO check remaining stream
             Ok(FieldAttr::Skip)
+        } else if name == "skip_interpolate" {
+            Ok(FieldAttr::SkipInterpolate)
         } else if name == "strategy" {
+            if input.peek(syn::token::Paren) {
+                let content;
+                syn::parenthesized!(content in input);
+                let strategies = content.parse_terminated(parse_type_strategy, Token![,])?;
+                Ok(FieldAttr::TypeStrategies(strategies.into_iter().collect()))
+            } else {
+                let _: Token![=] = input.parse()?;
+                let strategy: syn::Expr = input.parse()?;
+                Ok(FieldAttr::Strategy(strategy))
+            }
+        } else if name == "strategy_ref" {
+            let _: Token![=] = input.parse()?;
+            let path: syn::Path = input.parse()?;
+            Ok(FieldAttr::StrategyRef(path))
+        } else if name == "strategy_changed" {
+            let _: Token![=] = input.parse()?;
+            let path: syn::Path = input.parse()?;
+            Ok(FieldAttr::StrategyChanged(path))
+        } else if name == "strategy_try" {
+            let _: Token![=] = input.parse()?;
+            let path: syn::Path = input.parse()?;
+            Ok(FieldAttr::StrategyTry(path))
+        } else if name == "skip_if" {
+            let _: Token![=] = input.parse()?;
+            let path: syn::Path = input.parse()?;
+            Ok(FieldAttr::SkipIf(path))
+        } else if name == "only_if" {
             let _: Token![=] = input.parse()?;
             let path: syn::Path = input.parse()?;
-            Ok(FieldAttr::Strategy(path))
+            Ok(FieldAttr::OnlyIf(path))
+        } else if name == "visit" {
+            Ok(FieldAttr::Visit)
+        } else if name == "lockable" {
+            Ok(FieldAttr::Lockable)
+        } else if name == "auto" {
+            Ok(FieldAttr::Auto)
+        } else if name == "precedence" {
+            let _: Token![=] = input.parse()?;
+            let value: syn::LitStr = input.parse()?;
+            match value.value().as_str() {
+                "left" => Ok(FieldAttr::Precedence(Precedence::Left)),
+                "right" => Ok(FieldAttr::Precedence(Precedence::Right)),
+                _ => abort!(value, "Expected \"left\" or \"right\""),
+            }
+        } else if name == "deprecated_alias" {
+            let _: Token![=] = input.parse()?;
+            let ident = input.call(syn::Ident::parse_any)?;
+            Ok(FieldAttr::DeprecatedAlias(ident))
+        } else if name == "min_trust" {
+            let _: Token![=] = input.parse()?;
+            let value: syn::LitStr = input.parse()?;
+            let variant = match value.value().as_str() {
+                "local" => "Local",
+                "user" => "User",
+                "system" => "System",
+                _ => abort!(value, "Expected \"local\", \"user\" or \"system\""),
+            };
+            Ok(FieldAttr::MinTrust(syn::Ident::new(variant, value.span())))
         } else {
             abort!(name, "Unexpected attribute: {}", name)
         }
@@ -0,0 +1,75 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Implementation of the `MergeRef` derive macro.
+
+use proc_macro2::TokenStream;
+use proc_macro_error2::{abort_call_site, dummy::set_dummy};
+use quote::{quote, quote_spanned};
+
+use crate::{gen_conditions, gen_deprecated_aliases_ref, Field, FieldAttrs};
+
+pub fn impl_merge_ref(ast: &syn::DeriveInput) -> TokenStream {
+    let name = &ast.ident;
+    let default_strategy = FieldAttrs::from(ast.attrs.iter());
+
+    set_dummy(quote! {
+        impl ::conflate::MergeRef for #name {
+            fn merge_ref(&mut self, other: &Self) {
+                unimplemented!()
+            }
+        }
+    });
+
+    if let syn::Data::Struct(syn::DataStruct { ref fields, .. }) = ast.data {
+        let fields: Vec<_> = fields.iter().enumerate().map(Field::from).collect();
+        let folds = fields.iter().filter_map(|f| {
+            let old = f.deprecated_field(&fields)?;
+            let name = &f.name;
+            let old_name = &old.name;
+            Some(quote!(::conflate::deprecated::fold(&mut self.#name, &mut self.#old_name);))
+        });
+        let aliases = gen_deprecated_aliases_ref(&fields, &quote!(other), "other");
+        let value = |f: &Field| f.folded_value_of(&fields, &quote!(other), "other");
+        let conditions = gen_conditions(&fields, value);
+        let assignments = fields
+            .iter()
+            .filter(|f| !f.attrs.skip)
+            .map(|f| f.if_condition(gen_assignment(f, &default_strategy, &value(f))));
+
+        quote! {
+            impl ::conflate::MergeRef for #name {
+                fn merge_ref(&mut self, other: &Self) {
+                    #( #folds )*
+                    #aliases
+                    #conditions
+                    #( #assignments )*
+                }
+            }
+        }
+    } else {
+        abort_call_site!("conflate::MergeRef can only be derived for structs")
+    }
+}
+
+fn gen_assignment(
+    field: &Field,
+    default_strategy: &FieldAttrs,
+    other: &TokenStream,
+) -> TokenStream {
+    use syn::spanned::Spanned;
+
+    let name = &field.name;
+    if let Some(strategy) = field.strategy_ref(default_strategy) {
+        quote_spanned!(strategy.span()=> #strategy(&mut self.#name, &#other);)
+    } else if let Some(strategy) = field.strategy(default_strategy) {
+        let merge = field.call_strategy(
+            &strategy,
+            quote!(&mut self.#name),
+            quote!(::core::clone::Clone::clone(&#other)),
+        );
+        quote_spanned!(strategy.span()=> #merge;)
+    } else {
+        quote_spanned!(field.span=> ::conflate::MergeRef::merge_ref(&mut self.#name, &#other);)
+    }
+}
@@ -0,0 +1,138 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Implementation of the `MergeMeta` derive macro.
+
+use proc_macro2::{Spacing, TokenStream, TokenTree};
+use proc_macro_error2::{abort_call_site, dummy::set_dummy};
+use quote::{quote, ToTokens};
+
+use crate::serde_attr::SerdeContainerAttrs;
+use crate::{Field, FieldAttrs};
+
+pub fn impl_merge_meta(ast: &syn::DeriveInput) -> TokenStream {
+    let name = &ast.ident;
+    let default_strategy = FieldAttrs::from(ast.attrs.iter());
+    let container = SerdeContainerAttrs::from(ast.attrs.iter());
+
+    set_dummy(quote! {
+        impl ::conflate::MergeMeta for #name {}
+    });
+
+    if let syn::Data::Struct(syn::DataStruct { ref fields, .. }) = ast.data {
+        let fields = fields
+            .iter()
+            .enumerate()
+            .map(Field::from)
+            .map(|f| gen_field_meta(&f, &default_strategy, &container));
+
+        quote! {
+            impl ::conflate::MergeMeta for #name {
+                const FIELDS: &'static [::conflate::FieldMeta] = &[ #( #fields ),* ];
+            }
+        }
+    } else {
+        abort_call_site!("conflate::MergeMeta can only be derived for structs")
+    }
+}
+
+fn gen_field_meta(
+    field: &Field,
+    default_strategy: &FieldAttrs,
+    container: &SerdeContainerAttrs,
+) -> TokenStream {
+    let name = field.ident();
+    let key = match field.serde_key(container) {
+        Some(key) => quote!(::core::option::Option::Some(#key)),
+        None => quote!(::core::option::Option::None),
+    };
+    let ty = &field.ty;
+    let ty_name = tokens_to_string(ty);
+    let skip = field.attrs.skip;
+    let doc = &field.doc;
+    let (strategy, nested) = match field.strategy(default_strategy) {
+        _ if skip => (
+            quote!(::core::option::Option::None),
+            quote!(::core::option::Option::None),
+        ),
+        Some(strategy) => {
+            let strategy = tokens_to_string(&strategy);
+            (
+                quote!(::core::option::Option::Some(#strategy)),
+                quote!(::core::option::Option::None),
+            )
+        }
+        None => (
+            quote!(::core::option::Option::None),
+            quote!(::core::option::Option::Some(|| <#ty as ::conflate::MergeMeta>::FIELDS)),
+        ),
+    };
+
+    quote! {
+        ::conflate::FieldMeta {
+            name: #name,
+            key: #key,
+            ty: #ty_name,
+            strategy: #strategy,
+            skip: #skip,
+            doc: #doc,
+            nested: #nested,
+        }
+    }
+}
+
+/// Render the tokens of a type or path the way they would be written in source code.
+///
+/// `ToString` for token streams separates all tokens by spaces, e.g. `Option < u8 >`.
+fn tokens_to_string<T: ToTokens>(tokens: &T) -> String {
+    let mut s = String::new();
+    write_tokens(&mut s, tokens.to_token_stream());
+    s
+}
+
+fn write_tokens(s: &mut String, tokens: TokenStream) {
+    let mut needs_space = false;
+    for token in tokens {
+        match token {
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    proc_macro2::Delimiter::Parenthesis => ("(", ")"),
+                    proc_macro2::Delimiter::Brace => ("{", "}"),
+                    proc_macro2::Delimiter::Bracket => ("[", "]"),
+                    proc_macro2::Delimiter::None => ("", ""),
+                };
+                s.push_str(open);
+                write_tokens(s, group.stream());
+                s.push_str(close);
+                needs_space = false;
+            }
+            TokenTree::Ident(ident) => {
+                if needs_space {
+                    s.push(' ');
+                }
+                s.push_str(&ident.to_string());
+                needs_space = true;
+            }
+            TokenTree::Literal(literal) => {
+                if needs_space {
+                    s.push(' ');
+                }
+                s.push_str(&literal.to_string());
+                needs_space = true;
+            }
+            TokenTree::Punct(punct) => {
+                let ch = punct.as_char();
+                match ch {
+                    ',' | ';' => s.push(ch),
+                    '+' | '=' => {
+                        s.push(' ');
+                        s.push(ch);
+                    }
+                    '-' if punct.spacing() == Spacing::Joint => s.push_str(" -"),
+                    _ => s.push(ch),
+                }
+                needs_space = matches!(ch, ',' | ';' | '+' | '=') || s.ends_with(" ->");
+            }
+        }
+    }
+}
@@ -0,0 +1,150 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Implementation of the `MergePreview` derive macro.
+
+use proc_macro2::TokenStream;
+use proc_macro_error2::{abort, abort_call_site, dummy::set_dummy};
+use quote::{quote, quote_spanned};
+
+use crate::serde_attr::SerdeContainerAttrs;
+use crate::{gen_conditions, gen_deprecated_aliases_ref, Field, FieldAttrs};
+
+pub fn impl_merge_preview(ast: &syn::DeriveInput) -> TokenStream {
+    let name = &ast.ident;
+    let default_strategy = FieldAttrs::from(ast.attrs.iter());
+    let container = SerdeContainerAttrs::from(ast.attrs.iter());
+
+    set_dummy(quote! {
+        impl ::conflate::preview::MergePreview for #name {
+            fn merge_preview(
+                &self,
+                other: &Self,
+            ) -> ::std::vec::Vec<::conflate::preview::Change> {
+                unimplemented!()
+            }
+
+            fn diff(&self, other: &Self) -> ::std::vec::Vec<::conflate::preview::Change> {
+                unimplemented!()
+            }
+        }
+    });
+
+    if let syn::Data::Struct(syn::DataStruct { ref fields, .. }) = ast.data {
+        let fields: Vec<_> = fields.iter().enumerate().map(Field::from).collect();
+        let self_aliases = gen_deprecated_aliases_ref(&fields, &quote!(self), "self");
+        let other_aliases = gen_deprecated_aliases_ref(&fields, &quote!(other), "other");
+        let conditions = gen_conditions(&fields, |f| {
+            f.folded_value_of(&fields, &quote!(other), "other")
+        });
+        let previews = fields.iter().filter(|f| !f.attrs.skip).map(|f| {
+            if f.is_aliased(&fields) && f.strategy(&default_strategy).is_none() {
+                abort!(
+                    f.span,
+                    "Fields with a deprecated_alias must have a merge strategy to derive MergePreview"
+                );
+            }
+            f.if_condition(gen_preview(
+                f,
+                &default_strategy,
+                &container,
+                &f.folded_value_of(&fields, &quote!(self), "self"),
+                &f.folded_value_of(&fields, &quote!(other), "other"),
+            ))
+        });
+        let diffs = fields
+            .iter()
+            .filter(|f| !f.attrs.skip)
+            .map(|f| gen_diff(f, &default_strategy, &container));
+
+        quote! {
+            impl ::conflate::preview::MergePreview for #name {
+                fn merge_preview(
+                    &self,
+                    other: &Self,
+                ) -> ::std::vec::Vec<::conflate::preview::Change> {
+                    #self_aliases
+                    #other_aliases
+                    #conditions
+                    let mut changes = ::std::vec::Vec::new();
+                    #( #previews )*
+                    changes
+                }
+
+                fn diff(&self, other: &Self) -> ::std::vec::Vec<::conflate::preview::Change> {
+                    let mut changes = ::std::vec::Vec::new();
+                    #( #diffs )*
+                    changes
+                }
+            }
+        }
+    } else {
+        abort_call_site!("conflate::MergePreview can only be derived for structs")
+    }
+}
+
+fn gen_preview(
+    field: &Field,
+    default_strategy: &FieldAttrs,
+    container: &SerdeContainerAttrs,
+    base: &TokenStream,
+    other: &TokenStream,
+) -> TokenStream {
+    use syn::spanned::Spanned;
+
+    let name = &field.name;
+    let key = field.key(container).unwrap_or_default();
+    let merge = if let Some(strategy) = field.strategy_ref(default_strategy) {
+        quote_spanned!(strategy.span()=> #strategy(&mut new, &#other);)
+    } else if let Some(strategy) = field.strategy(default_strategy) {
+        let merge = field.call_strategy(
+            &strategy,
+            quote!(&mut new),
+            quote!(::core::clone::Clone::clone(&#other)),
+        );
+        quote_spanned!(strategy.span()=> #merge;)
+    } else {
+        return quote_spanned! {field.span=>
+            changes.extend(
+                ::conflate::preview::MergePreview::merge_preview(&#base, &#other)
+                    .into_iter()
+                    .map(|change| change.with_parent(#key)),
+            );
+        };
+    };
+
+    quote_spanned! {field.span=>
+        {
+            let mut new = ::core::clone::Clone::clone(&#base);
+            #merge
+            if new != self.#name {
+                changes.push(::conflate::preview::Change::new(#key, &self.#name, &new));
+            }
+        }
+    }
+}
+
+fn gen_diff(
+    field: &Field,
+    default_strategy: &FieldAttrs,
+    container: &SerdeContainerAttrs,
+) -> TokenStream {
+    let name = &field.name;
+    let key = field.key(container).unwrap_or_default();
+    if field.strategy_ref(default_strategy).is_some() || field.strategy(default_strategy).is_some()
+    {
+        quote_spanned! {field.span=>
+            if self.#name != other.#name {
+                changes.push(::conflate::preview::Change::new(#key, &self.#name, &other.#name));
+            }
+        }
+    } else {
+        quote_spanned! {field.span=>
+            changes.extend(
+                ::conflate::preview::MergePreview::diff(&self.#name, &other.#name)
+                    .into_iter()
+                    .map(|change| change.with_parent(#key)),
+            );
+        }
+    }
+}
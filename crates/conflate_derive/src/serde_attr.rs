@@ -0,0 +1,184 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Parsing of the `serde` attributes that change the external name of a field.
+//!
+//! Only the attributes that affect the keys used in serialized documents are interpreted, all
+//! other `serde` attributes are ignored.
+
+use proc_macro_error2::{abort, ResultExt};
+use syn::ext::IdentExt;
+use syn::Token;
+
+/// The `serde` attributes of a struct.
+#[derive(Default)]
+pub struct SerdeContainerAttrs {
+    pub rename_all: Option<RenameRule>,
+    pub deny_unknown_fields: bool,
+}
+
+/// The `serde` attributes of a field.
+#[derive(Default)]
+pub struct SerdeFieldAttrs {
+    pub rename: Option<String>,
+    pub aliases: Vec<String>,
+    pub skip: bool,
+    pub flatten: bool,
+}
+
+/// The case conversions supported by `#[serde(rename_all = "...")]`.
+#[derive(Clone, Copy)]
+pub enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    fn from_lit(lit: &syn::LitStr) -> Self {
+        match lit.value().as_str() {
+            "lowercase" => Self::Lower,
+            "UPPERCASE" => Self::Upper,
+            "PascalCase" => Self::Pascal,
+            "camelCase" => Self::Camel,
+            "snake_case" => Self::Snake,
+            "SCREAMING_SNAKE_CASE" => Self::ScreamingSnake,
+            "kebab-case" => Self::Kebab,
+            "SCREAMING-KEBAB-CASE" => Self::ScreamingKebab,
+            other => abort!(lit, "Unknown rename rule: {}", other),
+        }
+    }
+
+    /// Apply the rule to a field name, following the conversion done by `serde_derive`.
+    pub fn apply_to_field(self, field: &str) -> String {
+        match self {
+            Self::Lower | Self::Snake => field.to_owned(),
+            Self::Upper | Self::ScreamingSnake => field.to_ascii_uppercase(),
+            Self::Pascal => {
+                let mut pascal = String::new();
+                let mut capitalize = true;
+                for ch in field.chars() {
+                    if ch == '_' {
+                        capitalize = true;
+                    } else if capitalize {
+                        pascal.push(ch.to_ascii_uppercase());
+                        capitalize = false;
+                    } else {
+                        pascal.push(ch);
+                    }
+                }
+                pascal
+            }
+            Self::Camel => {
+                let pascal = Self::Pascal.apply_to_field(field);
+                let mut chars = pascal.chars();
+                chars.next().map_or_else(String::new, |first| {
+                    first.to_ascii_lowercase().to_string() + chars.as_str()
+                })
+            }
+            Self::Kebab => field.replace('_', "-"),
+            Self::ScreamingKebab => Self::ScreamingSnake.apply_to_field(field).replace('_', "-"),
+        }
+    }
+}
+
+impl<'a, I: Iterator<Item = &'a syn::Attribute>> From<I> for SerdeContainerAttrs {
+    fn from(iter: I) -> Self {
+        let mut attrs = Self::default();
+
+        for attr in iter.filter(|attr| attr.path().is_ident("serde")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename_all") {
+                    if let Some(lit) = parse_deserialize_name(&meta)? {
+                        attrs.rename_all = Some(RenameRule::from_lit(&lit));
+                    }
+                } else if meta.path.is_ident("deny_unknown_fields") {
+                    attrs.deny_unknown_fields = true;
+                } else {
+                    skip_meta(&meta)?;
+                }
+                Ok(())
+            })
+            .unwrap_or_abort();
+        }
+
+        attrs
+    }
+}
+
+impl<'a, I: Iterator<Item = &'a syn::Attribute>> From<I> for SerdeFieldAttrs {
+    fn from(iter: I) -> Self {
+        let mut attrs = Self::default();
+
+        for attr in iter.filter(|attr| attr.path().is_ident("serde")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    if let Some(lit) = parse_deserialize_name(&meta)? {
+                        attrs.rename = Some(lit.value());
+                    }
+                } else if meta.path.is_ident("alias") {
+                    let lit: syn::LitStr = meta.value()?.parse()?;
+                    attrs.aliases.push(lit.value());
+                } else if meta.path.is_ident("skip") || meta.path.is_ident("skip_deserializing") {
+                    attrs.skip = true;
+                } else if meta.path.is_ident("flatten") {
+                    attrs.flatten = true;
+                } else {
+                    skip_meta(&meta)?;
+                }
+                Ok(())
+            })
+            .unwrap_or_abort();
+        }
+
+        attrs
+    }
+}
+
+impl SerdeFieldAttrs {
+    /// Returns the key of the field with the given identifier in serialized documents.
+    pub fn key(&self, ident: &syn::Ident, container: &SerdeContainerAttrs) -> String {
+        let ident = ident.unraw().to_string();
+        match (&self.rename, container.rename_all) {
+            (Some(rename), _) => rename.clone(),
+            (None, Some(rule)) => rule.apply_to_field(&ident),
+            (None, None) => ident,
+        }
+    }
+}
+
+/// Parse `name = "..."` or `name(deserialize = "...")`, returning the name used for
+/// deserialization, if set.
+fn parse_deserialize_name(
+    meta: &syn::meta::ParseNestedMeta<'_>,
+) -> syn::Result<Option<syn::LitStr>> {
+    if meta.input.peek(Token![=]) {
+        return meta.value()?.parse().map(Some);
+    }
+
+    let mut name = None;
+    meta.parse_nested_meta(|meta| {
+        if meta.path.is_ident("deserialize") {
+            name = Some(meta.value()?.parse()?);
+        } else {
+            skip_meta(&meta)?;
+        }
+        Ok(())
+    })?;
+    Ok(name)
+}
+
+/// Consume a `serde` attribute that is not interpreted by this crate.
+fn skip_meta(meta: &syn::meta::ParseNestedMeta<'_>) -> syn::Result<()> {
+    if meta.input.peek(Token![=]) {
+        let _: syn::Expr = meta.value()?.parse()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        let _: proc_macro2::Group = meta.input.parse()?;
+    }
+    Ok(())
+}
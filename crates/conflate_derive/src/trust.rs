@@ -0,0 +1,109 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Implementation of the `MergeTrusted` derive macro.
+
+use proc_macro2::TokenStream;
+use proc_macro_error2::{abort_call_site, dummy::set_dummy};
+use quote::{quote, quote_spanned};
+
+use crate::serde_attr::SerdeContainerAttrs;
+use crate::{gen_conditions, gen_deprecated_aliases, Field, FieldAttrs};
+
+pub fn impl_merge_trusted(ast: &syn::DeriveInput) -> TokenStream {
+    let name = &ast.ident;
+    let default_strategy = FieldAttrs::from(ast.attrs.iter());
+    let container = SerdeContainerAttrs::from(ast.attrs.iter());
+
+    set_dummy(quote! {
+        impl ::conflate::MergeTrusted for #name {
+            fn merge_trusted_with(
+                &mut self,
+                other: Self,
+                trust: ::conflate::trust::Trust,
+                path: &::conflate::FieldPath<'_>,
+                rejected: &mut dyn FnMut(&::conflate::FieldPath<'_>),
+            ) {
+                unimplemented!()
+            }
+        }
+    });
+
+    if let syn::Data::Struct(syn::DataStruct { ref fields, .. }) = ast.data {
+        let fields: Vec<_> = fields.iter().enumerate().map(Field::from).collect();
+        let aliases = gen_deprecated_aliases(&fields, |_, _| None);
+        let conditions = gen_conditions(&fields, |f| f.value_of(&quote!(other)));
+        let assignments = fields
+            .iter()
+            .filter(|f| !f.attrs.skip)
+            .map(|f| f.if_condition(gen_assignment(f, &default_strategy, &container)));
+
+        quote! {
+            impl ::conflate::MergeTrusted for #name {
+                fn merge_trusted_with(
+                    &mut self,
+                    other: Self,
+                    trust: ::conflate::trust::Trust,
+                    path: &::conflate::FieldPath<'_>,
+                    rejected: &mut dyn FnMut(&::conflate::FieldPath<'_>),
+                ) {
+                    #aliases
+                    #conditions
+                    #( #assignments )*
+                }
+            }
+        }
+    } else {
+        abort_call_site!("conflate::MergeTrusted can only be derived for structs")
+    }
+}
+
+fn gen_assignment(
+    field: &Field,
+    default_strategy: &FieldAttrs,
+    container: &SerdeContainerAttrs,
+) -> TokenStream {
+    use syn::spanned::Spanned;
+
+    let name = &field.name;
+    let field_path = match field.key(container) {
+        Some(key) => quote!(&path.join(#key)),
+        None => quote!(path),
+    };
+    let strategy = field.strategy(default_strategy);
+
+    if let Some(min_trust) = &field.attrs.min_trust {
+        let merge = match &strategy {
+            Some(strategy) if field.attrs.lockable => {
+                let merge = field.call_strategy(strategy, quote!(left), quote!(right));
+                quote!(|left, right| #merge)
+            }
+            Some(strategy) => quote!(#strategy),
+            None => quote!(::conflate::Merge::merge),
+        };
+        quote_spanned! {field.span=>
+            if ::conflate::trust::merge_with(
+                &mut self.#name,
+                other.#name,
+                trust,
+                ::conflate::trust::Trust::#min_trust,
+                #merge,
+            ) {
+                rejected(#field_path);
+            }
+        }
+    } else if let Some(strategy) = strategy {
+        let merge = field.call_strategy(&strategy, quote!(&mut self.#name), quote!(other.#name));
+        quote_spanned!(strategy.span()=> #merge;)
+    } else {
+        quote_spanned! {field.span=>
+            ::conflate::MergeTrusted::merge_trusted_with(
+                &mut self.#name,
+                other.#name,
+                trust,
+                #field_path,
+                rejected,
+            );
+        }
+    }
+}
@@ -0,0 +1,108 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Implementation of the `TryMerge` derive macro.
+
+use proc_macro2::TokenStream;
+use proc_macro_error2::{abort, abort_call_site, dummy::set_dummy};
+use quote::{quote, quote_spanned};
+
+use crate::serde_attr::SerdeContainerAttrs;
+use crate::{gen_conditions, gen_deprecated_aliases, Field, FieldAttrs};
+
+pub fn impl_try_merge(ast: &syn::DeriveInput) -> TokenStream {
+    let name = &ast.ident;
+    let default_strategy = FieldAttrs::from(ast.attrs.iter());
+    let container = SerdeContainerAttrs::from(ast.attrs.iter());
+
+    set_dummy(quote! {
+        impl ::conflate::TryMerge for #name {
+            fn try_merge_with(
+                &mut self,
+                other: Self,
+                path: &::conflate::FieldPath<'_>,
+                overridden: &mut dyn FnMut(&::conflate::FieldPath<'_>),
+            ) {
+                unimplemented!()
+            }
+        }
+    });
+
+    if let syn::Data::Struct(syn::DataStruct { ref fields, .. }) = ast.data {
+        let fields: Vec<_> = fields.iter().enumerate().map(Field::from).collect();
+        let aliases = gen_deprecated_aliases(&fields, |_, _| None);
+        let conditions = gen_conditions(&fields, |f| f.value_of(&quote!(other)));
+        let assignments = fields
+            .iter()
+            .filter(|f| !f.attrs.skip)
+            .map(|f| f.if_condition(gen_assignment(f, &default_strategy, &container)));
+
+        quote! {
+            impl ::conflate::TryMerge for #name {
+                fn try_merge_with(
+                    &mut self,
+                    other: Self,
+                    path: &::conflate::FieldPath<'_>,
+                    overridden: &mut dyn FnMut(&::conflate::FieldPath<'_>),
+                ) {
+                    #aliases
+                    #conditions
+                    #( #assignments )*
+                }
+            }
+        }
+    } else {
+        abort_call_site!("conflate::TryMerge can only be derived for structs")
+    }
+}
+
+fn gen_assignment(
+    field: &Field,
+    default_strategy: &FieldAttrs,
+    container: &SerdeContainerAttrs,
+) -> TokenStream {
+    use syn::spanned::Spanned;
+
+    let name = &field.name;
+    let field_path = match field.key(container) {
+        Some(key) => quote!(&path.join(#key)),
+        None => quote!(path),
+    };
+    let strategy = field.strategy(default_strategy);
+
+    if let Some(strategy_try) = &field.attrs.strategy_try {
+        if field.attrs.lockable {
+            abort!(
+                strategy_try,
+                "strategy_try cannot be used for lockable fields"
+            );
+        }
+        quote_spanned! {strategy_try.span()=>
+            if #strategy_try(&mut self.#name, other.#name).is_err() {
+                overridden(#field_path);
+            }
+        }
+    } else if field.attrs.lockable {
+        let strategy = match &strategy {
+            Some(strategy) => quote!(#strategy),
+            None => quote!(::conflate::Merge::merge),
+        };
+        quote_spanned! {field.span=>
+            if ::conflate::locked::try_merge_with(&mut self.#name, other.#name, #strategy) {
+                overridden(#field_path);
+            }
+        }
+    } else if let Some(strategy) = strategy {
+        let merge = field.call_strategy(&strategy, quote!(&mut self.#name), quote!(other.#name));
+        quote_spanned!(strategy.span()=> #merge;)
+    } else {
+        quote_spanned! {field.span=>
+            ::conflate::TryMerge::try_merge_with(
+                &mut self.#name,
+                other.#name,
+                #field_path,
+                overridden,
+            );
+        }
+    }
+}
@@ -0,0 +1,63 @@
+// SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Generation of the `MergeVisit` implementation for the `Merge` and `Interpolate` derive macros.
+
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+
+use crate::serde_attr::SerdeContainerAttrs;
+use crate::Field;
+
+pub fn impl_merge_visit(
+    name: &syn::Ident,
+    fields: &[Field],
+    container: &SerdeContainerAttrs,
+) -> TokenStream {
+    let fields: Vec<_> = fields
+        .iter()
+        .filter(|f| !f.attrs.skip && !f.attrs.skip_interpolate)
+        .collect();
+    let visits = fields
+        .iter()
+        .map(|f| gen_visit(f, container, quote!(visit_with), quote!(&self)));
+    let visits_mut = fields
+        .iter()
+        .map(|f| gen_visit(f, container, quote!(visit_mut_with), quote!(&mut self)));
+
+    quote! {
+        impl ::conflate::MergeVisit for #name {
+            fn visit_with(
+                &self,
+                path: &::conflate::FieldPath<'_>,
+                visitor: &mut dyn ::conflate::visit::Visitor,
+            ) {
+                #( #visits )*
+            }
+
+            fn visit_mut_with(
+                &mut self,
+                path: &::conflate::FieldPath<'_>,
+                visitor: &mut dyn ::conflate::visit::VisitorMut,
+            ) {
+                #( #visits_mut )*
+            }
+        }
+    }
+}
+
+fn gen_visit(
+    field: &Field,
+    container: &SerdeContainerAttrs,
+    method: TokenStream,
+    receiver: TokenStream,
+) -> TokenStream {
+    let name = &field.name;
+    let field_path = match field.key(container) {
+        Some(key) => quote!(&path.join(#key)),
+        None => quote!(path),
+    };
+    quote_spanned! {field.span=>
+        ::conflate::MergeVisit::#method(#receiver.#name, #field_path, visitor);
+    }
+}
@@ -1,22 +1,32 @@
 // SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
 // SPDX-License-Identifier: CC0-1.0
 
-//! This example demonstrates how to merge configuration from different sources using the `conflate`
-//! crate.  The example defines a struct `Args` with three fields: `debug`, `input` and `output`.  The
-//! `debug` field is overwritten if it is `false`, the `input` and `output` fields are overwritten if they
-//! are `None`.  The example then merges a default configuration into a user configuration and asserts that
-//! the merged configuration is correct.
+//! This example demonstrates how to merge configuration from different sources using the
+//! `conflate` crate.  The example defines a struct `Args` with three fields: `debug`, `input` and
+//! `output`.  The `debug` field is a `TriState` that is set by the `--debug` and `--no-debug` flags
+//! and overwritten if it is unset, the `input` and `output` fields are overwritten if they are
+//! `None`.  The example then merges the environment and the configuration file into the
+//! command-line arguments.  The configuration file is deserialized directly on top of the merged
+//! arguments, so only the keys that are present in the file are merged.
 
 use clap::Parser;
-use conflate::Merge;
+use conflate::bool::{Flag, TriStateArgs};
+use conflate::{DeserializeMerge, Merge};
 use serde_derive::Deserialize;
 
-#[derive(Debug, Default, Deserialize, Merge, Parser)]
+struct DebugFlag;
+
+impl Flag for DebugFlag {
+    const LONG: &'static str = "debug";
+    const NO_LONG: &'static str = "no-debug";
+    const SHORT: Option<char> = Some('d');
+}
+
+#[derive(Debug, Default, Deserialize, DeserializeMerge, Merge, Parser)]
 #[serde(default)]
 struct Args {
-    #[arg(short, long)]
-    #[merge(strategy = conflate::bool::overwrite_false)]
-    debug: bool,
+    #[command(flatten)]
+    debug: TriStateArgs<DebugFlag>,
 
     #[merge(strategy = conflate::option::overwrite_none)]
     input: Option<String>,
@@ -25,13 +35,12 @@ struct Args {
     output: Option<String>,
 }
 
-fn get_config() -> Option<Args> {
+fn merge_config(args: &mut Args) {
     let path: &std::path::Path = "args.toml".as_ref();
     if path.is_file() {
         let s = std::fs::read_to_string(path).expect("Could not read configuration file");
-        Some(toml::from_str(&s).expect("Could not parse configuration"))
-    } else {
-        None
+        args.deserialize_merge(toml::Deserializer::new(&s))
+            .expect("Could not parse configuration");
     }
 }
 
@@ -44,8 +53,6 @@ fn get_env() -> Args {
 fn main() {
     let mut args = Args::parse();
     args.merge(get_env());
-    if let Some(config) = get_config() {
-        args.merge(config);
-    }
-    println!("{args:?}");
+    merge_config(&mut args);
+    println!("{args:?}, debug: {}", args.debug.resolve(false));
 }